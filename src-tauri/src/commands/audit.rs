@@ -0,0 +1,117 @@
+use crate::commands::export::{self, csv_field, opt_to_string, ExportResult};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{AuditEntry, AuditFilter, NewAuditEntry};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::io::Write;
+use tauri::State;
+
+const EXPORT_FORMATS: [&str; 2] = ["csv", "jsonl"];
+
+const CSV_HEADER: &str = "id,created_at,actor,action,target_type,target_id,target_name,before,after";
+
+const DEFAULT_LIMIT: i64 = 200;
+
+/// The OS account Conduit is running as.
+pub(crate) fn current_actor() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+pub(crate) fn record(conn: &Connection, entry: &NewAuditEntry) -> Result<(), String> {
+    queries::insert_audit_entry(conn, &current_actor(), entry).map_err(|e| e.to_string())
+}
+
+/// Record `action` on a target, with snapshots of it before and after. The
+/// change has already happened, so a failed write doesn't fail the command.
+pub(crate) fn log<T: Serialize>(
+    conn: &Connection,
+    action: &str,
+    target_type: &str,
+    target_id: Option<i64>,
+    target_name: Option<&str>,
+    before: Option<&T>,
+    after: Option<&T>,
+) {
+    let _ = record(
+        conn,
+        &NewAuditEntry {
+            action: action.to_string(),
+            target_type: target_type.to_string(),
+            target_id,
+            target_name: target_name.map(str::to_string),
+            before: before.and_then(|v| serde_json::to_value(v).ok()),
+            after: after.and_then(|v| serde_json::to_value(v).ok()),
+        },
+    );
+}
+
+fn write_csv_row(out: &mut impl Write, entry: &AuditEntry) -> std::io::Result<()> {
+    let fields = [
+        entry.id.to_string(),
+        entry.created_at.clone(),
+        csv_field(&entry.actor),
+        csv_field(&entry.action),
+        csv_field(&entry.target_type),
+        opt_to_string(entry.target_id),
+        csv_field(entry.target_name.as_deref().unwrap_or("")),
+        csv_field(&opt_to_string(entry.before.as_ref())),
+        csv_field(&opt_to_string(entry.after.as_ref())),
+    ];
+    writeln!(out, "{}", fields.join(","))
+}
+
+fn write_export(db: &Database, out: &mut impl Write, format: &str, filter: &AuditFilter) -> ExportResult<i64> {
+    let page = |conn: &rusqlite::Connection, last: Option<&AuditEntry>, limit| {
+        queries::get_audit_export_page(conn, filter, last.map(|e| e.id), limit)
+    };
+    let count = match format {
+        "csv" => {
+            writeln!(out, "{}", CSV_HEADER)?;
+            export::for_each_page(db, page, |entry| Ok(write_csv_row(out, entry)?))?
+        }
+        "jsonl" => export::for_each_page(db, page, |entry| {
+            serde_json::to_writer(&mut *out, entry)?;
+            Ok(out.write_all(b"\n")?)
+        })?,
+        other => unreachable!("export format {} not validated", other),
+    };
+    out.flush()?;
+    Ok(count)
+}
+
+/// Audit entries matching `filter`, newest first.
+#[tauri::command]
+pub fn get_audit_log(
+    db: State<'_, Database>,
+    filter: Option<AuditFilter>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_audit_log(
+        &conn,
+        &filter.unwrap_or_default(),
+        limit.unwrap_or(DEFAULT_LIMIT).max(1),
+        offset.unwrap_or(0).max(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Write audit entries matching `filter` to `path` as "csv" or "jsonl",
+/// oldest first. Returns the number of entries exported.
+#[tauri::command]
+pub fn export_audit_log(
+    db: State<'_, Database>,
+    path: String,
+    format: String,
+    filter: Option<AuditFilter>,
+) -> Result<i64, String> {
+    if !EXPORT_FORMATS.contains(&format.as_str()) {
+        return Err(format!("Unknown export format: {}", format));
+    }
+    let filter = filter.unwrap_or_default();
+    export::export_to_file(&path, |out| write_export(&db, out, &format, &filter))
+}
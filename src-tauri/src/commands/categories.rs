@@ -1,3 +1,4 @@
+use crate::commands::audit;
 use crate::db::queries;
 use crate::db::Database;
 use crate::models::Category;
@@ -12,23 +13,39 @@ pub fn get_categories(db: State<'_, Database>) -> Result<Vec<Category>, String>
 #[tauri::command]
 pub fn add_category(db: State<'_, Database>, name: String, color: String) -> Result<Category, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::insert_category(&conn, &name, &color).map_err(|e| e.to_string())
+    let created = queries::insert_category(&conn, &name, &color).map_err(|e| e.to_string())?;
+    audit::log(&conn, "category.add", "category", Some(created.id), Some(&created.name), None, Some(&created));
+    Ok(created)
 }
 
 #[tauri::command]
 pub fn update_category(db: State<'_, Database>, id: i64, name: String, color: String) -> Result<Category, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::update_category(&conn, id, &name, &color).map_err(|e| e.to_string())
+    let before = queries::get_category_by_id(&conn, id).map_err(|e| e.to_string())?;
+    let updated = queries::update_category(&conn, id, &name, &color).map_err(|e| e.to_string())?;
+    audit::log(&conn, "category.update", "category", Some(id), Some(&updated.name), before.as_ref(), Some(&updated));
+    Ok(updated)
 }
 
 #[tauri::command]
 pub fn delete_category(db: State<'_, Database>, id: i64) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::delete_category(&conn, id).map_err(|e| e.to_string())
+    let before = queries::get_category_by_id(&conn, id).map_err(|e| e.to_string())?;
+    queries::delete_category(&conn, id).map_err(|e| e.to_string())?;
+    let name = before.as_ref().map(|c| c.name.as_str());
+    audit::log(&conn, "category.delete", "category", Some(id), name, before.as_ref(), None);
+    Ok(())
 }
 
 #[tauri::command]
 pub fn reorder_categories(db: State<'_, Database>, ids: Vec<i64>) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::reorder_categories(&conn, &ids).map_err(|e| e.to_string())
+    let before: Vec<i64> = queries::get_all_categories(&conn)
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|c| c.id)
+        .collect();
+    queries::reorder_categories(&conn, &ids).map_err(|e| e.to_string())?;
+    audit::log(&conn, "category.reorder", "category", None, None, Some(&before), Some(&ids));
+    Ok(())
 }
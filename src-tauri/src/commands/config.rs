@@ -0,0 +1,640 @@
+use crate::commands::{audit, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{ConfigError, ConfigStatus, NewSchedule, NewScript, Schedule, Script, ScriptParam, UpdateScript};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::Connection;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use toml::Spanned;
+
+/// Looked up in the config directory in this order.
+const CONFIG_FILE_NAMES: [&str; 3] = ["conduit.toml", "conduit.yaml", "conduit.yml"];
+
+/// Editors often write a file in several steps; wait for them to settle.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+const DEFAULT_COLOR: &str = "#00d4aa";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    categories: Vec<ConfigCategory>,
+    #[serde(default)]
+    scripts: Vec<ConfigScript>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigCategory {
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigScript {
+    name: String,
+    /// Absolute, or relative to the directory holding the config file.
+    path: String,
+    description: Option<String>,
+    category: Option<String>,
+    color: Option<String>,
+    #[serde(default)]
+    run_as_admin: bool,
+    #[serde(default)]
+    params: Vec<ConfigParam>,
+    #[serde(default)]
+    schedules: Vec<ConfigSchedule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigParam {
+    name: String,
+    description: Option<String>,
+    default: Option<String>,
+    #[serde(default)]
+    required: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigSchedule {
+    #[serde(rename = "type")]
+    schedule_type: String,
+    time: Option<String>,
+    weekday: Option<i32>,
+    interval_seconds: Option<i64>,
+    #[serde(default = "default_true")]
+    enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Where each entry of a TOML config starts, so semantic errors point at the
+/// entry they concern. serde_yaml has no spans; YAML errors carry no line.
+#[derive(Deserialize, Default)]
+struct ConfigSpans {
+    #[serde(default)]
+    categories: Vec<Spanned<IgnoredAny>>,
+    #[serde(default)]
+    scripts: Vec<Spanned<ScriptSpans>>,
+}
+
+#[derive(Deserialize)]
+struct ScriptSpans {
+    #[serde(default)]
+    params: Vec<Spanned<IgnoredAny>>,
+    #[serde(default)]
+    schedules: Vec<Spanned<IgnoredAny>>,
+}
+
+/// Holds the directory watcher and the outcome of the last reconcile.
+pub struct ConfigState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    status: Mutex<ConfigStatus>,
+}
+
+impl ConfigState {
+    pub fn new() -> Self {
+        ConfigState {
+            watcher: Mutex::new(None),
+            status: Mutex::new(ConfigStatus::default()),
+        }
+    }
+}
+
+/// Config-managed scripts may only change through their config file.
+pub(crate) fn ensure_editable(script: &Script) -> Result<(), String> {
+    if script.config_managed {
+        return Err(format!(
+            "\"{}\" is managed by the Conduit config file; edit the file instead",
+            script.name
+        ));
+    }
+    Ok(())
+}
+
+fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES.iter().map(|name| dir.join(name)).find(|p| p.is_file())
+}
+
+/// 1-based line number of a byte offset.
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+fn parse_config(path: &Path, text: &str) -> Result<ConfigFile, ConfigError> {
+    let file = path.to_string_lossy().to_string();
+    let is_toml = path.extension().is_some_and(|e| e == "toml");
+    if is_toml {
+        toml::from_str(text).map_err(|e| ConfigError {
+            file,
+            line: e.span().map(|span| line_at(text, span.start)),
+            message: e.message().to_string(),
+        })
+    } else {
+        serde_yaml::from_str(text).map_err(|e| ConfigError {
+            file,
+            line: e.location().map(|l| l.line()),
+            message: e.to_string(),
+        })
+    }
+}
+
+fn parse_spans(path: &Path, text: &str) -> ConfigSpans {
+    if path.extension().is_some_and(|e| e == "toml") {
+        toml::from_str(text).unwrap_or_default()
+    } else {
+        ConfigSpans::default()
+    }
+}
+
+fn resolve_path(dir: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        dir.join(path)
+    }
+}
+
+fn is_valid_time(time: &str) -> bool {
+    chrono::NaiveTime::parse_from_str(time, "%H:%M").is_ok()
+}
+
+fn validate_schedule(schedule: &ConfigSchedule) -> Result<(), String> {
+    match schedule.schedule_type.as_str() {
+        "daily" => match schedule.time.as_deref() {
+            Some(time) if is_valid_time(time) => Ok(()),
+            _ => Err("daily schedules need a time in HH:MM".to_string()),
+        },
+        "weekly" => {
+            if !schedule.time.as_deref().is_some_and(is_valid_time) {
+                return Err("weekly schedules need a time in HH:MM".to_string());
+            }
+            match schedule.weekday {
+                Some(0..=6) => Ok(()),
+                _ => Err("weekly schedules need a weekday from 0 (Sunday) to 6".to_string()),
+            }
+        }
+        "interval" => match schedule.interval_seconds {
+            Some(seconds) if seconds > 0 => Ok(()),
+            _ => Err("interval schedules need a positive interval_seconds".to_string()),
+        },
+        other => Err(format!("unknown schedule type \"{}\"", other)),
+    }
+}
+
+fn validate_config(config: &ConfigFile, path: &Path, text: &str, dir: &Path) -> Vec<ConfigError> {
+    let file = path.to_string_lossy().to_string();
+    let spans = parse_spans(path, text);
+    let line = |entry: Option<&Spanned<IgnoredAny>>| entry.map(|e| line_at(text, e.span().start));
+    let mut errors = Vec::new();
+    let mut error = |line: Option<usize>, message: String| {
+        errors.push(ConfigError {
+            file: file.clone(),
+            line,
+            message,
+        });
+    };
+
+    let mut category_names = HashSet::new();
+    for (index, category) in config.categories.iter().enumerate() {
+        let at = line(spans.categories.get(index));
+        if category.name.trim().is_empty() {
+            error(at, "category name must not be empty".to_string());
+        } else if !category_names.insert(category.name.as_str()) {
+            error(at, format!("category \"{}\" is declared twice", category.name));
+        }
+    }
+
+    let mut paths = HashSet::new();
+    for (index, script) in config.scripts.iter().enumerate() {
+        let script_spans = spans.scripts.get(index);
+        let at = script_spans.map(|s| line_at(text, s.span().start));
+        if script.name.trim().is_empty() {
+            error(at, "script name must not be empty".to_string());
+        }
+        let resolved = resolve_path(dir, &script.path);
+        if !resolved.is_file() {
+            error(at, format!("script file {} does not exist", resolved.display()));
+        }
+        if !paths.insert(resolved) {
+            error(at, format!("script path {} is declared twice", script.path));
+        }
+
+        let mut param_names = HashSet::new();
+        for (index, param) in script.params.iter().enumerate() {
+            if param.name.trim().is_empty() || !param_names.insert(param.name.as_str()) {
+                error(
+                    line(script_spans.and_then(|s| s.get_ref().params.get(index))).or(at),
+                    format!("script \"{}\" has an empty or duplicate parameter name", script.name),
+                );
+            }
+        }
+        for (index, schedule) in script.schedules.iter().enumerate() {
+            if let Err(message) = validate_schedule(schedule) {
+                error(
+                    line(script_spans.and_then(|s| s.get_ref().schedules.get(index))).or(at),
+                    format!("script \"{}\": {}", script.name, message),
+                );
+            }
+        }
+    }
+    errors
+}
+
+fn same_schedule(existing: &Schedule, declared: &ConfigSchedule) -> bool {
+    existing.schedule_type == declared.schedule_type
+        && existing.time == declared.time
+        && existing.weekday == declared.weekday
+        && existing.interval_seconds == declared.interval_seconds
+}
+
+/// A change to an OS-level task. These are applied once the config's
+/// database side has committed, so a failed apply can't leave tasks behind
+/// for rows that were rolled back.
+enum ScheduleChange {
+    /// Rewrite the tasks of a script, by id and name, whose launcher changed.
+    Reinstall(i64, String),
+    Install(NewSchedule, bool),
+    Toggle(Schedule, bool),
+    Remove(Schedule),
+}
+
+/// The changes that bring a script's schedules in line with the declared
+/// ones.
+fn reconcile_schedules(
+    conn: &Connection,
+    script_id: i64,
+    declared: &[ConfigSchedule],
+    changes: &mut Vec<ScheduleChange>,
+) -> Result<(), String> {
+    let mut existing = queries::get_schedules_for_script(conn, script_id).map_err(|e| e.to_string())?;
+    for schedule in declared {
+        match existing.iter().position(|s| same_schedule(s, schedule)) {
+            Some(index) => {
+                let current = existing.swap_remove(index);
+                if current.enabled != schedule.enabled {
+                    changes.push(ScheduleChange::Toggle(current, schedule.enabled));
+                }
+            }
+            None => {
+                let new_schedule = NewSchedule {
+                    script_id,
+                    schedule_type: schedule.schedule_type.clone(),
+                    time: schedule.time.clone(),
+                    weekday: schedule.weekday,
+                    interval_seconds: schedule.interval_seconds,
+                };
+                changes.push(ScheduleChange::Install(new_schedule, schedule.enabled));
+            }
+        }
+    }
+    changes.extend(existing.into_iter().map(ScheduleChange::Remove));
+    Ok(())
+}
+
+/// Go through the platform backend for each change; failures become
+/// warnings rather than undoing the committed config. Protected scripts
+/// don't get new schedules, since the config can't carry the confirmation
+/// phrase.
+fn apply_schedule_changes(conn: &Connection, changes: Vec<ScheduleChange>, warnings: &mut Vec<String>) {
+    for change in changes {
+        match change {
+            ScheduleChange::Reinstall(script_id, name) => {
+                if let Err(e) = scheduler::reinstall_script_schedules(conn, script_id) {
+                    warnings.push(format!("Failed to update the schedules of \"{}\": {}", name, e));
+                }
+            }
+            ScheduleChange::Install(new_schedule, enabled) => {
+                match queries::get_script_protection(conn, new_schedule.script_id) {
+                    Ok(None) => {}
+                    Ok(Some(_)) => {
+                        warnings.push(format!(
+                            "Skipped the {} schedule: protected scripts need their confirmation phrase to be scheduled",
+                            new_schedule.schedule_type
+                        ));
+                        continue;
+                    }
+                    Err(e) => {
+                        warnings.push(format!("Failed to create {} schedule: {}", new_schedule.schedule_type, e));
+                        continue;
+                    }
+                }
+                match scheduler::install_schedule(conn, &new_schedule, enabled) {
+                    Ok(created) => scheduler::audit_schedule(conn, "schedule.create", &created, None, Some(&created)),
+                    Err(e) => warnings.push(format!("Failed to create {} schedule: {}", new_schedule.schedule_type, e)),
+                }
+            }
+            ScheduleChange::Toggle(schedule, enabled) => match scheduler::set_schedule_enabled(conn, &schedule, enabled) {
+                Ok(()) => scheduler::audit_schedule_toggle(conn, &schedule, enabled),
+                Err(e) => warnings.push(format!("Failed to update schedule {}: {}", schedule.plist_label, e)),
+            },
+            ScheduleChange::Remove(schedule) => match scheduler::remove_schedule(conn, &schedule) {
+                Ok(()) => scheduler::audit_schedule(conn, "schedule.delete", &schedule, Some(&schedule), None),
+                Err(e) => warnings.push(format!("Failed to remove schedule {}: {}", schedule.plist_label, e)),
+            },
+        }
+    }
+}
+
+/// The database side runs in one transaction, so a failure partway leaves
+/// the library as it was; OS-level tasks only change after it commits.
+fn apply_config(conn: &Connection, config: ConfigFile, dir: &Path, warnings: &mut Vec<String>) -> Result<i64, String> {
+    let outer = conn;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let conn = &*tx;
+    let mut changes = Vec::new();
+    let mut categories: HashMap<String, (i64, String)> = queries::get_all_categories(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| (c.name, (c.id, c.color)))
+        .collect();
+    let declared_colors: HashMap<&str, &str> = config
+        .categories
+        .iter()
+        .filter_map(|c| c.color.as_deref().map(|color| (c.name.as_str(), color)))
+        .collect();
+    let referenced = config
+        .categories
+        .iter()
+        .map(|c| c.name.clone())
+        .chain(config.scripts.iter().filter_map(|s| s.category.clone()));
+    for name in referenced {
+        let color = declared_colors.get(name.as_str()).copied();
+        match categories.get(&name) {
+            Some((id, current)) => {
+                if let Some(color) = color.filter(|c| c != current) {
+                    queries::update_category(conn, *id, &name, color).map_err(|e| e.to_string())?;
+                    categories.insert(name, (*id, color.to_string()));
+                }
+            }
+            None => {
+                let created = queries::insert_category(conn, &name, color.unwrap_or(DEFAULT_COLOR))
+                    .map_err(|e| e.to_string())?;
+                audit::log(conn, "category.add", "category", Some(created.id), Some(&created.name), None, Some(&created));
+                categories.insert(name, (created.id, created.color));
+            }
+        }
+    }
+
+    let scripts = queries::get_all_scripts(conn).map_err(|e| e.to_string())?;
+    let by_path: HashMap<&str, &Script> = scripts.iter().map(|s| (s.path.as_str(), s)).collect();
+    let mut declared_ids = HashSet::new();
+
+    for declared in config.scripts {
+        let path = resolve_path(dir, &declared.path).to_string_lossy().to_string();
+        let category_id = declared
+            .category
+            .as_ref()
+            .and_then(|name| categories.get(name))
+            .map(|(id, _)| *id)
+            .unwrap_or(1);
+        let color = declared.color.unwrap_or_else(|| DEFAULT_COLOR.to_string());
+
+        let script_id = match by_path.get(path.as_str()) {
+            Some(existing) => {
+                queries::update_script(
+                    conn,
+                    existing.id,
+                    &UpdateScript {
+                        name: Some(declared.name.clone()),
+                        path: None,
+                        description: Some(declared.description.unwrap_or_default()),
+                        category_id: Some(category_id),
+                        color: Some(color),
+                        run_as_admin: Some(declared.run_as_admin),
+                        timeout_seconds: None,
+                    },
+                )
+                .map_err(|e| e.to_string())?;
+                // Scheduled admin runs go through the elevating launcher on Unix
+                if cfg!(unix) && existing.run_as_admin != declared.run_as_admin {
+                    changes.push(ScheduleChange::Reinstall(existing.id, declared.name.clone()));
+                }
+                existing.id
+            }
+            None => {
+                let created = queries::insert_script(
+                    conn,
+                    &NewScript {
+                        name: declared.name.clone(),
+                        path,
+                        description: declared.description,
+                        category_id,
+                        color,
+                        run_as_admin: declared.run_as_admin,
+                    },
+                )
+                .map_err(|e| e.to_string())?;
+                audit::log(conn, "script.add", "script", Some(created.id), Some(&created.name), None, Some(&created));
+                created.id
+            }
+        };
+        queries::set_script_config_managed(conn, script_id, true).map_err(|e| e.to_string())?;
+        declared_ids.insert(script_id);
+
+        let params: Vec<ScriptParam> = declared
+            .params
+            .into_iter()
+            .map(|p| ScriptParam {
+                id: 0,
+                script_id,
+                name: p.name,
+                description: p.description,
+                default_value: p.default,
+                required: p.required,
+            })
+            .collect();
+        queries::replace_script_params(conn, script_id, &params).map_err(|e| e.to_string())?;
+
+        reconcile_schedules(conn, script_id, &declared.schedules, &mut changes)?;
+    }
+
+    // Scripts dropped from the config are removed; their schedule rows go
+    // with them and the OS-level tasks are removed after the commit
+    for script in scripts.iter().filter(|s| s.config_managed && !declared_ids.contains(&s.id)) {
+        let schedules = queries::get_schedules_for_script(conn, script.id).map_err(|e| e.to_string())?;
+        changes.extend(schedules.into_iter().map(ScheduleChange::Remove));
+        queries::delete_script(conn, script.id).map_err(|e| e.to_string())?;
+        audit::log(conn, "script.delete", "script", Some(script.id), Some(&script.name), Some(script), None);
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    apply_schedule_changes(outer, changes, warnings);
+    Ok(declared_ids.len() as i64)
+}
+
+/// Reconcile the database with the config file in `dir`. Invalid configs
+/// leave the database untouched and are reported through `errors`.
+fn reconcile(conn: &Connection, dir: &Path) -> ConfigStatus {
+    let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut status = ConfigStatus {
+        loaded_at: Some(now),
+        ..ConfigStatus::default()
+    };
+
+    let Some(path) = find_config_file(dir) else {
+        // Without a config file nothing is managed any more; keep the
+        // scripts rather than deleting a whole library on a stray rename
+        if let Err(e) = queries::release_config_managed_scripts(conn) {
+            status.warnings.push(e.to_string());
+        }
+        return status;
+    };
+    status.path = Some(path.to_string_lossy().to_string());
+
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            status.errors.push(ConfigError {
+                file: path.to_string_lossy().to_string(),
+                line: None,
+                message: e.to_string(),
+            });
+            return status;
+        }
+    };
+    let config = match parse_config(&path, &text) {
+        Ok(config) => config,
+        Err(e) => {
+            status.errors.push(e);
+            return status;
+        }
+    };
+    status.errors = validate_config(&config, &path, &text, dir);
+    if !status.errors.is_empty() {
+        return status;
+    }
+
+    match apply_config(conn, config, dir, &mut status.warnings) {
+        Ok(count) => status.managed_scripts = count,
+        Err(e) => status.errors.push(ConfigError {
+            file: path.to_string_lossy().to_string(),
+            line: None,
+            message: e,
+        }),
+    }
+    status
+}
+
+/// Reconcile against the configured directory, remember the outcome and
+/// tell the frontend to refresh.
+fn reload(app: &AppHandle) -> Result<ConfigStatus, String> {
+    let db = app.state::<Database>();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let dir = queries::get_settings(&conn).map_err(|e| e.to_string())?.config_dir;
+    let status = if dir.is_empty() {
+        ConfigStatus::default()
+    } else {
+        reconcile(&conn, Path::new(&dir))
+    };
+    drop(conn);
+
+    let state = app.state::<ConfigState>();
+    *state.status.lock().map_err(|e| e.to_string())? = status.clone();
+    let _ = app.emit("config-reconciled", status.clone());
+    Ok(status)
+}
+
+/// Watch `dir` for changes to the config file, replacing any previous
+/// watcher. An empty `dir` just stops watching.
+fn watch(app: &AppHandle, dir: &str) -> Result<(), String> {
+    let state = app.state::<ConfigState>();
+    let mut slot = state.watcher.lock().map_err(|e| e.to_string())?;
+    // Dropping the old watcher closes its channel and ends its thread
+    *slot = None;
+    if dir.is_empty() {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<()>();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        let touches_config = event.paths.iter().any(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| CONFIG_FILE_NAMES.contains(&n))
+        });
+        if touches_config {
+            let _ = tx.send(());
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(Path::new(dir), RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir, e))?;
+    *slot = Some(watcher);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            std::thread::sleep(RELOAD_DEBOUNCE);
+            while rx.try_recv().is_ok() {}
+            let _ = reload(&app);
+        }
+    });
+    Ok(())
+}
+
+/// Reconcile once at startup and keep watching the config directory.
+pub(crate) fn start_config_watcher(app: AppHandle) {
+    std::thread::spawn(move || {
+        let dir = {
+            let db = app.state::<Database>();
+            let Ok(conn) = db.conn.lock() else {
+                return;
+            };
+            queries::get_settings(&conn).map(|s| s.config_dir).unwrap_or_default()
+        };
+        if dir.is_empty() {
+            return;
+        }
+        let _ = reload(&app);
+        let _ = watch(&app, &dir);
+    });
+}
+
+#[tauri::command]
+pub fn get_config_status(state: State<'_, ConfigState>) -> Result<ConfigStatus, String> {
+    Ok(state.status.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Set (or with `None`, clear) the directory holding conduit.toml/yaml, then
+/// reconcile and start watching it.
+#[tauri::command]
+pub fn set_config_dir(app: AppHandle, db: State<'_, Database>, dir: Option<String>) -> Result<ConfigStatus, String> {
+    let dir = dir.unwrap_or_default();
+    if !dir.is_empty() && !Path::new(&dir).is_dir() {
+        return Err(format!("{} is not a directory", dir));
+    }
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let before = queries::get_settings(&conn).map_err(|e| e.to_string())?.config_dir;
+        queries::set_config_dir(&conn, &dir).map_err(|e| e.to_string())?;
+        if dir.is_empty() {
+            queries::release_config_managed_scripts(&conn).map_err(|e| e.to_string())?;
+        }
+        audit::log(&conn, "config.dir_update", "settings", None, None, Some(&before), Some(&dir));
+    }
+    watch(&app, &dir)?;
+    reload(&app)
+}
+
+#[tauri::command]
+pub fn reload_config(app: AppHandle) -> Result<ConfigStatus, String> {
+    reload(&app)
+}
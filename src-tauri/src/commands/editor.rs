@@ -0,0 +1,204 @@
+use crate::commands::{audit, config, header, health, library};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewScript, NewTemplateScript, Script, ScriptTemplate};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// The in-app editor refuses files larger than this.
+const MAX_EDIT_BYTES: u64 = 1024 * 1024;
+
+const DEFAULT_COLOR: &str = "#00d4aa";
+
+struct Template {
+    id: &'static str,
+    label: &'static str,
+    extension: &'static str,
+    shebang: Option<&'static str>,
+    comment: &'static str,
+    body: &'static str,
+}
+
+const TEMPLATES: [Template; 4] = [
+    Template {
+        id: "bash",
+        label: "Bash",
+        extension: "sh",
+        shebang: Some("#!/usr/bin/env bash"),
+        comment: "#",
+        body: "set -euo pipefail\n\necho \"Hello from $0\"\n",
+    },
+    Template {
+        id: "python",
+        label: "Python",
+        extension: "py",
+        shebang: Some("#!/usr/bin/env python3"),
+        comment: "#",
+        body: "import sys\n\n\ndef main() -> int:\n    print(f\"Hello from {sys.argv[0]}\")\n    return 0\n\n\nif __name__ == \"__main__\":\n    sys.exit(main())\n",
+    },
+    Template {
+        id: "powershell",
+        label: "PowerShell",
+        extension: "ps1",
+        shebang: None,
+        comment: "#",
+        body: "$ErrorActionPreference = \"Stop\"\n\nWrite-Output \"Hello from $PSCommandPath\"\n",
+    },
+    Template {
+        id: "node",
+        label: "Node.js",
+        extension: "js",
+        shebang: Some("#!/usr/bin/env node"),
+        comment: "//",
+        body: "console.log(`Hello from ${process.argv[1]}`);\n",
+    },
+];
+
+/// Starter content: a header block Conduit reads back, then the template body.
+fn render_template(template: &Template, name: &str, description: Option<&str>) -> String {
+    let mut text = String::new();
+    if let Some(shebang) = template.shebang {
+        text.push_str(shebang);
+        text.push('\n');
+    }
+    text.push_str(&format!("{} @name {}\n", template.comment, name));
+    if let Some(description) = description.filter(|d| !d.trim().is_empty()) {
+        text.push_str(&format!("{} @description {}\n", template.comment, description.trim()));
+    }
+    text.push('\n');
+    text.push_str(template.body);
+    text
+}
+
+/// `Nightly DB backup!` -> `nightly-db-backup`
+fn slugify(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "script".to_string()
+    } else {
+        slug
+    }
+}
+
+fn available_path(dir: &Path, file_name: &str) -> PathBuf {
+    let mut candidate = dir.join(file_name);
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = dir.join(library::numbered_name(file_name, n));
+        n += 1;
+    }
+    candidate
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = std::fs::metadata(path)?.permissions();
+    // Execute wherever there is read access, like chmod +x
+    let mode = perms.mode();
+    perms.set_mode(mode | ((mode & 0o444) >> 2));
+    std::fs::set_permissions(path, perms)
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Whether the file lives in Conduit's own scripts folder.
+fn is_managed_file(path: &Path) -> bool {
+    path.starts_with(library::get_scripts_dir())
+}
+
+#[tauri::command]
+pub fn get_script_templates() -> Vec<ScriptTemplate> {
+    TEMPLATES
+        .iter()
+        .map(|t| ScriptTemplate {
+            id: t.id.to_string(),
+            label: t.label.to_string(),
+            extension: t.extension.to_string(),
+        })
+        .collect()
+}
+
+/// Write a new script from a template into ~/.conduit/scripts and add it to
+/// the library.
+#[tauri::command]
+pub fn create_script_from_template(db: State<'_, Database>, script: NewTemplateScript) -> Result<Script, String> {
+    let name = script.name.trim();
+    if name.is_empty() {
+        return Err("Script name must not be empty".to_string());
+    }
+    let template = TEMPLATES
+        .iter()
+        .find(|t| t.id == script.template)
+        .ok_or_else(|| format!("Unknown template: {}", script.template))?;
+
+    let dir = library::get_scripts_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = available_path(&dir, &format!("{}.{}", slugify(name), template.extension));
+    let content = render_template(template, name, script.description.as_deref());
+    crate::commands::write_atomic(&path, content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    set_executable(&path).map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let created = queries::insert_script(
+        &conn,
+        &NewScript {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            description: script.description.filter(|d| !d.trim().is_empty()),
+            category_id: script.category_id,
+            color: script.color.unwrap_or_else(|| DEFAULT_COLOR.to_string()),
+            run_as_admin: false,
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    let created = header::sync_header(&conn, &created, false)?.unwrap_or(created);
+    health::check_script(&conn, &created)?;
+    audit::log(&conn, "script.add", "script", Some(created.id), Some(&created.name), None, Some(&created));
+    Ok(created)
+}
+
+/// Current content of a script's file, for the in-app editor.
+#[tauri::command]
+pub fn read_script_file(db: State<'_, Database>, script_id: i64) -> Result<String, String> {
+    let path = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?.path
+    };
+    let meta = std::fs::metadata(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if meta.len() > MAX_EDIT_BYTES {
+        return Err(format!("{} is too large to edit here; use an external editor", path));
+    }
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    String::from_utf8(bytes).map_err(|_| format!("{} is not a text file", path))
+}
+
+/// Save edited content over the script's file, then bring the library entry
+/// up to date with it: header metadata, executable flag and file health.
+/// Files Conduit created are kept executable.
+#[tauri::command]
+pub fn save_script_file(db: State<'_, Database>, script_id: i64, content: String) -> Result<Script, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    config::ensure_editable(&script)?;
+    let before = queries::get_script_health(&conn, script_id).map_err(|e| e.to_string())?;
+    let path = Path::new(&script.path);
+    crate::commands::write_atomic(path, content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", script.path, e))?;
+    if is_managed_file(path) {
+        set_executable(path).map_err(|e| format!("Failed to make {} executable: {}", script.path, e))?;
+    }
+    let after = health::check_script(&conn, &script)?;
+    audit::log(&conn, "script.file_save", "script", Some(script_id), Some(&script.name), before.as_ref(), Some(&after));
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    Ok(header::sync_header(&conn, &script, false)?.unwrap_or(script))
+}
@@ -0,0 +1,485 @@
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::ElevationStatus;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use tauri::{AppHandle, State};
+#[cfg(unix)]
+use {
+    serde::Serialize,
+    std::io::{Read, Write},
+    std::os::unix::net::{UnixListener, UnixStream},
+    std::sync::atomic::AtomicBool,
+    std::sync::Arc,
+    std::time::Duration,
+    tauri::{Emitter, Manager},
+};
+
+/// Tells a Conduit process started by `sudo -A` to act as the askpass helper
+/// and forward the prompt to the app over this socket.
+pub const ASKPASS_SOCKET_ENV: &str = "CONDUIT_ASKPASS_SOCKET";
+
+/// How long a password prompt waits for an answer before sudo is told no.
+#[cfg(unix)]
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Deleted by the elevated shell, so a marker still present once the command
+/// exits means authentication never succeeded.
+#[cfg(unix)]
+const MARKER: &str = "pending";
+
+#[cfg(unix)]
+const MARK_AND_EXEC: &str = r#"rm -f -- "$1"; shift; exec "$@""#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Method {
+    /// `sudo -n`, when sudo doesn't ask for a password.
+    SudoPasswordless,
+    /// `sudo -A`, with Conduit as the askpass helper prompting in the app.
+    SudoAskpass,
+    /// pkexec, prompting through the desktop's polkit agent.
+    Pkexec,
+    /// Plain sudo prompting inside the run's terminal.
+    Terminal,
+    /// Windows UAC via `Start-Process -Verb RunAs`.
+    #[cfg(target_os = "windows")]
+    Uac,
+}
+
+impl Method {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Method::SudoPasswordless => "sudo-nopasswd",
+            Method::SudoAskpass => "sudo-askpass",
+            Method::Pkexec => "pkexec",
+            Method::Terminal => "terminal",
+            #[cfg(target_os = "windows")]
+            Method::Uac => "uac",
+        }
+    }
+}
+
+pub(crate) fn validate_method(method: &str) -> Result<(), String> {
+    if !["auto", "sudo", "pkexec", "terminal"].contains(&method) {
+        return Err(format!("Unknown elevation method: {}", method));
+    }
+    Ok(())
+}
+
+/// Desktop launches can have a short PATH, so the usual system directories
+/// are checked too.
+#[cfg(unix)]
+pub(crate) fn find_tool(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&path)
+        .chain(["/usr/bin", "/bin", "/usr/sbin", "/sbin"].map(PathBuf::from))
+        .map(|dir| dir.join(name))
+        .find(|p| p.is_file() && queries::check_is_executable(&p.to_string_lossy()))
+}
+
+#[cfg(unix)]
+fn passwordless_sudo() -> bool {
+    std::process::Command::new("sudo")
+        .args(["-n", "true"])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Pick how an admin run elevates under the `setting` from Settings.
+/// pkexec clears the environment, so it can't be used when `has_env`.
+#[cfg(unix)]
+pub(crate) fn resolve_method(setting: &str, has_env: bool) -> Result<Method, String> {
+    let sudo = find_tool("sudo").is_some();
+    let pkexec = || {
+        if find_tool("pkexec").is_none() {
+            return Err("pkexec is not installed (it ships with polkit)".to_string());
+        }
+        if has_env {
+            return Err(
+                "pkexec clears the environment, so it can't run scripts with environment variables; use sudo for elevation"
+                    .to_string(),
+            );
+        }
+        Ok(Method::Pkexec)
+    };
+    let sudo_method = || {
+        if passwordless_sudo() {
+            Method::SudoPasswordless
+        } else {
+            Method::SudoAskpass
+        }
+    };
+    match setting {
+        "pkexec" => pkexec(),
+        "sudo" | "terminal" if !sudo => Err("sudo is not installed".to_string()),
+        "terminal" => Ok(Method::Terminal),
+        "sudo" => Ok(sudo_method()),
+        _ if sudo => Ok(sudo_method()),
+        _ if find_tool("pkexec").is_some() => pkexec(),
+        _ => Err("Neither sudo nor pkexec is installed; one is needed to run scripts as root".to_string()),
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn resolve_method(_setting: &str, _has_env: bool) -> Result<Method, String> {
+    Ok(Method::Uac)
+}
+
+/// Scheduled tasks have nobody to answer a prompt, so admin scripts need
+/// passwordless sudo. Returns `None` when no elevation is needed.
+#[cfg(unix)]
+pub(crate) fn scheduled_launch(run_as_admin: bool) -> Result<Option<Launch>, String> {
+    if !run_as_admin {
+        return Ok(None);
+    }
+    if find_tool("sudo").is_none() || !passwordless_sudo() {
+        return Err(
+            "Scheduled runs of admin scripts need passwordless sudo; add a NOPASSWD sudoers rule for this user or script"
+                .to_string(),
+        );
+    }
+    prepare(Method::SudoPasswordless, None, 0).map(Some)
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn scheduled_launch(_run_as_admin: bool) -> Result<Option<Launch>, String> {
+    Ok(None)
+}
+
+/// Elevation for one run. On Unix this owns a private directory holding the
+/// success marker and, for `sudo -A`, the askpass socket.
+pub(crate) struct Launch {
+    pub(crate) method: Method,
+    dir: Option<PathBuf>,
+    #[cfg(unix)]
+    askpass: Option<AskpassServer>,
+}
+
+/// Set up elevation for a run. `app` is needed to show password prompts
+/// for `Method::SudoAskpass`.
+#[cfg(unix)]
+pub(crate) fn prepare(method: Method, app: Option<&AppHandle>, script_id: i64) -> Result<Launch, String> {
+    use std::os::unix::fs::DirBuilderExt;
+    let dir = std::env::temp_dir().join(format!("conduit-elevate-{}", uuid::Uuid::new_v4()));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .map_err(|e| format!("Failed to prepare elevation: {}", e))?;
+    let mut launch = Launch {
+        method,
+        dir: Some(dir.clone()),
+        askpass: None,
+    };
+    std::fs::write(dir.join(MARKER), b"").map_err(|e| format!("Failed to prepare elevation: {}", e))?;
+    if method == Method::SudoAskpass {
+        let app = app.ok_or_else(|| "Password prompts need the Conduit window".to_string())?;
+        launch.askpass = Some(AskpassServer::start(app.clone(), dir.join("askpass.sock"), script_id)?);
+    }
+    Ok(launch)
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn prepare(method: Method, _app: Option<&AppHandle>, _script_id: i64) -> Result<Launch, String> {
+    Ok(Launch { method, dir: None })
+}
+
+impl Launch {
+    /// For previews: the method without a marker or askpass socket.
+    pub(crate) fn preview(method: Method) -> Self {
+        Launch {
+            method,
+            dir: None,
+            #[cfg(unix)]
+            askpass: None,
+        }
+    }
+
+    /// The full argv running `target` elevated. `keep_env` names variables
+    /// sudo should pass through.
+    #[cfg(unix)]
+    pub(crate) fn wrap(&self, target: Vec<String>, keep_env: &[&str]) -> Vec<String> {
+        let mut argv: Vec<String> = match self.method {
+            Method::Pkexec => vec!["pkexec".to_string()],
+            Method::SudoPasswordless => vec!["sudo".to_string(), "-n".to_string()],
+            Method::SudoAskpass => vec!["sudo".to_string(), "-A".to_string()],
+            Method::Terminal => vec!["sudo".to_string()],
+        };
+        if self.method != Method::Pkexec && !keep_env.is_empty() {
+            argv.push(format!("--preserve-env={}", keep_env.join(",")));
+        }
+        if let Some(ref dir) = self.dir {
+            argv.extend([
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                MARK_AND_EXEC.to_string(),
+                "conduit-elevate".to_string(),
+                dir.join(MARKER).to_string_lossy().to_string(),
+            ]);
+        }
+        argv.extend(target);
+        argv
+    }
+
+    /// Environment for the elevating command itself (not the script).
+    #[cfg(unix)]
+    pub(crate) fn env(&self) -> Vec<(String, String)> {
+        match self.askpass {
+            Some(ref askpass) => {
+                let exe = std::env::current_exe().unwrap_or_default();
+                vec![
+                    ("SUDO_ASKPASS".to_string(), exe.to_string_lossy().to_string()),
+                    (ASKPASS_SOCKET_ENV.to_string(), askpass.socket.to_string_lossy().to_string()),
+                ]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Call once the elevated command has exited. Returns why elevation
+    /// failed, or `None` if the script got to run.
+    pub(crate) fn finish(&mut self) -> Option<String> {
+        let dir = self.dir.take()?;
+        #[cfg(unix)]
+        {
+            let elevated = !dir.join(MARKER).exists();
+            let prompts = self.askpass.take().map(AskpassServer::stop);
+            let _ = std::fs::remove_dir_all(&dir);
+            if elevated {
+                return None;
+            }
+            let reason = match (self.method, prompts) {
+                (_, Some(p)) if p.cancelled => "the password prompt was cancelled",
+                (_, Some(p)) if p.timed_out => "the password prompt timed out",
+                (_, Some(p)) if p.answered > 0 => "sudo rejected the password",
+                (Method::SudoPasswordless, _) => "sudo asked for a password, so passwordless sudo is no longer available",
+                (Method::Pkexec, _) => "authorization was denied or the polkit dialog was dismissed",
+                _ => "authentication failed",
+            };
+            Some(format!("Elevation failed: {}", reason))
+        }
+        #[cfg(target_os = "windows")]
+        {
+            let _ = dir;
+            None
+        }
+    }
+}
+
+impl Drop for Launch {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(unix)]
+#[derive(Default, Clone, Copy)]
+struct PromptOutcome {
+    answered: u32,
+    cancelled: bool,
+    timed_out: bool,
+}
+
+/// Answers `sudo -A` password requests by asking the user in the app.
+#[cfg(unix)]
+struct AskpassServer {
+    socket: PathBuf,
+    outcome: Arc<Mutex<PromptOutcome>>,
+    done: Arc<AtomicBool>,
+    /// The prompt currently shown, so stopping can dismiss it.
+    current: Arc<Mutex<Option<u64>>>,
+    app: AppHandle,
+}
+
+#[cfg(unix)]
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ElevationPromptEvent {
+    request_id: u64,
+    script_id: i64,
+    prompt: String,
+}
+
+#[cfg(unix)]
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ElevationPromptClosedEvent {
+    request_id: u64,
+}
+
+#[cfg(unix)]
+impl AskpassServer {
+    fn start(app: AppHandle, socket: PathBuf, script_id: i64) -> Result<Self, String> {
+        let listener = UnixListener::bind(&socket).map_err(|e| format!("Failed to start the password prompt: {}", e))?;
+        let server = AskpassServer {
+            socket,
+            outcome: Arc::new(Mutex::new(PromptOutcome::default())),
+            done: Arc::new(AtomicBool::new(false)),
+            current: Arc::new(Mutex::new(None)),
+            app,
+        };
+        let (app, outcome, done, current) = (
+            server.app.clone(),
+            Arc::clone(&server.outcome),
+            Arc::clone(&server.done),
+            Arc::clone(&server.current),
+        );
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if done.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut prompt = String::new();
+                let _ = (&mut stream).take(1024).read_to_string(&mut prompt);
+
+                let state = app.state::<ElevationState>();
+                let (request_id, answer_rx) = state.register();
+                if let Ok(mut current) = current.lock() {
+                    *current = Some(request_id);
+                }
+                let _ = app.emit(
+                    "elevation-prompt",
+                    ElevationPromptEvent {
+                        request_id,
+                        script_id,
+                        prompt: prompt.trim().to_string(),
+                    },
+                );
+                let answer = answer_rx.recv_timeout(PROMPT_TIMEOUT);
+                state.forget(request_id);
+                if let Ok(mut current) = current.lock() {
+                    *current = None;
+                }
+                let _ = app.emit("elevation-prompt-closed", ElevationPromptClosedEvent { request_id });
+
+                let Ok(mut outcome) = outcome.lock() else {
+                    continue;
+                };
+                match answer {
+                    Ok(Some(password)) => {
+                        outcome.answered += 1;
+                        let _ = stream.write_all(format!("{}\n", password).as_bytes());
+                    }
+                    // Closing without an answer makes sudo give up
+                    Ok(None) => outcome.cancelled = true,
+                    Err(_) => outcome.timed_out = true,
+                }
+            }
+        });
+        Ok(server)
+    }
+
+    fn stop(self) -> PromptOutcome {
+        self.done.store(true, Ordering::SeqCst);
+        if let Some(request_id) = self.current.lock().ok().and_then(|c| *c) {
+            self.app.state::<ElevationState>().resolve(request_id, None);
+        }
+        // Wake the accept loop so it sees `done`
+        let _ = UnixStream::connect(&self.socket);
+        self.outcome.lock().map(|o| *o).unwrap_or_default()
+    }
+}
+
+/// Entry point for Conduit acting as sudo's askpass helper: sends the prompt
+/// to the app and prints the password it answers with.
+#[cfg(unix)]
+pub fn askpass(socket: &str, prompt: &str) -> i32 {
+    let Ok(mut stream) = UnixStream::connect(socket) else {
+        eprintln!("conduit: the password prompt is no longer available");
+        return 1;
+    };
+    if stream.write_all(prompt.as_bytes()).is_err() || stream.shutdown(std::net::Shutdown::Write).is_err() {
+        return 1;
+    }
+    let mut password = String::new();
+    if stream.read_to_string(&mut password).is_err() || password.is_empty() {
+        return 1;
+    }
+    print!("{}", password);
+    let _ = std::io::stdout().flush();
+    0
+}
+
+/// Password prompts waiting on an answer from the UI.
+pub struct ElevationState {
+    pending: Mutex<HashMap<u64, mpsc::Sender<Option<String>>>>,
+    next_id: AtomicU64,
+}
+
+impl ElevationState {
+    pub fn new() -> Self {
+        ElevationState {
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    #[cfg(unix)]
+    fn register(&self) -> (u64, mpsc::Receiver<Option<String>>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(id, tx);
+        }
+        (id, rx)
+    }
+
+    #[cfg(unix)]
+    fn forget(&self, id: u64) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&id);
+        }
+    }
+
+    fn resolve(&self, id: u64, answer: Option<String>) -> bool {
+        let sender = self.pending.lock().ok().and_then(|mut pending| pending.remove(&id));
+        sender.is_some_and(|tx| tx.send(answer).is_ok())
+    }
+}
+
+#[tauri::command]
+pub fn get_elevation_status(db: State<'_, Database>) -> Result<ElevationStatus, String> {
+    let method = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        queries::get_settings(&conn).map_err(|e| e.to_string())?.elevation_method
+    };
+    #[cfg(unix)]
+    let (sudo, passwordless_sudo, pkexec) = {
+        let sudo = find_tool("sudo").is_some();
+        (sudo, sudo && passwordless_sudo(), find_tool("pkexec").is_some())
+    };
+    #[cfg(target_os = "windows")]
+    let (sudo, passwordless_sudo, pkexec) = (false, false, false);
+    let (effective, error) = match resolve_method(&method, false) {
+        Ok(m) => (Some(m.as_str().to_string()), None),
+        Err(e) => (None, Some(e)),
+    };
+    Ok(ElevationStatus {
+        sudo,
+        passwordless_sudo,
+        pkexec,
+        method,
+        effective,
+        error,
+    })
+}
+
+/// Answer a password prompt; `None` cancels it.
+#[tauri::command]
+pub fn answer_elevation_prompt(
+    elevation: State<'_, ElevationState>,
+    request_id: u64,
+    password: Option<String>,
+) -> Result<(), String> {
+    if !elevation.resolve(request_id, password) {
+        return Err("The password prompt is no longer waiting".to_string());
+    }
+    Ok(())
+}
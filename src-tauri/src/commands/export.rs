@@ -0,0 +1,203 @@
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{RunExportRow, RunSearchFilter};
+use rusqlite::Connection;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tauri::State;
+
+pub(crate) type ExportResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+const EXPORT_FORMATS: [&str; 3] = ["csv", "jsonl", "junit"];
+
+/// Rows read per database lock while exporting.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+const CSV_HEADER: &str =
+    "run_id,script_id,script,category,started_at,finished_at,duration_seconds,exit_code,status,output,metadata";
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub(crate) fn opt_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Create `path` and stream an export into it, returning the row count. A
+/// failed export removes the file rather than leave a truncated one behind.
+pub(crate) fn export_to_file(
+    path: &str,
+    write: impl FnOnce(&mut BufWriter<File>) -> ExportResult<i64>,
+) -> Result<i64, String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut out = BufWriter::new(file);
+    let result = write(&mut out);
+    drop(out);
+    result.map_err(|e| {
+        let _ = std::fs::remove_file(path);
+        e.to_string()
+    })
+}
+
+/// Feed every row `page` returns to `write`, a page at a time. The database
+/// lock is only held while a page is read, so a slow disk doesn't stall
+/// other commands. `page` gets the last row written so far.
+pub(crate) fn for_each_page<T>(
+    db: &Database,
+    mut page: impl FnMut(&Connection, Option<&T>, i64) -> rusqlite::Result<Vec<T>>,
+    mut write: impl FnMut(&T) -> ExportResult<()>,
+) -> ExportResult<i64> {
+    let mut count = 0;
+    let mut last: Option<T> = None;
+    loop {
+        let rows = {
+            let conn = db.conn.lock().map_err(|e| e.to_string())?;
+            page(&conn, last.as_ref(), EXPORT_PAGE_SIZE)?
+        };
+        let full = rows.len() as i64 == EXPORT_PAGE_SIZE;
+        for row in &rows {
+            write(row)?;
+            count += 1;
+        }
+        match rows.into_iter().last() {
+            Some(row) if full => last = Some(row),
+            _ => return Ok(count),
+        }
+    }
+}
+
+fn for_each_run(
+    db: &Database,
+    filter: &RunSearchFilter,
+    write: impl FnMut(&RunExportRow) -> ExportResult<()>,
+) -> ExportResult<i64> {
+    for_each_page(
+        db,
+        |conn, last: Option<&RunExportRow>, limit| {
+            queries::get_run_export_page(conn, filter, last.map(|r| (r.started_at.as_str(), r.id)), limit)
+        },
+        write,
+    )
+}
+
+fn write_csv_row(out: &mut impl Write, run: &RunExportRow) -> std::io::Result<()> {
+    let fields = [
+        run.id.to_string(),
+        run.script_id.to_string(),
+        csv_field(&run.script_name),
+        csv_field(&run.category_name),
+        run.started_at.clone(),
+        opt_to_string(run.finished_at.as_deref()),
+        opt_to_string(run.duration_seconds),
+        opt_to_string(run.exit_code),
+        run.status.clone(),
+        csv_field(run.output.as_deref().unwrap_or("")),
+        csv_field(&opt_to_string(run.metadata.as_ref())),
+    ];
+    writeln!(out, "{}", fields.join(","))
+}
+
+/// Escape text for XML attributes and content, dropping characters XML 1.0
+/// can't represent at all (most C0 controls).
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Each run becomes a testcase: errors are failures, cancelled runs are
+/// skipped, and the captured output goes in system-out.
+fn write_junit_case(out: &mut impl Write, run: &RunExportRow) -> std::io::Result<()> {
+    let classname = if run.category_name.is_empty() {
+        format!("conduit.{}", run.script_name)
+    } else {
+        format!("conduit.{}.{}", run.category_name, run.script_name)
+    };
+    writeln!(
+        out,
+        "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">",
+        xml_escape(&classname),
+        xml_escape(&format!("run {} ({})", run.id, run.started_at)),
+        run.duration_seconds.unwrap_or(0.0)
+    )?;
+    match run.status.as_str() {
+        "error" => writeln!(
+            out,
+            "      <failure message=\"exit code {}\" type=\"error\"/>",
+            opt_to_string(run.exit_code)
+        )?,
+        "cancelled" => writeln!(out, "      <skipped message=\"cancelled\"/>")?,
+        _ => {}
+    }
+    if let Some(output) = run.output.as_deref().filter(|o| !o.is_empty()) {
+        writeln!(out, "      <system-out>{}</system-out>", xml_escape(output))?;
+    }
+    writeln!(out, "    </testcase>")
+}
+
+fn write_export(db: &Database, out: &mut impl Write, format: &str, filter: &RunSearchFilter) -> ExportResult<i64> {
+    let count = match format {
+        "csv" => {
+            writeln!(out, "{}", CSV_HEADER)?;
+            for_each_run(db, filter, |run| Ok(write_csv_row(out, run)?))?
+        }
+        "jsonl" => for_each_run(db, filter, |run| {
+            serde_json::to_writer(&mut *out, run)?;
+            Ok(out.write_all(b"\n")?)
+        })?,
+        "junit" => {
+            // Suite totals come from a separate aggregate so the header can be
+            // written before the runs are streamed.
+            let totals = {
+                let conn = db.conn.lock().map_err(|e| e.to_string())?;
+                queries::get_run_export_totals(&conn, filter)?
+            };
+            let timestamp = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S");
+            writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+            writeln!(
+                out,
+                "<testsuites name=\"conduit\" tests=\"{0}\" failures=\"{1}\" skipped=\"{2}\" time=\"{3:.3}\">\n  \
+                 <testsuite name=\"conduit\" tests=\"{0}\" failures=\"{1}\" errors=\"0\" skipped=\"{2}\" time=\"{3:.3}\" timestamp=\"{4}\">",
+                totals.runs, totals.failures, totals.skipped, totals.duration_seconds, timestamp
+            )?;
+            let count = for_each_run(db, filter, |run| Ok(write_junit_case(out, run)?))?;
+            writeln!(out, "  </testsuite>\n</testsuites>")?;
+            count
+        }
+        other => unreachable!("export format {} not validated", other),
+    };
+    out.flush()?;
+    Ok(count)
+}
+
+/// Write finished runs matching `filter` to `path` as "csv", "jsonl" or
+/// "junit". Returns the number of runs exported.
+#[tauri::command]
+pub fn export_run_history(
+    db: State<'_, Database>,
+    path: String,
+    format: String,
+    filter: Option<RunSearchFilter>,
+) -> Result<i64, String> {
+    if !EXPORT_FORMATS.contains(&format.as_str()) {
+        return Err(format!("Unknown export format: {}", format));
+    }
+    let filter = filter.unwrap_or_default();
+    export_to_file(&path, |out| write_export(&db, out, &format, &filter))
+}
@@ -0,0 +1,517 @@
+use crate::commands::{audit, config, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{
+    HeaderDrift, HeaderParam, HeaderSchedule, NewSchedule, Schedule, Script, ScriptHeader, ScriptHeaderReport,
+    ScriptParam, UpdateScript,
+};
+use rusqlite::Connection;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tauri::State;
+
+/// Only the top of a file is searched for a header comment.
+const HEADER_SCAN_LINES: usize = 40;
+
+const DEFAULT_COLOR: &str = "#00d4aa";
+
+pub(crate) const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Text of a single-line comment, without its marker.
+fn comment_text(line: &str) -> Option<&str> {
+    ["#", "//", "::", "REM ", "rem "]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .map(str::trim)
+}
+
+/// `90`, `90s`, `15m`, `2h`, `1h30m` or `1d` as seconds.
+fn parse_duration(text: &str) -> Option<i64> {
+    let mut total = 0i64;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return None,
+        };
+        total += number.parse::<i64>().ok()? * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        total += number.parse::<i64>().ok()?;
+    }
+    (total > 0).then_some(total)
+}
+
+fn parse_time(text: &str) -> Option<String> {
+    chrono::NaiveTime::parse_from_str(text, "%H:%M")
+        .ok()
+        .map(|t| t.format("%H:%M").to_string())
+}
+
+fn parse_weekday(text: &str) -> Option<i32> {
+    if let Ok(day) = text.parse::<i32>() {
+        return (0..=6).contains(&day).then_some(day);
+    }
+    let prefix = text.get(..3)?.to_lowercase();
+    WEEKDAYS.iter().position(|d| *d == prefix).map(|d| d as i32)
+}
+
+/// `daily 08:00`, `weekly mon 08:00` or `every 15m`.
+fn parse_schedule(spec: &str) -> Result<HeaderSchedule, String> {
+    let words: Vec<&str> = spec.split_whitespace().collect();
+    let schedule = match words.as_slice() {
+        ["daily", time] => HeaderSchedule {
+            schedule_type: "daily".to_string(),
+            time: Some(parse_time(time).ok_or("daily schedules need a time in HH:MM")?),
+            weekday: None,
+            interval_seconds: None,
+        },
+        ["weekly", day, time] => HeaderSchedule {
+            schedule_type: "weekly".to_string(),
+            time: Some(parse_time(time).ok_or("weekly schedules need a time in HH:MM")?),
+            weekday: Some(parse_weekday(day).ok_or("weekly schedules need a weekday (sun-sat or 0-6)")?),
+            interval_seconds: None,
+        },
+        ["every", interval] => HeaderSchedule {
+            schedule_type: "interval".to_string(),
+            time: None,
+            weekday: None,
+            interval_seconds: Some(parse_duration(interval).ok_or("intervals look like 90s, 15m or 2h")?),
+        },
+        _ => return Err("expected \"daily HH:MM\", \"weekly DAY HH:MM\" or \"every DURATION\"".to_string()),
+    };
+    Ok(schedule)
+}
+
+/// `name[!][=default] [description]`, where `!` marks the parameter as
+/// required and a default containing spaces is double-quoted.
+fn parse_param(spec: &str) -> Result<HeaderParam, String> {
+    let name_end = spec.find(['=', '!', ' ', '\t']).unwrap_or(spec.len());
+    let name = &spec[..name_end];
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err("parameter names may only contain letters, digits, '_' and '-'".to_string());
+    }
+    let mut rest = &spec[name_end..];
+    let required = rest.starts_with('!');
+    if required {
+        rest = &rest[1..];
+    }
+    let mut default_value = None;
+    if let Some(value) = rest.strip_prefix('=') {
+        let (default, remainder) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').ok_or("unterminated quoted default")?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => {
+                let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            }
+        };
+        default_value = Some(default.to_string());
+        rest = remainder;
+    }
+    let description = rest.trim();
+    Ok(HeaderParam {
+        name: name.to_string(),
+        description: (!description.is_empty()).then(|| description.to_string()),
+        default_value,
+        required,
+    })
+}
+
+fn apply_tag(header: &mut ScriptHeader, description: &mut Vec<String>, tag: &str, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    match tag {
+        "name" | "category" if value.is_empty() => return Err(format!("@{} needs a value", tag)),
+        "name" => header.name = Some(value.to_string()),
+        "category" => header.category = Some(value.to_string()),
+        "description" => description.push(value.to_string()),
+        "timeout" => header.timeout_seconds = Some(parse_duration(value).ok_or("timeouts look like 90s, 15m or 2h")?),
+        "param" => {
+            let param = parse_param(value)?;
+            if header.params.iter().any(|p| p.name == param.name) {
+                return Err(format!("parameter \"{}\" is declared twice", param.name));
+            }
+            header.params.push(param);
+        }
+        "schedule" => header.schedules.push(parse_schedule(value)?),
+        // Other tools' tags (@author, @license, ...) are none of our business
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Read the comment block at the top of a script. `@tag` lines fill the
+/// matching fields; without an `@description` the first plain paragraph is
+/// used. Returns `None` when the file can't be read.
+pub(crate) fn parse_header(path: &Path) -> Option<ScriptHeader> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut header = ScriptHeader::default();
+    let mut tagged_description: Vec<String> = Vec::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut paragraph_done = false;
+    let mut in_block = false;
+
+    for (index, line) in BufReader::new(file).lines().take(HEADER_SCAN_LINES).enumerate() {
+        let line = line.ok()?;
+        let trimmed = line.trim();
+        if trimmed.starts_with("#!") {
+            continue;
+        }
+        // PowerShell block comments carry their text without a marker
+        if trimmed.starts_with("<#") {
+            in_block = true;
+            continue;
+        }
+        if trimmed.starts_with("#>") {
+            in_block = false;
+            continue;
+        }
+        let text = if in_block { Some(trimmed) } else { comment_text(trimmed) };
+        match text {
+            Some(text) if text.starts_with('@') => {
+                let (tag, value) = text[1..].split_once(char::is_whitespace).unwrap_or((&text[1..], ""));
+                if let Err(e) = apply_tag(&mut header, &mut tagged_description, tag, value) {
+                    header.warnings.push(format!("line {}: {}", index + 1, e));
+                }
+            }
+            Some("") => paragraph_done |= !paragraph.is_empty(),
+            Some(text) if !paragraph_done => paragraph.push(text.to_string()),
+            Some(_) => {}
+            None if trimmed.is_empty() => paragraph_done |= !paragraph.is_empty(),
+            None => break,
+        }
+    }
+
+    header.description_untagged = tagged_description.is_empty() && !paragraph.is_empty();
+    let description = if tagged_description.is_empty() {
+        paragraph
+    } else {
+        tagged_description
+    };
+    if !description.is_empty() {
+        header.description = Some(description.join(" "));
+    }
+    Some(header)
+}
+
+fn find_or_create_category(conn: &Connection, name: &str) -> Result<i64, String> {
+    let categories = queries::get_all_categories(conn).map_err(|e| e.to_string())?;
+    match categories.iter().find(|c| c.name.eq_ignore_ascii_case(name)) {
+        Some(category) => Ok(category.id),
+        None => {
+            let created = queries::insert_category(conn, name, DEFAULT_COLOR).map_err(|e| e.to_string())?;
+            audit::log(conn, "category.add", "category", Some(created.id), Some(&created.name), None, Some(&created));
+            Ok(created.id)
+        }
+    }
+}
+
+fn same_schedule(existing: &Schedule, declared: &HeaderSchedule) -> bool {
+    existing.schedule_type == declared.schedule_type
+        && existing.time == declared.time
+        && existing.weekday == declared.weekday
+        && existing.interval_seconds == declared.interval_seconds
+}
+
+fn same_params(existing: &[ScriptParam], declared: &[HeaderParam]) -> bool {
+    existing.len() == declared.len()
+        && existing.iter().zip(declared).all(|(e, d)| {
+            e.name == d.name
+                && e.description == d.description
+                && e.default_value == d.default_value
+                && e.required == d.required
+        })
+}
+
+/// Write the header's values onto the script. Schedules the script doesn't
+/// have yet are only installed when `install_schedules` is set, and never
+/// for protected scripts, whose schedules need the confirmation phrase;
+/// skipped schedules and failures to install them end up in `warnings`.
+/// Callers record the applied snapshot.
+pub(crate) fn apply_header(
+    conn: &Connection,
+    script: &Script,
+    header: &ScriptHeader,
+    install_schedules: bool,
+    warnings: &mut Vec<String>,
+) -> Result<Script, String> {
+    let category_id = match header.category {
+        Some(ref name) => Some(find_or_create_category(conn, name)?),
+        None => None,
+    };
+    let updated = queries::update_script(
+        conn,
+        script.id,
+        &UpdateScript {
+            name: header.name.clone(),
+            path: None,
+            description: header.description.clone(),
+            category_id,
+            color: None,
+            run_as_admin: None,
+            timeout_seconds: header.timeout_seconds,
+        },
+    )
+    .map_err(|e| e.to_string())?;
+
+    // A header without @param lines leaves parameters set up elsewhere alone
+    if !header.params.is_empty() {
+        let existing = queries::get_script_params(conn, script.id).map_err(|e| e.to_string())?;
+        if !same_params(&existing, &header.params) {
+            let defs: Vec<ScriptParam> = header
+                .params
+                .iter()
+                .map(|p| ScriptParam {
+                    id: 0,
+                    script_id: script.id,
+                    name: p.name.clone(),
+                    description: p.description.clone(),
+                    default_value: p.default_value.clone(),
+                    required: p.required,
+                })
+                .collect();
+            queries::replace_script_params(conn, script.id, &defs).map_err(|e| e.to_string())?;
+        }
+    }
+
+    if install_schedules && !header.schedules.is_empty() {
+        let existing = queries::get_schedules_for_script(conn, script.id).map_err(|e| e.to_string())?;
+        let protected = queries::get_script_protection(conn, script.id).map_err(|e| e.to_string())?.is_some();
+        for schedule in header
+            .schedules
+            .iter()
+            .filter(|h| !existing.iter().any(|e| same_schedule(e, h)))
+        {
+            if protected {
+                warnings.push(format!(
+                    "Skipped the {} schedule: protected scripts need their confirmation phrase to be scheduled",
+                    schedule.schedule_type
+                ));
+                continue;
+            }
+            let new_schedule = NewSchedule {
+                script_id: script.id,
+                schedule_type: schedule.schedule_type.clone(),
+                time: schedule.time.clone(),
+                weekday: schedule.weekday,
+                interval_seconds: schedule.interval_seconds,
+            };
+            match scheduler::install_schedule(conn, &new_schedule, true) {
+                Ok(created) => scheduler::audit_schedule(conn, "schedule.create", &created, None, Some(&created)),
+                Err(e) => warnings.push(format!("Failed to create {} schedule: {}", schedule.schedule_type, e)),
+            }
+        }
+    }
+
+    Ok(updated)
+}
+
+/// What `sync_header` would do for a script.
+enum Pending {
+    Nothing,
+    /// Record the header as the baseline without applying it.
+    Baseline(String),
+    /// Apply these tagged fields and record the snapshot.
+    Apply(ScriptHeader, String),
+}
+
+fn pending_header(conn: &Connection, script: &Script) -> Result<Pending, String> {
+    if script.config_managed {
+        return Ok(Pending::Nothing);
+    }
+    let Some(header) = parse_header(Path::new(&script.path)) else {
+        return Ok(Pending::Nothing);
+    };
+    let snapshot = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+    let applied = queries::get_header_applied(conn, script.id).map_err(|e| e.to_string())?;
+    match applied.as_deref() {
+        Some(applied) if applied == snapshot => return Ok(Pending::Nothing),
+        // Seeded for scripts that predate header sync
+        Some("") => return Ok(Pending::Baseline(snapshot)),
+        _ => {}
+    }
+
+    let previous_description = applied
+        .as_deref()
+        .and_then(|a| serde_json::from_str::<serde_json::Value>(a).ok())
+        .and_then(|v| v.get("description")?.as_str().map(String::from));
+    let entered = script.description.as_deref().is_some_and(|d| !d.is_empty());
+    let description_changed = applied.is_some() && previous_description != header.description;
+    let mut explicit = header;
+    if explicit.description_untagged || (entered && !description_changed) {
+        explicit.description = None;
+    }
+    Ok(Pending::Apply(explicit, snapshot))
+}
+
+/// Re-apply a script's header if it changed since it was last applied, so
+/// edits made in the app stick until the header itself is edited. Only what
+/// the header declares with tags is applied, and a tagged description only
+/// replaces one the user entered once the header's description changes;
+/// anything else shows up as drift. Scripts owned by the config file and
+/// unreadable files are skipped. Returns the updated script when something
+/// was applied.
+pub(crate) fn sync_header(conn: &Connection, script: &Script, install_schedules: bool) -> Result<Option<Script>, String> {
+    match pending_header(conn, script)? {
+        Pending::Nothing => Ok(None),
+        Pending::Baseline(snapshot) => {
+            queries::set_header_applied(conn, script.id, &snapshot).map_err(|e| e.to_string())?;
+            Ok(None)
+        }
+        Pending::Apply(header, snapshot) => {
+            let mut warnings = Vec::new();
+            let updated = apply_header(conn, script, &header, install_schedules, &mut warnings)?;
+            queries::set_header_applied(conn, script.id, &snapshot).map_err(|e| e.to_string())?;
+            Ok(Some(updated))
+        }
+    }
+}
+
+/// The script as `sync_header` would leave it, without writing anything.
+/// Only the fields a run uses are filled in.
+pub(crate) fn preview_header(conn: &Connection, script: &Script) -> Result<Script, String> {
+    let mut script = script.clone();
+    if let Pending::Apply(header, _) = pending_header(conn, &script)? {
+        if let Some(name) = header.name {
+            script.name = name;
+        }
+        if header.timeout_seconds.is_some() {
+            script.timeout_seconds = header.timeout_seconds;
+        }
+    }
+    Ok(script)
+}
+
+fn describe_schedule(schedule: &HeaderSchedule) -> String {
+    match schedule.schedule_type.as_str() {
+        "daily" => format!("daily {}", schedule.time.as_deref().unwrap_or("")),
+        "weekly" => format!(
+            "weekly {} {}",
+            schedule
+                .weekday
+                .and_then(|d| WEEKDAYS.get(d as usize))
+                .unwrap_or(&"?"),
+            schedule.time.as_deref().unwrap_or("")
+        ),
+        _ => format!("every {}s", schedule.interval_seconds.unwrap_or(0)),
+    }
+}
+
+fn describe_param(name: &str, default_value: Option<&str>, required: bool) -> String {
+    let mut text = name.to_string();
+    if required {
+        text.push('!');
+    }
+    if let Some(default) = default_value {
+        text.push('=');
+        text.push_str(default);
+    }
+    text
+}
+
+/// Fields where the library disagrees with what the header declares.
+fn header_drift(conn: &Connection, script: &Script, header: &ScriptHeader) -> Result<Vec<HeaderDrift>, String> {
+    let mut drift = Vec::new();
+    let mut differs = |field: &str, header_value: String, db_value: String| {
+        if header_value != db_value {
+            drift.push(HeaderDrift {
+                field: field.to_string(),
+                header_value,
+                db_value,
+            });
+        }
+    };
+
+    if let Some(ref name) = header.name {
+        differs("name", name.clone(), script.name.clone());
+    }
+    if let Some(ref description) = header.description {
+        differs("description", description.clone(), script.description.clone().unwrap_or_default());
+    }
+    if let Some(ref category) = header.category {
+        let current = queries::get_all_categories(conn)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|c| c.id == script.category_id)
+            .map(|c| c.name)
+            .unwrap_or_default();
+        if !category.eq_ignore_ascii_case(&current) {
+            differs("category", category.clone(), current);
+        }
+    }
+    if let Some(timeout) = header.timeout_seconds {
+        differs(
+            "timeout",
+            timeout.to_string(),
+            script.timeout_seconds.map(|t| t.to_string()).unwrap_or_default(),
+        );
+    }
+    if !header.params.is_empty() {
+        let existing = queries::get_script_params(conn, script.id).map_err(|e| e.to_string())?;
+        if !same_params(&existing, &header.params) {
+            differs(
+                "params",
+                header
+                    .params
+                    .iter()
+                    .map(|p| describe_param(&p.name, p.default_value.as_deref(), p.required))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                existing
+                    .iter()
+                    .map(|p| describe_param(&p.name, p.default_value.as_deref(), p.required))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+    }
+    if !header.schedules.is_empty() {
+        let existing = queries::get_schedules_for_script(conn, script.id).map_err(|e| e.to_string())?;
+        for schedule in header
+            .schedules
+            .iter()
+            .filter(|h| !existing.iter().any(|e| same_schedule(e, h)))
+        {
+            differs("schedule", describe_schedule(schedule), String::new());
+        }
+    }
+    Ok(drift)
+}
+
+/// The parsed header of a script and where the library disagrees with it.
+#[tauri::command]
+pub fn get_script_header(db: State<'_, Database>, script_id: i64) -> Result<ScriptHeaderReport, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    let header =
+        parse_header(Path::new(&script.path)).ok_or_else(|| format!("Could not read {}", script.path))?;
+    let drift = header_drift(&conn, &script, &header)?;
+    Ok(ScriptHeaderReport { header, drift })
+}
+
+/// Overwrite the script's settings with its header, installing any declared
+/// schedules it doesn't have yet.
+#[tauri::command]
+pub fn apply_script_header(db: State<'_, Database>, script_id: i64) -> Result<(Script, Vec<String>), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    config::ensure_editable(&script)?;
+    let header =
+        parse_header(Path::new(&script.path)).ok_or_else(|| format!("Could not read {}", script.path))?;
+    let mut warnings = header.warnings.clone();
+    let updated = apply_header(&conn, &script, &header, true, &mut warnings)?;
+    let snapshot = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+    queries::set_header_applied(&conn, script.id, &snapshot).map_err(|e| e.to_string())?;
+    audit::log(&conn, "script.header_apply", "script", Some(script.id), Some(&updated.name), Some(&script), Some(&updated));
+    Ok((updated, warnings))
+}
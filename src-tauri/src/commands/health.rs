@@ -0,0 +1,412 @@
+use crate::commands::{audit, config, header, library, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{Script, ScriptHealth, UpdateScript};
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use walkdir::WalkDir;
+
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How deep to look below each search root for a moved script.
+const RELOCATE_MAX_DEPTH: usize = 4;
+
+/// Stop searching for a moved script after examining this many files.
+const RELOCATE_MAX_FILES: usize = 20_000;
+
+/// SHA-256 of a file's contents as lowercase hex.
+pub(crate) fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub(crate) fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(unix)]
+fn file_mode(meta: &std::fs::Metadata) -> Option<i64> {
+    use std::os::unix::fs::PermissionsExt;
+    Some((meta.permissions().mode() & 0o7777) as i64)
+}
+
+#[cfg(target_os = "windows")]
+fn file_mode(_meta: &std::fs::Metadata) -> Option<i64> {
+    None
+}
+
+fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Work out a script's file health from disk without recording anything.
+/// Also returns the previously recorded health.
+fn inspect(conn: &Connection, script: &Script) -> Result<(ScriptHealth, Option<ScriptHealth>), String> {
+    let previous = queries::get_script_health(conn, script.id).map_err(|e| e.to_string())?;
+    Ok((inspect_file(script, previous.as_ref()), previous))
+}
+
+/// The file side of `inspect`, given the previously recorded health. Needs
+/// no database access, so callers can hash files without holding the lock.
+fn inspect_file(script: &Script, previous: Option<&ScriptHealth>) -> ScriptHealth {
+    let mut health = ScriptHealth {
+        script_id: script.id,
+        status: "ok".to_string(),
+        size: None,
+        modified_at: None,
+        mode: None,
+        // Keep the last known hash while the file is gone so a moved copy
+        // can still be recognised
+        hash: previous.and_then(|p| p.hash.clone()),
+        approved_hash: previous.and_then(|p| p.approved_hash.clone()),
+        checked_at: now(),
+    };
+
+    let path = Path::new(&script.path);
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) if meta.is_file() => meta,
+        Ok(_) => {
+            health.status = "unreadable".to_string();
+            return health;
+        }
+        Err(e) => {
+            health.status = if e.kind() == std::io::ErrorKind::NotFound {
+                "missing"
+            } else {
+                "unreadable"
+            }
+            .to_string();
+            return health;
+        }
+    };
+
+    health.size = Some(meta.len() as i64);
+    health.modified_at = meta
+        .modified()
+        .ok()
+        .map(|t| chrono::DateTime::<chrono::Utc>::from(t).format("%Y-%m-%d %H:%M:%S%.f").to_string());
+    health.mode = file_mode(&meta);
+
+    // Pinned scripts are always re-hashed; an mtime can be put back
+    let unchanged = !script.pinned
+        && previous.is_some_and(|p| p.hash.is_some() && p.size == health.size && p.modified_at == health.modified_at);
+    if !unchanged {
+        match hash_file(path) {
+            Ok(hash) => health.hash = Some(hash),
+            Err(_) => {
+                health.status = "unreadable".to_string();
+                return health;
+            }
+        }
+    }
+    if health.approved_hash.is_none() {
+        health.approved_hash = health.hash.clone();
+    }
+    if health.hash != health.approved_hash {
+        health.status = "modified".to_string();
+    }
+
+    health
+}
+
+/// Look at a script's file and record what was found. Unpinned files are
+/// only re-hashed when their size or mtime moved. The first hash seen for a script
+/// becomes its approved hash; a later edit leaves the script "modified" until
+/// it is approved again.
+pub(crate) fn check_script(conn: &Connection, script: &Script) -> Result<ScriptHealth, String> {
+    let (health, previous) = inspect(conn, script)?;
+    record(conn, script, health, previous.as_ref())
+}
+
+/// The recording side of `check_script`.
+fn record(
+    conn: &Connection,
+    script: &Script,
+    health: ScriptHealth,
+    previous: Option<&ScriptHealth>,
+) -> Result<ScriptHealth, String> {
+    let readable = health.status != "missing" && health.status != "unreadable";
+    if readable {
+        let is_executable = queries::check_is_executable(&script.path);
+        if is_executable != script.is_executable {
+            queries::set_script_executable(conn, script.id, is_executable).map_err(|e| e.to_string())?;
+        }
+    }
+    queries::upsert_script_health(conn, &health).map_err(|e| e.to_string())?;
+
+    // Content changed since the last check: pick up header edits
+    let content_changed = previous.is_some_and(|p| p.hash.is_some() && p.hash != health.hash);
+    if readable && content_changed {
+        header::sync_header(conn, script, false)?;
+    }
+    Ok(health)
+}
+
+/// Check every script, returning those whose status changed. Files are read
+/// and hashed without holding the database lock; scripts deleted, moved or
+/// checked (say, approved) in the meantime are skipped.
+fn check_all(db: &Database) -> Result<Vec<ScriptHealth>, String> {
+    let (scripts, previous) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let previous: HashMap<i64, ScriptHealth> = queries::get_all_script_health(&conn)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|h| (h.script_id, h))
+            .collect();
+        (queries::get_all_scripts(&conn).map_err(|e| e.to_string())?, previous)
+    };
+    let inspected: Vec<(Script, ScriptHealth)> = scripts
+        .into_iter()
+        .map(|script| {
+            let health = inspect_file(&script, previous.get(&script.id));
+            (script, health)
+        })
+        .collect();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let current: HashMap<i64, Script> = queries::get_all_scripts(&conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| (s.id, s))
+        .collect();
+    let recorded: HashMap<i64, ScriptHealth> = queries::get_all_script_health(&conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|h| (h.script_id, h))
+        .collect();
+    let same_record = |a: Option<&ScriptHealth>, b: Option<&ScriptHealth>| {
+        a.map(|h| (&h.checked_at, &h.approved_hash)) == b.map(|h| (&h.checked_at, &h.approved_hash))
+    };
+    let mut changed = Vec::new();
+    for (script, health) in inspected {
+        let Some(script) = current.get(&script.id).filter(|c| c.path == script.path && c.pinned == script.pinned) else {
+            continue;
+        };
+        let before = previous.get(&script.id);
+        if !same_record(before, recorded.get(&script.id)) {
+            continue;
+        }
+        let health = record(&conn, script, health, before)?;
+        if before.map(|p| &p.status) != Some(&health.status) {
+            changed.push(health);
+        }
+    }
+    Ok(changed)
+}
+
+/// Called before a run. Missing files can't run at all; a pinned script only
+/// runs while its content matches the approved hash.
+pub(crate) fn ensure_runnable(conn: &Connection, script: &Script) -> Result<(), String> {
+    runnable(script, &check_script(conn, script)?)
+}
+
+/// `ensure_runnable` for previews: the file is checked but nothing recorded.
+pub(crate) fn check_runnable(conn: &Connection, script: &Script) -> Result<(), String> {
+    runnable(script, &inspect(conn, script)?.0)
+}
+
+fn runnable(script: &Script, health: &ScriptHealth) -> Result<(), String> {
+    match health.status.as_str() {
+        "missing" => Err(format!("Script file {} no longer exists", script.path)),
+        "unreadable" if script.pinned => Err(format!(
+            "\"{}\" is pinned and its file {} can't be read to verify it",
+            script.name, script.path
+        )),
+        "modified" if script.pinned => Err(format!(
+            "\"{}\" is pinned and its file changed since it was approved; review and approve the change first",
+            script.name
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Periodically re-check every script file and tell the frontend which ones
+/// changed status.
+pub(crate) fn start_health_monitor(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let changed = check_all(&app.state::<Database>());
+        if let Ok(changed) = changed {
+            if !changed.is_empty() {
+                let _ = app.emit("script-health-changed", changed);
+            }
+        }
+        std::thread::sleep(HEALTH_CHECK_INTERVAL);
+    });
+}
+
+fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// Folders worth searching for a moved script: around its old location, the
+/// watched source folders and Conduit's own scripts folder.
+fn relocation_roots(conn: &Connection, script: &Script) -> Result<Vec<PathBuf>, String> {
+    let mut roots = Vec::new();
+    let old = Path::new(&script.path);
+    // One level above the old folder catches renamed and sibling folders,
+    // but never walk the whole filesystem
+    let around = old.parent().and_then(|p| p.parent().filter(|gp| gp.parent().is_some()).or(Some(p)));
+    if let Some(dir) = around.filter(|d| d.parent().is_some()) {
+        roots.push(dir.to_path_buf());
+    }
+    for source in queries::get_script_sources(conn).map_err(|e| e.to_string())? {
+        roots.push(PathBuf::from(source.path));
+    }
+    roots.push(library::get_scripts_dir());
+    Ok(roots)
+}
+
+#[tauri::command]
+pub fn get_script_health(db: State<'_, Database>) -> Result<Vec<ScriptHealth>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_all_script_health(&conn).map_err(|e| e.to_string())
+}
+
+/// Re-check every script file now. Returns the current state of all of them.
+#[tauri::command]
+pub fn check_script_files(app: AppHandle, db: State<'_, Database>) -> Result<Vec<ScriptHealth>, String> {
+    let changed = check_all(&db)?;
+    if !changed.is_empty() {
+        let _ = app.emit("script-health-changed", changed);
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_all_script_health(&conn).map_err(|e| e.to_string())
+}
+
+fn approve(conn: &Connection, script_id: i64) -> Result<ScriptHealth, String> {
+    let script = queries::get_script_by_id(conn, script_id).map_err(|e| e.to_string())?;
+    let mut health = check_script(conn, &script)?;
+    if health.status == "missing" || health.status == "unreadable" {
+        return Err(format!("Can't approve {}: the file is {}", script.path, health.status));
+    }
+    health.approved_hash = health.hash.clone();
+    health.status = "ok".to_string();
+    queries::upsert_script_health(conn, &health).map_err(|e| e.to_string())?;
+    Ok(health)
+}
+
+/// Accept the file's current content as the approved version.
+#[tauri::command]
+pub fn approve_script_file(db: State<'_, Database>, script_id: i64) -> Result<ScriptHealth, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    let before = queries::get_script_health(&conn, script_id).map_err(|e| e.to_string())?;
+    let after = approve(&conn, script_id)?;
+    audit::log(&conn, "script.approve", "script", Some(script_id), Some(&script.name), before.as_ref(), Some(&after));
+    Ok(after)
+}
+
+/// Pinning also approves the file as it is right now.
+#[tauri::command]
+pub fn set_script_pinned(db: State<'_, Database>, script_id: i64, pinned: bool) -> Result<Script, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let before = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    if pinned {
+        approve(&conn, script_id)?;
+    }
+    queries::set_script_pinned(&conn, script_id, pinned).map_err(|e| e.to_string())?;
+    // Scheduled runs of pinned scripts go through `conduit --run-script` so
+    // the hash is verified when the task fires
+    if before.pinned != pinned {
+        scheduler::reinstall_script_schedules(&conn, script_id)?;
+    }
+    let after = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    let approved = queries::get_script_health(&conn, script_id)
+        .map_err(|e| e.to_string())?
+        .and_then(|h| h.approved_hash);
+    audit::log(
+        &conn,
+        "script.pin",
+        "script",
+        Some(script_id),
+        Some(&after.name),
+        Some(&serde_json::json!({ "pinned": before.pinned })),
+        Some(&serde_json::json!({ "pinned": after.pinned, "approvedHash": approved })),
+    );
+    Ok(after)
+}
+
+/// Files whose content matches the last known hash of a missing script.
+#[tauri::command]
+pub fn find_moved_script(db: State<'_, Database>, script_id: i64) -> Result<Vec<String>, String> {
+    let (script, health, roots) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+        let health = queries::get_script_health(&conn, script_id).map_err(|e| e.to_string())?;
+        let roots = relocation_roots(&conn, &script)?;
+        (script, health, roots)
+    };
+    let Some((Some(hash), size)) = health.map(|h| (h.hash, h.size)) else {
+        return Err(format!("No earlier version of \"{}\" was recorded to search for", script.name));
+    };
+
+    let mut visited = HashSet::new();
+    let mut candidates = Vec::new();
+    let mut examined = 0;
+    for root in roots {
+        let files = WalkDir::new(&root)
+            .max_depth(RELOCATE_MAX_DEPTH)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| !is_hidden(e))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file());
+        for entry in files {
+            if !visited.insert(entry.path().to_path_buf()) {
+                continue;
+            }
+            examined += 1;
+            if examined > RELOCATE_MAX_FILES {
+                return Ok(candidates);
+            }
+            // Size first so only plausible files get hashed
+            let same_size = size.is_none_or(|s| entry.metadata().is_ok_and(|m| m.len() as i64 == s));
+            if same_size && entry.path() != Path::new(&script.path) && hash_file(entry.path()).is_ok_and(|h| h == hash) {
+                candidates.push(entry.path().to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(candidates)
+}
+
+/// Point a script at its new location and re-check it.
+#[tauri::command]
+pub fn relocate_script(db: State<'_, Database>, script_id: i64, path: String) -> Result<Script, String> {
+    if !Path::new(&path).is_file() {
+        return Err(format!("{} is not a file", path));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    config::ensure_editable(&existing)?;
+    let updated = queries::update_script(
+        &conn,
+        script_id,
+        &UpdateScript {
+            name: None,
+            path: Some(path),
+            description: None,
+            category_id: None,
+            color: None,
+            run_as_admin: None,
+            timeout_seconds: None,
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    check_script(&conn, &updated)?;
+    audit::log(&conn, "script.relocate", "script", Some(script_id), Some(&updated.name), Some(&existing), Some(&updated));
+    Ok(updated)
+}
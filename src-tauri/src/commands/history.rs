@@ -1,6 +1,7 @@
+use crate::commands::audit;
 use crate::db::queries;
 use crate::db::Database;
-use crate::models::RunRecord;
+use crate::models::{CategoryStats, FailingScript, RunListItem, RunRecord, RunSearchFilter, ScriptStats};
 use tauri::State;
 
 #[tauri::command]
@@ -18,5 +19,47 @@ pub fn get_latest_run(db: State<'_, Database>, script_id: i64) -> Result<Option<
 #[tauri::command]
 pub fn clear_history(db: State<'_, Database>, script_id: i64) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::clear_run_history(&conn, script_id).map_err(|e| e.to_string())
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    queries::clear_run_history(&conn, script_id).map_err(|e| e.to_string())?;
+    audit::log::<()>(&conn, "history.clear", "script", Some(script_id), Some(&script.name), None, None);
+    Ok(())
+}
+
+/// Runs across all scripts, newest first, for the dashboard. Output is omitted;
+/// fetch a script's history for the full record.
+#[tauri::command]
+pub fn get_recent_runs(
+    db: State<'_, Database>,
+    filter: Option<RunSearchFilter>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<RunListItem>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_recent_runs(
+        &conn,
+        &filter.unwrap_or_default(),
+        limit.unwrap_or(50).clamp(1, 500),
+        offset.unwrap_or(0).max(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Per-script success rate and durations. `since` is a `YYYY-MM-DD HH:MM:SS`
+/// UTC timestamp; omit it for all-time figures.
+#[tauri::command]
+pub fn get_script_stats(db: State<'_, Database>, since: Option<String>) -> Result<Vec<ScriptStats>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_stats(&conn, since.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_failing_scripts(db: State<'_, Database>) -> Result<Vec<FailingScript>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_failing_scripts(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_category_stats(db: State<'_, Database>, since: Option<String>) -> Result<Vec<CategoryStats>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_category_stats(&conn, since.as_deref()).map_err(|e| e.to_string())
 }
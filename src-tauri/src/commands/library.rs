@@ -0,0 +1,838 @@
+use crate::commands::{audit, header, lint, scheduler, settings};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{
+    LibraryImportOptions, LibraryImportPlan, NewSchedule, NewScript, PlannedCategory, PlannedScript, Settings,
+    UpdateScript, UpdateSettings,
+};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::State;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bumped whenever the manifest changes incompatibly. Older manifests keep
+/// importing; newer ones are rejected.
+const LIBRARY_FORMAT_VERSION: u32 = 1;
+
+/// Manifest file name inside a .zip bundle.
+const BUNDLE_MANIFEST: &str = "conduit-library.json";
+
+/// Folder inside a .zip bundle holding the script files.
+const BUNDLE_SCRIPTS_DIR: &str = "scripts";
+
+const DEFAULT_CATEGORY_COLOR: &str = "#00d4aa";
+
+// The manifest is a user-facing file format, so it keeps snake_case keys
+// rather than the camelCase used over IPC.
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LibraryManifest {
+    version: u32,
+    exported_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    settings: Option<ManifestSettings>,
+    #[serde(default)]
+    categories: Vec<ManifestCategory>,
+    #[serde(default)]
+    scripts: Vec<ManifestScript>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestCategory {
+    name: String,
+    #[serde(default = "default_category_color")]
+    color: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestScript {
+    name: String,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default = "default_category_name")]
+    category: String,
+    #[serde(default = "default_category_color")]
+    color: String,
+    #[serde(default)]
+    run_as_admin: bool,
+    /// Entry name of the bundled copy, when exported as a .zip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(default)]
+    schedules: Vec<ManifestSchedule>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestSchedule {
+    schedule_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weekday: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    interval_seconds: Option<i64>,
+    #[serde(default = "default_true")]
+    enabled: bool,
+}
+
+/// Portable settings. Secrets such as the SMTP password are never exported.
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct ManifestSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    editor_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    theme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    powershell_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smtp_host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smtp_port: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smtp_security: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smtp_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smtp_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digest_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retention_keep_runs: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retention_keep_days: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retention_failure_days: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_retention_days: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lint_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    elevation_method: Option<String>,
+}
+
+impl From<&Settings> for ManifestSettings {
+    fn from(s: &Settings) -> Self {
+        ManifestSettings {
+            editor_path: Some(s.editor_path.clone()),
+            theme: Some(s.theme.clone()),
+            powershell_version: Some(s.powershell_version.clone()),
+            smtp_host: Some(s.smtp_host.clone()),
+            smtp_port: Some(s.smtp_port),
+            smtp_security: Some(s.smtp_security.clone()),
+            smtp_username: Some(s.smtp_username.clone()),
+            smtp_from: Some(s.smtp_from.clone()),
+            digest_time: Some(s.digest_time.clone()),
+            retention_keep_runs: Some(s.retention_keep_runs),
+            retention_keep_days: Some(s.retention_keep_days),
+            retention_failure_days: Some(s.retention_failure_days),
+            log_retention_days: Some(s.log_retention_days),
+            lint_policy: Some(s.lint_policy.clone()),
+            elevation_method: Some(s.elevation_method.clone()),
+        }
+    }
+}
+
+impl From<ManifestSettings> for UpdateSettings {
+    fn from(s: ManifestSettings) -> Self {
+        UpdateSettings {
+            editor_path: s.editor_path,
+            theme: s.theme,
+            powershell_version: s.powershell_version,
+            smtp_host: s.smtp_host,
+            smtp_port: s.smtp_port,
+            smtp_security: s.smtp_security,
+            smtp_username: s.smtp_username,
+            smtp_password: None,
+            smtp_from: s.smtp_from,
+            digest_time: s.digest_time,
+            retention_keep_runs: s.retention_keep_runs,
+            retention_keep_days: s.retention_keep_days,
+            retention_failure_days: s.retention_failure_days,
+            log_retention_days: s.log_retention_days,
+            lint_policy: s.lint_policy,
+            elevation_method: s.elevation_method,
+        }
+    }
+}
+
+fn default_category_name() -> String {
+    "General".to_string()
+}
+
+fn default_category_color() -> String {
+    DEFAULT_CATEGORY_COLOR.to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Default home for script files Conduit owns, e.g. ones extracted from a bundle.
+pub(crate) fn get_scripts_dir() -> PathBuf {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".conduit");
+    path.push("scripts");
+    path
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LibraryFormat {
+    Json,
+    Toml,
+    Zip,
+}
+
+fn library_format(path: &Path) -> Result<LibraryFormat, String> {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("json") => Ok(LibraryFormat::Json),
+        Some("toml") => Ok(LibraryFormat::Toml),
+        Some("zip") => Ok(LibraryFormat::Zip),
+        _ => Err("Library files must end in .json, .toml or .zip".to_string()),
+    }
+}
+
+// ──────────────────────────────────────────────
+// Export
+// ──────────────────────────────────────────────
+
+/// Build the manifest from the database. With `bundle` set, each existing
+/// script file gets a unique entry name under `scripts/`; the returned pairs
+/// map entry names to source paths.
+fn build_manifest(conn: &Connection, bundle: bool) -> Result<(LibraryManifest, Vec<(String, String)>), String> {
+    let categories = queries::get_all_categories(conn).map_err(|e| e.to_string())?;
+    let scripts = queries::get_all_scripts(conn).map_err(|e| e.to_string())?;
+    let settings = queries::get_settings(conn).map_err(|e| e.to_string())?;
+    let category_names: HashMap<i64, String> = categories.iter().map(|c| (c.id, c.name.clone())).collect();
+
+    let mut files = Vec::new();
+    let mut entry_names = HashSet::new();
+    let mut manifest_scripts = Vec::with_capacity(scripts.len());
+    for script in scripts {
+        let file = if bundle && Path::new(&script.path).is_file() {
+            let entry = unique_entry_name(&script.path, &mut entry_names);
+            files.push((entry.clone(), script.path.clone()));
+            Some(entry)
+        } else {
+            None
+        };
+        let schedules = queries::get_schedules_for_script(conn, script.id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|s| ManifestSchedule {
+                schedule_type: s.schedule_type,
+                time: s.time,
+                weekday: s.weekday,
+                interval_seconds: s.interval_seconds,
+                enabled: s.enabled,
+            })
+            .collect();
+        manifest_scripts.push(ManifestScript {
+            name: script.name,
+            path: script.path,
+            description: script.description,
+            category: category_names
+                .get(&script.category_id)
+                .cloned()
+                .unwrap_or_else(default_category_name),
+            color: script.color,
+            run_as_admin: script.run_as_admin,
+            file,
+            schedules,
+        });
+    }
+
+    let manifest = LibraryManifest {
+        version: LIBRARY_FORMAT_VERSION,
+        exported_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        settings: Some(ManifestSettings::from(&settings)),
+        categories: categories
+            .into_iter()
+            .map(|c| ManifestCategory {
+                name: c.name,
+                color: c.color,
+            })
+            .collect(),
+        scripts: manifest_scripts,
+    };
+    Ok((manifest, files))
+}
+
+fn unique_entry_name(path: &str, taken: &mut HashSet<String>) -> String {
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "script".to_string());
+    let mut candidate = file_name.clone();
+    let mut n = 1;
+    while !taken.insert(candidate.clone()) {
+        candidate = numbered_name(&file_name, n);
+        n += 1;
+    }
+    format!("{}/{}", BUNDLE_SCRIPTS_DIR, candidate)
+}
+
+/// `backup.sh` -> `backup-1.sh`
+pub(crate) fn numbered_name(file_name: &str, n: usize) -> String {
+    let path = Path::new(file_name);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => format!("{}-{}.{}", stem.to_string_lossy(), n, ext.to_string_lossy()),
+        _ => format!("{}-{}", file_name, n),
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(path: &str) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).map(|m| m.permissions().mode() & 0o777).unwrap_or(0o644)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_path: &str) -> u32 {
+    0o644
+}
+
+fn write_bundle(path: &Path, manifest: &LibraryManifest, files: &[(String, String)]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file(BUNDLE_MANIFEST, options).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(&mut zip, manifest).map_err(|e| e.to_string())?;
+
+    for (entry, source) in files {
+        zip.start_file(entry.as_str(), options.unix_permissions(file_mode(source)))
+            .map_err(|e| e.to_string())?;
+        let mut src = File::open(source).map_err(|e| format!("Failed to read {}: {}", source, e))?;
+        std::io::copy(&mut src, &mut zip).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Export scripts, categories, schedules and settings. The format follows the
+/// extension: .json or .toml write a manifest only, .zip also bundles the
+/// script files. Returns the number of scripts exported.
+#[tauri::command]
+pub fn export_library(db: State<'_, Database>, path: String) -> Result<i64, String> {
+    let path = PathBuf::from(path);
+    let format = library_format(&path)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (manifest, files) = build_manifest(&conn, format == LibraryFormat::Zip)?;
+    drop(conn);
+
+    match format {
+        LibraryFormat::Json => {
+            let text = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+            std::fs::write(&path, text).map_err(|e| e.to_string())?;
+        }
+        LibraryFormat::Toml => {
+            let text = toml::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+            std::fs::write(&path, text).map_err(|e| e.to_string())?;
+        }
+        LibraryFormat::Zip => write_bundle(&path, &manifest, &files)?,
+    }
+    Ok(manifest.scripts.len() as i64)
+}
+
+// ──────────────────────────────────────────────
+// Import
+// ──────────────────────────────────────────────
+
+struct LoadedLibrary {
+    manifest: LibraryManifest,
+    bundle: Option<ZipArchive<File>>,
+}
+
+impl LoadedLibrary {
+    fn open(path: &Path) -> Result<Self, String> {
+        let format = library_format(path)?;
+        let (manifest, bundle) = match format {
+            LibraryFormat::Json => {
+                let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+                let manifest = serde_json::from_str(&text).map_err(|e| format!("Invalid library manifest: {}", e))?;
+                (manifest, None)
+            }
+            LibraryFormat::Toml => {
+                let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+                let manifest = toml::from_str(&text).map_err(|e| format!("Invalid library manifest: {}", e))?;
+                (manifest, None)
+            }
+            LibraryFormat::Zip => {
+                let file = File::open(path).map_err(|e| e.to_string())?;
+                let mut archive = ZipArchive::new(file).map_err(|e| format!("Invalid library bundle: {}", e))?;
+                let manifest: LibraryManifest = {
+                    let entry = archive
+                        .by_name(BUNDLE_MANIFEST)
+                        .map_err(|_| format!("Bundle has no {}", BUNDLE_MANIFEST))?;
+                    serde_json::from_reader(entry).map_err(|e| format!("Invalid library manifest: {}", e))?
+                };
+                (manifest, Some(archive))
+            }
+        };
+        if manifest.version > LIBRARY_FORMAT_VERSION {
+            return Err(format!(
+                "Library format version {} is newer than this version of Conduit supports ({})",
+                manifest.version, LIBRARY_FORMAT_VERSION
+            ));
+        }
+        Ok(LoadedLibrary { manifest, bundle })
+    }
+
+    /// Contents and Unix mode of a bundled script file.
+    fn bundled_file(&mut self, entry: &str) -> Result<(Vec<u8>, Option<u32>), String> {
+        let archive = self
+            .bundle
+            .as_mut()
+            .ok_or_else(|| format!("{} refers to a bundled file but this is not a bundle", entry))?;
+        let mut file = archive
+            .by_name(entry)
+            .map_err(|_| format!("Bundle is missing {}", entry))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+        Ok((data, file.unix_mode()))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ConflictPolicy {
+    Rename,
+    Overwrite,
+    Skip,
+}
+
+impl ConflictPolicy {
+    fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.unwrap_or("rename") {
+            "rename" => Ok(ConflictPolicy::Rename),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "skip" => Ok(ConflictPolicy::Skip),
+            other => Err(format!("Unknown conflict policy: {}", other)),
+        }
+    }
+}
+
+/// The plan for one script plus what's needed to carry it out.
+struct ScriptStep {
+    planned: PlannedScript,
+    existing_id: Option<i64>,
+    file: Option<(Vec<u8>, Option<u32>)>,
+}
+
+fn plan_import(
+    conn: &Connection,
+    library: &mut LoadedLibrary,
+    options: &LibraryImportOptions,
+) -> Result<(LibraryImportPlan, Vec<ScriptStep>), String> {
+    let policy = ConflictPolicy::parse(options.on_conflict.as_deref())?;
+    let scripts_dir = options
+        .scripts_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(get_scripts_dir);
+
+    let existing_categories: HashSet<String> = queries::get_all_categories(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    let existing_scripts: HashMap<String, (i64, bool)> = queries::get_all_scripts(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| (s.path, (s.id, s.config_managed)))
+        .collect();
+
+    // Categories are matched by name; ones only referenced by scripts are
+    // created with the default color.
+    let mut categories: Vec<PlannedCategory> = Vec::new();
+    let referenced = library
+        .manifest
+        .categories
+        .iter()
+        .map(|c| c.name.clone())
+        .chain(library.manifest.scripts.iter().map(|s| s.category.clone()));
+    for name in referenced {
+        if categories.iter().any(|c| c.name == name) {
+            continue;
+        }
+        let action = if existing_categories.contains(&name) { "existing" } else { "create" };
+        categories.push(PlannedCategory {
+            name,
+            action: action.to_string(),
+        });
+    }
+
+    let mut claimed_targets: HashSet<PathBuf> = HashSet::new();
+    let mut steps = Vec::with_capacity(library.manifest.scripts.len());
+    for index in 0..library.manifest.scripts.len() {
+        let mut warnings = Vec::new();
+        let mut file_action = None;
+        let mut file = None;
+
+        // Cloned up front: reading from the bundle needs `library` mutably
+        let file_entry = library.manifest.scripts[index].file.clone();
+        let source_path = library.manifest.scripts[index].path.clone();
+        let target = match &file_entry {
+            Some(entry) => {
+                let (data, mode) = library.bundled_file(entry)?;
+                let file_name = Path::new(entry)
+                    .file_name()
+                    .ok_or_else(|| format!("Invalid bundle entry: {}", entry))?
+                    .to_string_lossy()
+                    .to_string();
+                let mut target = scripts_dir.join(&file_name);
+                let identical = std::fs::read(&target).map(|d| d == data).unwrap_or(false);
+                let action = if identical {
+                    "identical"
+                } else if !target.exists() && !claimed_targets.contains(&target) {
+                    "write"
+                } else {
+                    match policy {
+                        ConflictPolicy::Overwrite => "overwrite",
+                        ConflictPolicy::Skip => "keep",
+                        ConflictPolicy::Rename => {
+                            let mut n = 1;
+                            while target.exists() || claimed_targets.contains(&target) {
+                                target = scripts_dir.join(numbered_name(&file_name, n));
+                                n += 1;
+                            }
+                            "write"
+                        }
+                    }
+                };
+                if action == "write" || action == "overwrite" {
+                    file = Some((data, mode));
+                }
+                file_action = Some(action.to_string());
+                claimed_targets.insert(target.clone());
+                target
+            }
+            None => {
+                let target = PathBuf::from(&source_path);
+                if !target.exists() {
+                    warnings.push(format!("{} does not exist on this machine", source_path));
+                }
+                target
+            }
+        };
+        let target_path = target.to_string_lossy().to_string();
+        let source = &library.manifest.scripts[index];
+        let schedule_count = source.schedules.len();
+
+        let existing = existing_scripts.get(&target_path).copied();
+        let existing_id = existing.map(|(id, _)| id);
+        let action = match (existing, policy) {
+            (None, _) => "create",
+            (Some((_, true)), _) => {
+                warnings.push("Managed by the Conduit config file; left unchanged".to_string());
+                "skip"
+            }
+            (Some(_), ConflictPolicy::Overwrite) => "update",
+            (Some(_), _) => "skip",
+        };
+        if action != "create" && schedule_count > 0 {
+            warnings.push("Schedules are only recreated for newly added scripts".to_string());
+        }
+
+        let schedule_specs: Vec<String> = if action == "create" && !options.skip_schedules {
+            source.schedules.iter().map(schedule_spec).collect()
+        } else {
+            Vec::new()
+        };
+        steps.push(ScriptStep {
+            planned: PlannedScript {
+                name: source.name.clone(),
+                category: source.category.clone(),
+                source_path,
+                target_path,
+                action: action.to_string(),
+                file_action,
+                run_as_admin: source.run_as_admin,
+                schedules: schedule_specs.len() as i64,
+                schedule_specs,
+                warnings,
+            },
+            existing_id,
+            file,
+        });
+    }
+
+    let settings_changes = match &library.manifest.settings {
+        Some(incoming) => {
+            let current = queries::get_settings(conn).map_err(|e| e.to_string())?;
+            changed_settings(&ManifestSettings::from(&current), incoming)
+        }
+        None => Vec::new(),
+    };
+
+    let plan = LibraryImportPlan {
+        version: library.manifest.version,
+        categories,
+        scripts: steps.iter().map(|s| s.planned.clone()).collect(),
+        settings_changes,
+        applied: false,
+    };
+    Ok((plan, steps))
+}
+
+fn schedule_spec(schedule: &ManifestSchedule) -> String {
+    let spec = match schedule.schedule_type.as_str() {
+        "daily" => format!("daily {}", schedule.time.as_deref().unwrap_or("")),
+        "weekly" => format!(
+            "weekly {} {}",
+            schedule
+                .weekday
+                .and_then(|d| header::WEEKDAYS.get(d as usize))
+                .unwrap_or(&"?"),
+            schedule.time.as_deref().unwrap_or("")
+        ),
+        _ => format!("every {}s", schedule.interval_seconds.unwrap_or(0)),
+    };
+    if schedule.enabled {
+        spec
+    } else {
+        format!("{} (disabled)", spec)
+    }
+}
+
+/// Names of the settings the manifest would change.
+fn changed_settings(current: &ManifestSettings, incoming: &ManifestSettings) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(current)), Ok(serde_json::Value::Object(incoming))) =
+        (serde_json::to_value(current), serde_json::to_value(incoming))
+    else {
+        return Vec::new();
+    };
+    incoming
+        .into_iter()
+        .filter(|(key, value)| current.get(key) != Some(value))
+        .map(|(key, _)| key)
+        .collect()
+}
+
+/// Write a bundled file to a temp sibling of `path`, to be renamed into place
+/// once the rest of the import has succeeded.
+fn stage_script_file(path: &Path, data: &[u8], mode: Option<u32>) -> Result<PathBuf, String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = dir.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+    let written = (|| {
+        let mut file = File::create(&tmp).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        file.write_all(data).map_err(|e| e.to_string())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = mode.map(|m| m & 0o777).unwrap_or(0o644);
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(mode)).map_err(|e| e.to_string())?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        Ok(())
+    })();
+    match written {
+        Ok(()) => Ok(tmp),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+/// Files are staged first and the database changes made in one transaction,
+/// so a failure leaves neither half-extracted files nor half-created scripts.
+/// Schedules are installed after the commit; failures there are warnings.
+fn apply_import(
+    conn: &Connection,
+    library: LoadedLibrary,
+    options: &LibraryImportOptions,
+    mut plan: LibraryImportPlan,
+    steps: Vec<ScriptStep>,
+) -> Result<LibraryImportPlan, String> {
+    let manifest = library.manifest;
+    let settings_update = match manifest.settings {
+        Some(settings) if options.apply_settings => {
+            let mut update = UpdateSettings::from(settings);
+            settings::validate_settings(&mut update)?;
+            Some(update)
+        }
+        _ => None,
+    };
+
+    let mut staged: Vec<(usize, PathBuf)> = Vec::new();
+    let discard = |staged: &[(usize, PathBuf)]| {
+        for (_, tmp) in staged {
+            let _ = std::fs::remove_file(tmp);
+        }
+    };
+    for (index, step) in steps.iter().enumerate() {
+        if let Some((data, mode)) = &step.file {
+            match stage_script_file(Path::new(&plan.scripts[index].target_path), data, *mode) {
+                Ok(tmp) => staged.push((index, tmp)),
+                Err(e) => {
+                    discard(&staged);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    let lint_policy_before = queries::get_settings(conn).map_err(|e| e.to_string())?.lint_policy;
+    let mut schedules: Vec<(usize, NewSchedule, bool)> = Vec::new();
+    let recorded = (|| {
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        record_import(&tx, &manifest.categories, manifest.scripts, &plan, steps, options, &mut schedules)?;
+        if let Some(ref update) = settings_update {
+            queries::upsert_settings(&tx, update).map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    })();
+    if let Err(e) = recorded {
+        discard(&staged);
+        return Err(e);
+    }
+
+    for (index, tmp) in staged {
+        let planned = &mut plan.scripts[index];
+        if let Err(e) = std::fs::rename(&tmp, &planned.target_path) {
+            let _ = std::fs::remove_file(&tmp);
+            planned.warnings.push(format!("Failed to write {}: {}", planned.target_path, e));
+        }
+    }
+    if let Some(ref policy) = settings_update.as_ref().and_then(|u| u.lint_policy.clone()) {
+        if let Err(e) = lint::reinstall_for_global_policy(conn, &lint_policy_before, policy) {
+            plan.settings_changes.push(format!("Failed to update scheduled tasks for the lint policy: {}", e));
+        }
+    }
+    for (index, schedule, enabled) in schedules {
+        // A platform without a working scheduler shouldn't abort the rest
+        // of the import
+        if let Err(e) = scheduler::install_schedule(conn, &schedule, enabled) {
+            plan.scripts[index].warnings.push(format!("Failed to recreate schedule: {}", e));
+        }
+    }
+
+    plan.applied = true;
+    Ok(plan)
+}
+
+/// The database side of an import: categories, new and updated scripts.
+/// Schedules to install for the new scripts are collected into `schedules`.
+fn record_import(
+    conn: &Connection,
+    manifest_categories: &[ManifestCategory],
+    manifest_scripts: Vec<ManifestScript>,
+    plan: &LibraryImportPlan,
+    steps: Vec<ScriptStep>,
+    options: &LibraryImportOptions,
+    schedules: &mut Vec<(usize, NewSchedule, bool)>,
+) -> Result<(), String> {
+    let colors: HashMap<&str, &str> = manifest_categories
+        .iter()
+        .map(|c| (c.name.as_str(), c.color.as_str()))
+        .collect();
+    let mut category_ids: HashMap<String, i64> = queries::get_all_categories(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| (c.name, c.id))
+        .collect();
+    for category in plan.categories.iter().filter(|c| c.action == "create") {
+        let color = colors.get(category.name.as_str()).copied().unwrap_or(DEFAULT_CATEGORY_COLOR);
+        let created = queries::insert_category(conn, &category.name, color).map_err(|e| e.to_string())?;
+        category_ids.insert(created.name, created.id);
+    }
+
+    for (index, (step, source)) in steps.into_iter().zip(manifest_scripts).enumerate() {
+        let planned = &plan.scripts[index];
+        let category_id = category_ids.get(&planned.category).copied().unwrap_or(1);
+
+        match (planned.action.as_str(), step.existing_id) {
+            ("create", _) => {
+                let script = queries::insert_script(
+                    conn,
+                    &NewScript {
+                        name: source.name,
+                        path: planned.target_path.clone(),
+                        description: source.description,
+                        category_id,
+                        color: source.color,
+                        run_as_admin: source.run_as_admin,
+                    },
+                )
+                .map_err(|e| e.to_string())?;
+
+                if options.skip_schedules {
+                    continue;
+                }
+                for schedule in source.schedules {
+                    let new_schedule = NewSchedule {
+                        script_id: script.id,
+                        schedule_type: schedule.schedule_type,
+                        time: schedule.time,
+                        weekday: schedule.weekday,
+                        interval_seconds: schedule.interval_seconds,
+                    };
+                    schedules.push((index, new_schedule, schedule.enabled));
+                }
+            }
+            ("update", Some(id)) => {
+                queries::update_script(
+                    conn,
+                    id,
+                    &UpdateScript {
+                        name: Some(source.name),
+                        path: None,
+                        description: source.description,
+                        category_id: Some(category_id),
+                        color: Some(source.color),
+                        run_as_admin: Some(source.run_as_admin),
+                        timeout_seconds: None,
+                    },
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Show what importing a library file would do without changing anything.
+#[tauri::command]
+pub fn preview_library_import(
+    db: State<'_, Database>,
+    path: String,
+    options: Option<LibraryImportOptions>,
+) -> Result<LibraryImportPlan, String> {
+    let mut library = LoadedLibrary::open(Path::new(&path))?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (plan, _) = plan_import(&conn, &mut library, &options.unwrap_or_default())?;
+    Ok(plan)
+}
+
+/// Import a library file and return the plan that was carried out, including
+/// any warnings raised while recreating schedules.
+#[tauri::command]
+pub fn import_library(
+    db: State<'_, Database>,
+    path: String,
+    options: Option<LibraryImportOptions>,
+) -> Result<LibraryImportPlan, String> {
+    let options = options.unwrap_or_default();
+    let mut library = LoadedLibrary::open(Path::new(&path))?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (plan, steps) = plan_import(&conn, &mut library, &options)?;
+    let plan = apply_import(&conn, library, &options, plan, steps)?;
+    audit::log::<LibraryImportPlan>(&conn, "library.import", "library", None, Some(&path), None, Some(&plan));
+    Ok(plan)
+}
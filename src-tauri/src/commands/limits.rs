@@ -0,0 +1,273 @@
+use crate::commands::{audit, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewResourceLimits, ResourceLimitSupport, ResourceLimits};
+use tauri::State;
+#[cfg(unix)]
+use {crate::commands::elevation, std::path::PathBuf};
+
+/// `conduit --with-limits key=value... -- command...` applies rlimits and
+/// priorities to itself, then execs the command.
+pub const WITH_LIMITS_FLAG: &str = "--with-limits";
+
+const IO_CLASSES: [&str; 2] = ["best-effort", "idle"];
+
+/// The largest address space limit whose size in bytes fits an i64.
+const MAX_ADDRESS_SPACE_MB: i64 = i64::MAX / (1024 * 1024);
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type Resource = libc::c_int;
+
+fn validate(limits: &NewResourceLimits) -> Result<(), String> {
+    if cfg!(target_os = "windows") {
+        return Err("Resource limits are only supported on Unix".to_string());
+    }
+    let sizes = [
+        ("CPU time", limits.cpu_seconds),
+        ("Address space", limits.address_space_mb),
+        ("Open files", limits.open_files),
+        ("Memory", limits.memory_max_mb),
+        ("CPU quota", limits.cpu_quota_percent),
+    ];
+    for (name, value) in sizes {
+        if value.is_some_and(|v| v <= 0) {
+            return Err(format!("{} limit must be positive", name));
+        }
+    }
+    if limits.address_space_mb.is_some_and(|mb| mb > MAX_ADDRESS_SPACE_MB) {
+        return Err(format!("Address space limit must be at most {} MB", MAX_ADDRESS_SPACE_MB));
+    }
+    if limits.nice.is_some_and(|n| !(0..=19).contains(&n)) {
+        return Err("Nice must be between 0 and 19".to_string());
+    }
+    if let Some(ref class) = limits.io_class {
+        if !IO_CLASSES.contains(&class.as_str()) {
+            return Err(format!("Unknown IO class: {}", class));
+        }
+        if !cfg!(target_os = "linux") {
+            return Err("IO priority is only supported on Linux".to_string());
+        }
+    }
+    match (limits.io_class.as_deref(), limits.io_priority) {
+        (None, Some(_)) => Err("Set an IO class to use an IO priority".to_string()),
+        (Some("idle"), Some(_)) => Err("The idle IO class has no priority levels".to_string()),
+        (_, Some(p)) if !(0..=7).contains(&p) => Err("IO priority must be between 0 and 7".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// systemd-run, when a user manager on the unified cgroup hierarchy can put
+/// a run in a transient scope with memory and CPU quota limits.
+#[cfg(unix)]
+fn systemd_run() -> Option<PathBuf> {
+    let cgroup2 = std::path::Path::new("/sys/fs/cgroup/cgroup.controllers").exists();
+    let user_manager = std::env::var_os("XDG_RUNTIME_DIR")
+        .is_some_and(|dir| std::path::Path::new(&dir).join("systemd/private").exists());
+    if cgroup2 && user_manager {
+        elevation::find_tool("systemd-run")
+    } else {
+        None
+    }
+}
+
+/// The `--with-limits` arguments for the rlimits and priorities in `limits`.
+#[cfg(unix)]
+fn spec(limits: &ResourceLimits) -> Vec<String> {
+    [
+        limits.cpu_seconds.map(|s| format!("cpu={}", s)),
+        limits.address_space_mb.map(|mb| format!("as={}", mb.saturating_mul(1024 * 1024))),
+        limits.open_files.map(|n| format!("nofile={}", n)),
+        limits.nice.map(|n| format!("nice={}", n)),
+        limits.io_class.as_ref().map(|class| match limits.io_priority {
+            Some(p) => format!("io={}:{}", class, p),
+            None => format!("io={}", class),
+        }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Argv to put in front of a command so it runs under `limits`; empty when
+/// nothing is limited. Memory and CPU quota go through a systemd scope and
+/// fail the run when that isn't available, rather than running unlimited.
+#[cfg(unix)]
+pub(crate) fn wrapper(limits: &ResourceLimits) -> Result<Vec<String>, String> {
+    let mut argv = Vec::new();
+    let properties: Vec<String> = [
+        limits.memory_max_mb.map(|mb| format!("MemoryMax={}M", mb)),
+        limits.cpu_quota_percent.map(|p| format!("CPUQuota={}%", p)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !properties.is_empty() {
+        let systemd_run = systemd_run()
+            .ok_or_else(|| "Memory and CPU quota limits need systemd-run in a user session with cgroup v2".to_string())?;
+        argv.extend([
+            systemd_run.to_string_lossy().to_string(),
+            "--user".to_string(),
+            "--scope".to_string(),
+            "--quiet".to_string(),
+            "--collect".to_string(),
+        ]);
+        for property in properties {
+            argv.extend(["-p".to_string(), property]);
+        }
+    }
+    let spec = spec(limits);
+    if !spec.is_empty() {
+        let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the Conduit executable: {}", e))?;
+        argv.extend([exe.to_string_lossy().to_string(), WITH_LIMITS_FLAG.to_string()]);
+        argv.extend(spec);
+        argv.push("--".to_string());
+    }
+    Ok(argv)
+}
+
+/// `[Service]` lines applying `limits` to a scheduled run's systemd unit.
+#[cfg(target_os = "linux")]
+pub(crate) fn unit_directives(limits: &ResourceLimits) -> Vec<String> {
+    [
+        limits.cpu_seconds.map(|s| format!("LimitCPU={}", s)),
+        limits.address_space_mb.map(|mb| format!("LimitAS={}M", mb)),
+        limits.open_files.map(|n| format!("LimitNOFILE={}", n)),
+        limits.nice.map(|n| format!("Nice={}", n)),
+        limits.io_class.as_ref().map(|class| format!("IOSchedulingClass={}", class)),
+        limits.io_priority.map(|p| format!("IOSchedulingPriority={}", p)),
+        limits.memory_max_mb.map(|mb| format!("MemoryMax={}M", mb)),
+        limits.cpu_quota_percent.map(|p| format!("CPUQuota={}%", p)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(unix)]
+fn last_os_error(what: &str) -> String {
+    format!("Failed to set {}: {}", what, std::io::Error::last_os_error())
+}
+
+/// Lower both the soft and hard limit, never above the current hard limit.
+#[cfg(unix)]
+fn set_rlimit(resource: Resource, what: &str, value: &str) -> Result<(), String> {
+    let value: libc::rlim_t = value.parse().map_err(|_| format!("Invalid {} limit: {}", what, value))?;
+    let mut current = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    unsafe {
+        if libc::getrlimit(resource, &mut current) != 0 {
+            return Err(last_os_error(what));
+        }
+        let value = value.min(current.rlim_max);
+        let limit = libc::rlimit { rlim_cur: value, rlim_max: value };
+        if libc::setrlimit(resource, &limit) != 0 {
+            return Err(last_os_error(what));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(value: &str) -> Result<(), String> {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: u32 = 13;
+    let (class, level) = match value.split_once(':') {
+        Some((class, level)) => (class, level.parse().map_err(|_| format!("Invalid IO priority: {}", level))?),
+        None => (value, 4),
+    };
+    let class: libc::c_int = match class {
+        "best-effort" => 2,
+        "idle" => 3,
+        other => return Err(format!("Unknown IO class: {}", other)),
+    };
+    let ioprio: libc::c_int = (class << IOPRIO_CLASS_SHIFT) | level;
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) } != 0 {
+        return Err(last_os_error("IO priority"));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn apply_and_exec(args: &[String]) -> Result<std::convert::Infallible, String> {
+    use std::os::unix::process::CommandExt;
+    let split = args.iter().position(|a| a == "--").ok_or("Missing -- before the command")?;
+    let (spec, command) = (&args[..split], &args[split + 1..]);
+    let program = command.first().ok_or("No command to run")?;
+    for item in spec {
+        let (key, value) = item.split_once('=').ok_or_else(|| format!("Invalid limit: {}", item))?;
+        match key {
+            "cpu" => set_rlimit(libc::RLIMIT_CPU, "CPU time", value)?,
+            "as" => set_rlimit(libc::RLIMIT_AS, "address space", value)?,
+            "nofile" => set_rlimit(libc::RLIMIT_NOFILE, "open files", value)?,
+            "nice" => {
+                let nice: libc::c_int = value.parse().map_err(|_| format!("Invalid nice value: {}", value))?;
+                if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+                    return Err(last_os_error("nice"));
+                }
+            }
+            #[cfg(target_os = "linux")]
+            "io" => set_io_priority(value)?,
+            other => return Err(format!("Unknown limit: {}", other)),
+        }
+    }
+    let error = std::process::Command::new(program).args(&command[1..]).exec();
+    Err(format!("Failed to start {}: {}", program, error))
+}
+
+/// Entry point for `conduit --with-limits`. Only returns if the limits
+/// couldn't be applied or the command couldn't be started.
+#[cfg(unix)]
+pub fn exec_with_limits(args: &[String]) -> i32 {
+    match apply_and_exec(args) {
+        Ok(never) => match never {},
+        Err(e) => {
+            eprintln!("conduit: {}", e);
+            126
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_script_limits(db: State<'_, Database>, script_id: i64) -> Result<Option<ResourceLimits>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_limits(&conn, script_id).map_err(|e| e.to_string())
+}
+
+/// Set a script's limits, or with None remove them. Scheduled runs pick the
+/// change up from their regenerated systemd units on Linux, and elsewhere
+/// switch to or from the `--run-script` launcher that applies them.
+#[tauri::command]
+pub fn set_script_limits(
+    db: State<'_, Database>,
+    script_id: i64,
+    limits: Option<NewResourceLimits>,
+) -> Result<Option<ResourceLimits>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    let before = queries::get_script_limits(&conn, script_id).map_err(|e| e.to_string())?;
+    match limits {
+        Some(ref new) => {
+            validate(new)?;
+            queries::upsert_script_limits(&conn, script_id, new).map_err(|e| e.to_string())?;
+        }
+        None => queries::delete_script_limits(&conn, script_id).map_err(|e| e.to_string())?,
+    }
+    let after = queries::get_script_limits(&conn, script_id).map_err(|e| e.to_string())?;
+    if cfg!(unix) {
+        scheduler::reinstall_script_schedules(&conn, script_id)?;
+    }
+    audit::log(&conn, "script.limits_update", "script", Some(script_id), Some(&script.name), before.as_ref(), after.as_ref());
+    Ok(after)
+}
+
+#[tauri::command]
+pub fn get_resource_limit_support() -> ResourceLimitSupport {
+    ResourceLimitSupport {
+        #[cfg(unix)]
+        cgroups: systemd_run().is_some(),
+        #[cfg(target_os = "windows")]
+        cgroups: false,
+        io_priority: cfg!(target_os = "linux"),
+    }
+}
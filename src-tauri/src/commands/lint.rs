@@ -0,0 +1,365 @@
+use crate::commands::{audit, health, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{LintDiagnostic, LintReport, Script};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::Command;
+use tauri::{AppHandle, Emitter, State};
+
+const LINT_POLICIES: [&str; 3] = ["off", "warn", "block"];
+
+const SHELLCHECK_EXTENSIONS: [&str; 4] = ["sh", "bash", "ksh", "dash"];
+
+#[derive(Clone, Copy)]
+enum Linter {
+    ShellCheck,
+    ScriptAnalyzer,
+}
+
+impl Linter {
+    fn name(self) -> &'static str {
+        match self {
+            Linter::ShellCheck => "shellcheck",
+            Linter::ScriptAnalyzer => "PSScriptAnalyzer",
+        }
+    }
+}
+
+enum LintFailure {
+    /// The tool isn't installed; never a reason to block a run.
+    Unavailable(String),
+    Failed(String),
+}
+
+#[derive(Deserialize)]
+struct ShellCheckOutput {
+    comments: Vec<ShellCheckComment>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShellCheckComment {
+    line: i64,
+    end_line: Option<i64>,
+    column: i64,
+    end_column: Option<i64>,
+    level: String,
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AnalyzerRecord {
+    line: Option<i64>,
+    column: Option<i64>,
+    severity: String,
+    rule_name: String,
+    message: String,
+}
+
+pub(crate) fn validate_policy(policy: &str) -> Result<(), String> {
+    if LINT_POLICIES.contains(&policy) {
+        Ok(())
+    } else {
+        Err(format!("Unknown lint policy: {}", policy))
+    }
+}
+
+/// Shell scripts by extension, or extensionless files whose shebang names a
+/// POSIX-family shell.
+fn linter_for(path: &Path) -> Option<Linter> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if SHELLCHECK_EXTENSIONS.contains(&ext.as_str()) {
+        return Some(Linter::ShellCheck);
+    }
+    if ext == "ps1" || ext == "psm1" {
+        return Some(Linter::ScriptAnalyzer);
+    }
+    if !ext.is_empty() {
+        return None;
+    }
+    let file = std::fs::File::open(path).ok()?;
+    let first = BufReader::new(file).lines().next()?.ok()?;
+    let interpreter = shebang_interpreter(&first)?;
+    SHELLCHECK_EXTENSIONS
+        .contains(&interpreter)
+        .then_some(Linter::ShellCheck)
+}
+
+/// The interpreter's name from a shebang: `#!/bin/bash -e` gives `bash`, and
+/// `#!/usr/bin/env -S bash -e` the first word after env's flags.
+fn shebang_interpreter(line: &str) -> Option<&str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let program = words.next()?;
+    let program = program.rsplit('/').next().unwrap_or(program);
+    if program != "env" {
+        return Some(program);
+    }
+    let program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    Some(program.rsplit('/').next().unwrap_or(program))
+}
+
+fn spawn_failure(tool: &str, e: std::io::Error) -> LintFailure {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        LintFailure::Unavailable(format!("{} is not installed", tool))
+    } else {
+        LintFailure::Failed(format!("Failed to run {}: {}", tool, e))
+    }
+}
+
+fn run_shellcheck(path: &Path) -> Result<Vec<LintDiagnostic>, LintFailure> {
+    let output = Command::new("shellcheck")
+        .arg("--format=json1")
+        .arg(path)
+        .output()
+        .map_err(|e| spawn_failure("shellcheck", e))?;
+    // Exit code 1 just means "found issues"; only unparseable output is an error
+    let parsed: ShellCheckOutput = serde_json::from_slice(&output.stdout).map_err(|_| {
+        LintFailure::Failed(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    })?;
+    Ok(parsed
+        .comments
+        .into_iter()
+        .map(|c| LintDiagnostic {
+            tool: Linter::ShellCheck.name().to_string(),
+            line: c.line,
+            column: c.column,
+            end_line: c.end_line,
+            end_column: c.end_column,
+            level: c.level,
+            code: format!("SC{}", c.code),
+            message: c.message,
+        })
+        .collect())
+}
+
+fn run_script_analyzer(path: &Path, powershell_exe: &str) -> Result<Vec<LintDiagnostic>, LintFailure> {
+    // Severity is an enum that ConvertTo-Json would write as a number
+    let script = format!(
+        "$ErrorActionPreference = 'Stop'; Import-Module PSScriptAnalyzer; \
+         ConvertTo-Json -Compress -InputObject @(Invoke-ScriptAnalyzer -Path '{}' | ForEach-Object {{ \
+         [pscustomobject]@{{ Line = $_.Line; Column = $_.Column; Severity = $_.Severity.ToString(); \
+         RuleName = $_.RuleName; Message = $_.Message }} }})",
+        path.to_string_lossy().replace('\'', "''")
+    );
+    let output = Command::new(powershell_exe)
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .map_err(|e| spawn_failure(powershell_exe, e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        if stderr.contains("PSScriptAnalyzer") {
+            return Err(LintFailure::Unavailable(
+                "PSScriptAnalyzer is not installed (Install-Module PSScriptAnalyzer)".to_string(),
+            ));
+        }
+        return Err(LintFailure::Failed(stderr.trim().to_string()));
+    }
+    let records: Vec<AnalyzerRecord> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| LintFailure::Failed(format!("Unexpected PSScriptAnalyzer output: {}", e)))?;
+    Ok(records
+        .into_iter()
+        .map(|r| LintDiagnostic {
+            tool: Linter::ScriptAnalyzer.name().to_string(),
+            line: r.line.unwrap_or(0),
+            column: r.column.unwrap_or(0),
+            end_line: None,
+            end_column: None,
+            level: match r.severity.as_str() {
+                "Error" | "ParseError" => "error",
+                "Warning" => "warning",
+                _ => "info",
+            }
+            .to_string(),
+            code: r.rule_name,
+            message: r.message,
+        })
+        .collect())
+}
+
+fn run_linter(script: &Script, hash: Option<String>, powershell_exe: &str) -> LintReport {
+    let path = Path::new(&script.path);
+    let mut report = LintReport {
+        script_id: script.id,
+        tool: None,
+        status: "unsupported".to_string(),
+        hash,
+        message: None,
+        linted_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        errors: 0,
+        warnings: 0,
+        diagnostics: Vec::new(),
+    };
+    let Some(linter) = linter_for(path) else {
+        return report;
+    };
+    report.tool = Some(linter.name().to_string());
+    let result = match linter {
+        Linter::ShellCheck => run_shellcheck(path),
+        Linter::ScriptAnalyzer => run_script_analyzer(path, powershell_exe),
+    };
+    match result {
+        Ok(diagnostics) => {
+            report.status = if diagnostics.is_empty() { "ok" } else { "issues" }.to_string();
+            report.errors = diagnostics.iter().filter(|d| d.level == "error").count() as i64;
+            report.warnings = diagnostics.iter().filter(|d| d.level == "warning").count() as i64;
+            report.diagnostics = diagnostics;
+        }
+        Err(LintFailure::Unavailable(message)) => {
+            report.status = "unavailable".to_string();
+            report.message = Some(message);
+        }
+        Err(LintFailure::Failed(message)) => {
+            report.status = "failed".to_string();
+            report.message = Some(message);
+        }
+    }
+    report
+}
+
+/// Lint a script and store the result. Unless `force` is set, the stored
+/// result is reused while the file content is unchanged. The linter runs
+/// without holding the database lock.
+fn lint(db: &Database, script_id: i64, force: bool) -> Result<LintReport, String> {
+    let (script, powershell_exe, cached) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+        let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+        let cached = queries::get_lint_report(&conn, script_id).map_err(|e| e.to_string())?;
+        (script, crate::commands::powershell_exe(&settings.powershell_version), cached)
+    };
+    let hash = health::hash_file(Path::new(&script.path)).ok();
+    if let Some(cached) = cached {
+        // An unavailable tool may have been installed since
+        if !force && hash.is_some() && cached.hash == hash && cached.status != "unavailable" {
+            return Ok(cached);
+        }
+    }
+
+    let report = run_linter(&script, hash, powershell_exe);
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::save_lint_report(&conn, &report).map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// The script's own lint policy, or the global one when it has none.
+pub(crate) fn effective_policy(conn: &rusqlite::Connection, script: &Script) -> Result<String, String> {
+    match script.lint_policy {
+        Some(ref policy) => Ok(policy.clone()),
+        None => Ok(queries::get_settings(conn).map_err(|e| e.to_string())?.lint_policy),
+    }
+}
+
+/// Lint a script about to run, returning its effective policy with the
+/// report, or None when the policy is "off".
+fn lint_for_run(db: &Database, script_id: i64) -> Result<Option<(String, LintReport)>, String> {
+    let policy = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+        effective_policy(&conn, &script)?
+    };
+    if policy == "off" {
+        return Ok(None);
+    }
+    Ok(Some((policy, lint(db, script_id, false)?)))
+}
+
+/// With "block", lint errors stop the run; a missing linter never does.
+fn enforce_policy(policy: &str, report: &LintReport) -> Result<(), String> {
+    if policy == "block" && report.errors > 0 {
+        return Err(format!(
+            "{} reported {} error(s); fix them or relax the lint policy to run",
+            report.tool.as_deref().unwrap_or("The linter"),
+            report.errors
+        ));
+    }
+    Ok(())
+}
+
+/// Applies the script's lint policy (or the global one) before a run.
+pub(crate) fn pre_run_check(app: &AppHandle, db: &Database, script_id: i64) -> Result<(), String> {
+    let Some((policy, report)) = lint_for_run(db, script_id)? else {
+        return Ok(());
+    };
+    if !report.diagnostics.is_empty() {
+        let _ = app.emit("script-linted", &report);
+    }
+    enforce_policy(&policy, &report)
+}
+
+/// `pre_run_check` for `conduit --run-script`, which has no window to show
+/// diagnostics in. Blocked runs are recorded in the audit log.
+pub(crate) fn scheduled_check(db: &Database, script_id: i64) -> Result<(), String> {
+    let Some((policy, report)) = lint_for_run(db, script_id)? else {
+        return Ok(());
+    };
+    enforce_policy(&policy, &report).inspect_err(|reason| {
+        if let Ok(conn) = db.conn.lock() {
+            let name = queries::get_script_by_id(&conn, script_id).map(|s| s.name).ok();
+            let details = serde_json::json!({ "reason": reason, "scheduled": true });
+            audit::log(&conn, "script.run_blocked", "script", Some(script_id), name.as_deref(), None, Some(&details));
+        }
+    })
+}
+
+/// Scheduled tasks of scripts with an effective "block" policy go through
+/// `conduit --run-script` to be linted, so when the global policy switches
+/// to or from "block" the tasks of scripts following it are rewritten.
+pub(crate) fn reinstall_for_global_policy(conn: &rusqlite::Connection, before: &str, after: &str) -> Result<(), String> {
+    if (before == "block") == (after == "block") {
+        return Ok(());
+    }
+    let mut script_ids: Vec<i64> = queries::get_all_schedules(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| s.script_id)
+        .collect();
+    script_ids.sort_unstable();
+    script_ids.dedup();
+    for script_id in script_ids {
+        let script = queries::get_script_by_id(conn, script_id).map_err(|e| e.to_string())?;
+        if script.lint_policy.is_none() {
+            scheduler::reinstall_script_schedules(conn, script_id)?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn lint_script(db: State<'_, Database>, script_id: i64, force: Option<bool>) -> Result<LintReport, String> {
+    lint(&db, script_id, force.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn get_lint_report(db: State<'_, Database>, script_id: i64) -> Result<Option<LintReport>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_lint_report(&conn, script_id).map_err(|e| e.to_string())
+}
+
+/// `None` makes the script follow the global lint policy again.
+#[tauri::command]
+pub fn set_script_lint_policy(
+    db: State<'_, Database>,
+    script_id: i64,
+    policy: Option<String>,
+) -> Result<Script, String> {
+    if let Some(ref policy) = policy {
+        validate_policy(policy)?;
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let before = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    queries::set_script_lint_policy(&conn, script_id, policy.as_deref()).map_err(|e| e.to_string())?;
+    let after = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    // Scheduled runs of blocking scripts go through `conduit --run-script`
+    if (effective_policy(&conn, &before)? == "block") != (effective_policy(&conn, &after)? == "block") {
+        scheduler::reinstall_script_schedules(&conn, script_id)?;
+    }
+    audit::log(&conn, "script.lint_policy_update", "script", Some(script_id), Some(&after.name), before.lint_policy.as_ref(), after.lint_policy.as_ref());
+    Ok(after)
+}
@@ -0,0 +1,75 @@
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{MetricPoint, RunMetric};
+use tauri::State;
+
+/// Prefix scripts print to report a metric, e.g. `::conduit metric rows=123`.
+const METRIC_PREFIX: &str = "::conduit metric ";
+
+/// Metrics kept per run; anything beyond is ignored.
+pub(crate) const MAX_METRICS_PER_RUN: usize = 1000;
+
+/// Series name for the built-in wall-clock duration of each run.
+const DURATION_METRIC: &str = "duration";
+
+fn is_valid_metric_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != DURATION_METRIC
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Parse the `name=value` pairs of a metric line. Non-numeric values and
+/// invalid names are skipped; lines without the prefix yield nothing.
+pub(crate) fn parse_metric_line(line: &str) -> Vec<(String, f64)> {
+    let Some(rest) = line.trim_start().strip_prefix(METRIC_PREFIX) else {
+        return Vec::new();
+    };
+    rest.split_whitespace()
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(name, _)| is_valid_metric_name(name))
+        .filter_map(|(name, value)| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .map(|v| (name.to_string(), v))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_run_metrics(db: State<'_, Database>, run_id: i64) -> Result<Vec<RunMetric>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_run_metrics(&conn, run_id).map_err(|e| e.to_string())
+}
+
+/// Metric names recorded for a script, always including "duration".
+#[tauri::command]
+pub fn get_metric_names(db: State<'_, Database>, script_id: i64) -> Result<Vec<String>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut names = vec![DURATION_METRIC.to_string()];
+    names.extend(queries::get_metric_names(&conn, script_id).map_err(|e| e.to_string())?);
+    Ok(names)
+}
+
+/// One value per run over time, oldest first, for charting. `since` is a
+/// `YYYY-MM-DD HH:MM:SS` UTC timestamp.
+#[tauri::command]
+pub fn get_metric_series(
+    db: State<'_, Database>,
+    script_id: i64,
+    name: String,
+    since: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<MetricPoint>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(500);
+    if name == DURATION_METRIC {
+        queries::get_duration_series(&conn, script_id, since.as_deref(), limit)
+    } else {
+        queries::get_metric_series(&conn, script_id, &name, since.as_deref(), limit)
+    }
+    .map_err(|e| e.to_string())
+}
@@ -4,6 +4,64 @@ pub mod runner;
 pub mod scheduler;
 pub mod history;
 pub mod settings;
+pub mod notifications;
+pub mod output_rules;
+pub mod metrics;
+pub mod search;
+pub mod retention;
+pub mod export;
+pub mod library;
+pub mod config;
+pub mod sources;
+pub mod header;
+pub mod health;
+pub mod versions;
+pub mod editor;
+pub mod lint;
+pub mod secrets;
+pub mod redaction;
+pub mod elevation;
+pub mod audit;
+pub mod protection;
+pub mod limits;
+pub mod sandbox;
+
+/// Service name under which Conduit keeps credentials in the OS keyring.
+const KEYRING_SERVICE: &str = "conduit";
+
+/// Look up a credential slot in the OS keyring (Keychain, Credential
+/// Manager or Secret Service).
+pub(crate) fn keyring_entry(name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, name).map_err(|e| format!("System keyring unavailable: {}", e))
+}
+
+fn write_and_replace(tmp: &std::path::Path, path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::File::create(tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    if let Ok(meta) = std::fs::metadata(path) {
+        std::fs::set_permissions(tmp, meta.permissions())?;
+    }
+    std::fs::rename(tmp, path)
+}
+
+/// Replace a file's contents by writing a sibling temp file and renaming it
+/// over the original, so readers never see a half-written script. An existing
+/// file's permissions are carried over, and a symlink is followed so the
+/// link stays a link and its target gets the new contents.
+pub(crate) fn write_atomic(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    let resolved = std::fs::canonicalize(path);
+    let path = resolved.as_deref().unwrap_or(path);
+    let dir = path.parent().unwrap_or(std::path::Path::new("."));
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = dir.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+    let result = write_and_replace(&tmp, path, contents);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
 
 /// Resolve which PowerShell executable runs .ps1 scripts from the settings
 /// value: 'ps5' = Windows PowerShell 5.1, anything else = PowerShell 7.
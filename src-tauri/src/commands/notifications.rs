@@ -0,0 +1,568 @@
+use crate::commands::audit;
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{
+    NewNotifier, NotificationLogEntry, NotificationPayload, Notifier, RunRecord, Settings, UpdateNotifier,
+};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::io::Write;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+/// Number of trailing output lines included in notification payloads.
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// Maximum length of a delivery response kept in the log.
+const MAX_RESPONSE_CHARS: usize = 500;
+
+/// Command notifiers are killed if they run longer than this.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Keyring slot holding the SMTP password.
+const SMTP_PASSWORD_KEY: &str = "smtp-password";
+
+pub(crate) fn output_tail(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    let start = lines.len().saturating_sub(OUTPUT_TAIL_LINES);
+    lines[start..].join("\n")
+}
+
+/// Replace `{{field}}` placeholders with payload values. `{{payload}}`
+/// expands to the full JSON payload. With `json_strings`, string values are
+/// escaped for use inside a JSON string literal, as webhook bodies are sent
+/// as JSON.
+fn render_template(template: &str, payload: &NotificationPayload, json_strings: bool) -> String {
+    let json = serde_json::to_value(payload).unwrap_or_default();
+    let mut rendered = template.replace("{{payload}}", &json.to_string());
+    if let Some(fields) = json.as_object() {
+        for (key, value) in fields {
+            let text = match value {
+                serde_json::Value::String(s) if json_strings => {
+                    let quoted = serde_json::to_string(s).unwrap_or_default();
+                    quoted[1..quoted.len() - 1].to_string()
+                }
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            rendered = rendered.replace(&format!("{{{{{}}}}}", key), &text);
+        }
+    }
+    rendered
+}
+
+fn render_body(notifier: &Notifier, payload: &NotificationPayload) -> String {
+    match notifier.template {
+        Some(ref template) => render_template(template, payload, notifier.kind == "webhook"),
+        None => serde_json::to_string(payload).unwrap_or_default(),
+    }
+}
+
+fn truncate_response(text: &str) -> String {
+    text.chars().take(MAX_RESPONSE_CHARS).collect()
+}
+
+fn deliver_webhook(notifier: &Notifier, body: &str) -> Result<String, String> {
+    let mut request = ureq::post(&notifier.target)
+        .timeout(Duration::from_secs(15))
+        .set("Content-Type", "application/json");
+    if let Some(ref headers) = notifier.headers {
+        for line in headers.lines() {
+            if let Some((name, value)) = line.split_once(':') {
+                request = request.set(name.trim(), value.trim());
+            }
+        }
+    }
+
+    match request.send_string(body) {
+        Ok(response) => {
+            let status = response.status();
+            let text = response.into_string().unwrap_or_default();
+            Ok(truncate_response(&format!("HTTP {} {}", status, text)))
+        }
+        Err(ureq::Error::Status(code, response)) => {
+            let text = response.into_string().unwrap_or_default();
+            Err(truncate_response(&format!("HTTP {} {}", code, text)))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn deliver_command(notifier: &Notifier, payload: &NotificationPayload, body: &str) -> Result<String, String> {
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut c = std::process::Command::new("/bin/sh");
+        c.args(["-c", &notifier.target]);
+        c
+    };
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut c = std::process::Command::new("cmd.exe");
+        c.args(["/C", &notifier.target]);
+        c
+    };
+
+    let mut child = cmd
+        .env("CONDUIT_EVENT", &payload.event)
+        .env("CONDUIT_SCRIPT_NAME", &payload.script_name)
+        .env("CONDUIT_STATUS", &payload.status)
+        .env(
+            "CONDUIT_EXIT_CODE",
+            payload.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+        )
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start notifier command: {}", e))?;
+
+    // Feed stdin and drain both pipes on their own threads, so a command that
+    // ignores stdin or prints more than a pipe buffer can't stall the poll
+    if let Some(mut stdin) = child.stdin.take() {
+        let body = body.as_bytes().to_vec();
+        // The command may not read stdin at all; a broken pipe is fine
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(&body);
+        });
+    }
+    let stdout = child.stdout.take().map(read_to_end);
+    let stderr = child.stderr.take().map(read_to_end);
+
+    let deadline = std::time::Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if std::time::Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Notifier command timed out after {}s", COMMAND_TIMEOUT.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+
+    let mut text = String::new();
+    for reader in [stdout, stderr].into_iter().flatten() {
+        text.push_str(&String::from_utf8_lossy(&reader.join().unwrap_or_default()));
+    }
+    if status.success() {
+        Ok(truncate_response(text.trim()))
+    } else {
+        Err(truncate_response(&format!("{} {}", status, text.trim())))
+    }
+}
+
+fn read_to_end(mut pipe: impl std::io::Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+pub(crate) fn set_smtp_password(password: &str) -> Result<(), String> {
+    let entry = crate::commands::keyring_entry(SMTP_PASSWORD_KEY)?;
+    if password.is_empty() {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to remove SMTP password: {}", e)),
+        }
+    } else {
+        entry
+            .set_password(password)
+            .map_err(|e| format!("Failed to store SMTP password: {}", e))
+    }
+}
+
+fn smtp_password(settings: &Settings) -> Result<Option<String>, String> {
+    if !settings.smtp_password_set {
+        return Ok(None);
+    }
+    crate::commands::keyring_entry(SMTP_PASSWORD_KEY)?
+        .get_password()
+        .map(Some)
+        .map_err(|e| format!("Failed to read SMTP password: {}", e))
+}
+
+fn build_mailer(settings: &Settings) -> Result<SmtpTransport, String> {
+    if settings.smtp_host.is_empty() {
+        return Err("No SMTP server configured. Set one in Settings.".to_string());
+    }
+    let builder = match settings.smtp_security.as_str() {
+        "tls" => SmtpTransport::relay(&settings.smtp_host).map_err(|e| e.to_string())?,
+        "starttls" => SmtpTransport::starttls_relay(&settings.smtp_host).map_err(|e| e.to_string())?,
+        _ => SmtpTransport::builder_dangerous(&settings.smtp_host),
+    };
+    let mut builder = builder
+        .port(u16::try_from(settings.smtp_port).map_err(|_| format!("Invalid SMTP port: {}", settings.smtp_port))?)
+        .timeout(Some(Duration::from_secs(30)));
+    if !settings.smtp_username.is_empty() {
+        let password = smtp_password(settings)?.unwrap_or_default();
+        builder = builder.credentials(Credentials::new(settings.smtp_username.clone(), password));
+    }
+    Ok(builder.build())
+}
+
+fn send_email(settings: &Settings, recipients: &str, subject: &str, body: &str) -> Result<String, String> {
+    let from = if settings.smtp_from.is_empty() {
+        &settings.smtp_username
+    } else {
+        &settings.smtp_from
+    };
+    let mut message = Message::builder()
+        .from(from.parse().map_err(|e| format!("Invalid sender address '{}': {}", from, e))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for recipient in recipients.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        message = message.to(recipient
+            .parse()
+            .map_err(|e| format!("Invalid recipient address '{}': {}", recipient, e))?);
+    }
+    let email = message.body(body.to_string()).map_err(|e| e.to_string())?;
+
+    let response = build_mailer(settings)?.send(&email).map_err(|e| e.to_string())?;
+    Ok(truncate_response(&format!(
+        "SMTP {} {}",
+        response.code(),
+        response.message().collect::<Vec<_>>().join(" ")
+    )))
+}
+
+fn default_email_body(payload: &NotificationPayload) -> String {
+    format!(
+        "Script: {}\nStatus: {}\nExit code: {}\nStarted: {}\nFinished: {}\nDuration: {}\n\nOutput (last {} lines):\n{}\n",
+        payload.script_name,
+        payload.status,
+        payload.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string()),
+        payload.started_at,
+        payload.finished_at.as_deref().unwrap_or("-"),
+        payload.duration_seconds.map(|d| format!("{}s", d)).unwrap_or_else(|| "-".to_string()),
+        OUTPUT_TAIL_LINES,
+        payload.output_tail,
+    )
+}
+
+/// Email templates may start with a `Subject:` line; the rest is the body.
+fn split_subject(text: &str) -> (Option<String>, String) {
+    if let Some((first, rest)) = text.split_once('\n') {
+        if let Some(subject) = first.strip_prefix("Subject:") {
+            return (Some(subject.trim().to_string()), rest.trim_start_matches('\n').to_string());
+        }
+    }
+    (None, text.to_string())
+}
+
+fn deliver_email(notifier: &Notifier, payload: &NotificationPayload, settings: &Settings) -> Result<String, String> {
+    let text = match notifier.template {
+        Some(ref template) => render_template(template, payload, false),
+        None => default_email_body(payload),
+    };
+    let (subject, body) = split_subject(&text);
+    let subject =
+        subject.unwrap_or_else(|| format!("[Conduit] {} {}", payload.script_name, payload.status));
+    send_email(settings, &notifier.target, &subject, &body)
+}
+
+fn deliver_once(notifier: &Notifier, payload: &NotificationPayload, settings: &Settings) -> Result<String, String> {
+    match notifier.kind.as_str() {
+        "webhook" => deliver_webhook(notifier, &render_body(notifier, payload)),
+        "command" => deliver_command(notifier, payload, &render_body(notifier, payload)),
+        "email" => deliver_email(notifier, payload, settings),
+        other => Err(format!("Unknown notifier kind: {}", other)),
+    }
+}
+
+/// Retry `deliver` with exponential backoff. Returns the number of attempts
+/// made alongside the final outcome.
+fn with_retries(max_retries: i64, mut deliver: impl FnMut() -> Result<String, String>) -> (i64, Result<String, String>) {
+    let max_attempts = max_retries.max(0) + 1;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let result = deliver();
+        if result.is_ok() || attempt >= max_attempts {
+            return (attempt, result);
+        }
+        let backoff = 1u64 << (attempt - 1).min(5);
+        std::thread::sleep(Duration::from_secs(backoff));
+    }
+}
+
+/// A failed run is delivered to notifiers subscribed to either "failure" or
+/// "finish"; every other event needs an exact subscription.
+fn wants_event(notifier: &Notifier, event: &str) -> bool {
+    notifier.events.iter().any(|e| e == event || (event == "failure" && e == "finish"))
+}
+
+fn log_delivery(app: &AppHandle, notifier_id: i64, run_id: Option<i64>, event: &str, attempts: i64, result: &Result<String, String>) {
+    let (status, response) = match result {
+        Ok(text) => ("delivered", text.as_str()),
+        Err(text) => ("failed", text.as_str()),
+    };
+    let db = app.state::<Database>();
+    if let Ok(conn) = db.conn.lock() {
+        let _ = queries::insert_notification_log(
+            &conn,
+            notifier_id,
+            run_id,
+            event,
+            attempts,
+            status,
+            if response.is_empty() { None } else { Some(response) },
+        );
+    };
+}
+
+/// Fan a run event out to every matching notifier. Each delivery runs on its
+/// own thread so retries never hold up the runner.
+pub(crate) fn notify(app: &AppHandle, payload: NotificationPayload) {
+    let (notifiers, settings) = {
+        let db = app.state::<Database>();
+        let conn = match db.conn.lock() {
+            Ok(conn) => conn,
+            Err(_) => return,
+        };
+        let settings = match queries::get_settings(&conn) {
+            Ok(settings) => settings,
+            Err(_) => return,
+        };
+        (
+            queries::get_notifiers_for_script(&conn, payload.script_id).unwrap_or_default(),
+            settings,
+        )
+    };
+
+    for notifier in notifiers.into_iter().filter(|n| wants_event(n, &payload.event)) {
+        let app = app.clone();
+        let payload = payload.clone();
+        let settings = settings.clone();
+        std::thread::spawn(move || {
+            let (attempts, result) =
+                with_retries(notifier.max_retries, || deliver_once(&notifier, &payload, &settings));
+            log_delivery(&app, notifier.id, Some(payload.run_id), &payload.event, attempts, &result);
+        });
+    }
+}
+
+fn build_digest(runs: &[(RunRecord, String)]) -> (String, String) {
+    let failed: Vec<&(RunRecord, String)> = runs.iter().filter(|(r, _)| r.status == "error").collect();
+    let subject = format!("[Conduit] Daily digest: {} runs, {} failed", runs.len(), failed.len());
+
+    let mut body = format!(
+        "Conduit runs in the last 24 hours: {} total, {} failed.\n\n",
+        runs.len(),
+        failed.len()
+    );
+    for (run, name) in runs {
+        body.push_str(&format!(
+            "{:<10} {:<30} {}  exit {}\n",
+            run.status.to_uppercase(),
+            name,
+            run.started_at,
+            run.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string()),
+        ));
+    }
+    for (run, name) in failed {
+        body.push_str(&format!(
+            "\n--- {} ({}) ---\n{}\n",
+            name,
+            run.started_at,
+            output_tail(run.output.as_deref().unwrap_or("")),
+        ));
+    }
+    (subject, body)
+}
+
+/// Send the daily digest once the configured local time has passed, at most
+/// once per day.
+fn send_digest_if_due(app: &AppHandle) {
+    let now = chrono::Local::now();
+    let today = now.format("%Y-%m-%d").to_string();
+
+    let (settings, notifiers, runs) = {
+        let db = app.state::<Database>();
+        let conn = match db.conn.lock() {
+            Ok(conn) => conn,
+            Err(_) => return,
+        };
+        let settings = match queries::get_settings(&conn) {
+            Ok(settings) => settings,
+            Err(_) => return,
+        };
+        let last_sent = queries::get_last_digest_date(&conn).unwrap_or(None);
+        // Compared as times, since settings saved before validation may hold "8:00"
+        let due = chrono::NaiveTime::parse_from_str(&settings.digest_time, "%H:%M").is_ok_and(|at| now.time() >= at);
+        if last_sent.as_deref() == Some(today.as_str()) || !due {
+            return;
+        }
+        let notifiers: Vec<Notifier> = queries::get_all_notifiers(&conn)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|n| n.enabled && n.kind == "email" && n.events.iter().any(|e| e == "digest"))
+            .collect();
+        let since = (chrono::Utc::now() - chrono::Duration::hours(24))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        let runs = queries::get_runs_since(&conn, &since).unwrap_or_default();
+        // Mark the day as sent up front so a slow or failing SMTP server
+        // can't cause repeated digests; retries cover transient errors.
+        let _ = queries::set_last_digest_date(&conn, &today);
+        (settings, notifiers, runs)
+    };
+
+    for notifier in notifiers {
+        let scoped: Vec<(RunRecord, String)> = runs
+            .iter()
+            .filter(|(r, _)| notifier.script_id.is_none_or(|id| id == r.script_id))
+            .cloned()
+            .collect();
+        let (subject, body) = build_digest(&scoped);
+        let (attempts, result) =
+            with_retries(notifier.max_retries, || send_email(&settings, &notifier.target, &subject, &body));
+        log_delivery(app, notifier.id, None, "digest", attempts, &result);
+    }
+}
+
+/// Background thread that checks once a minute whether the daily digest is due.
+pub(crate) fn start_digest_worker(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(60));
+        send_digest_if_due(&app);
+    });
+}
+
+fn validate_notifier(kind: &str, target: &str, events: &[String]) -> Result<(), String> {
+    match kind {
+        "webhook" => {
+            if !target.starts_with("http://") && !target.starts_with("https://") {
+                return Err("Webhook target must be an http:// or https:// URL".to_string());
+            }
+        }
+        "command" => {
+            if target.trim().is_empty() {
+                return Err("Command notifier needs a command to run".to_string());
+            }
+        }
+        "email" => {
+            if !target.split(',').any(|r| r.contains('@')) {
+                return Err("Email notifier needs at least one recipient address".to_string());
+            }
+        }
+        other => return Err(format!("Unknown notifier kind: {}", other)),
+    }
+    if let Some(bad) = events.iter().find(|e| !["start", "finish", "failure", "match", "digest"].contains(&e.as_str())) {
+        return Err(format!("Unknown notifier event: {}", bad));
+    }
+    if kind != "email" && events.iter().any(|e| e == "digest") {
+        return Err("Daily digests are only available for email notifiers".to_string());
+    }
+    Ok(())
+}
+
+/// A notifier for the audit log, with header values left out since they
+/// often carry tokens.
+fn audit_notifier(conn: &rusqlite::Connection, action: &str, before: Option<&Notifier>, after: Option<&Notifier>) {
+    let snapshot = |notifier: &Notifier| {
+        let mut value = serde_json::to_value(notifier).unwrap_or_default();
+        if let Some(ref headers) = notifier.headers {
+            let names: Vec<&str> = headers.lines().filter_map(|l| l.split_once(':')).map(|(n, _)| n.trim()).collect();
+            value["headers"] = serde_json::json!(names);
+        }
+        value
+    };
+    let Some(notifier) = after.or(before) else {
+        return;
+    };
+    audit::log(
+        conn,
+        action,
+        "notifier",
+        Some(notifier.id),
+        Some(&notifier.name),
+        before.map(snapshot).as_ref(),
+        after.map(snapshot).as_ref(),
+    );
+}
+
+#[tauri::command]
+pub fn get_notifiers(db: State<'_, Database>) -> Result<Vec<Notifier>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_all_notifiers(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_notifier(db: State<'_, Database>, notifier: NewNotifier) -> Result<Notifier, String> {
+    validate_notifier(&notifier.kind, &notifier.target, &notifier.events)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let created = queries::insert_notifier(&conn, &notifier).map_err(|e| e.to_string())?;
+    audit_notifier(&conn, "notifier.add", None, Some(&created));
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn update_notifier(db: State<'_, Database>, id: i64, update: UpdateNotifier) -> Result<Notifier, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = queries::get_notifier_by_id(&conn, id).map_err(|e| e.to_string())?;
+    validate_notifier(
+        &existing.kind,
+        update.target.as_deref().unwrap_or(&existing.target),
+        update.events.as_deref().unwrap_or(&existing.events),
+    )?;
+    let updated = queries::update_notifier(&conn, id, &update).map_err(|e| e.to_string())?;
+    audit_notifier(&conn, "notifier.update", Some(&existing), Some(&updated));
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn delete_notifier(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = queries::get_notifier_by_id(&conn, id).map_err(|e| e.to_string())?;
+    queries::delete_notifier(&conn, id).map_err(|e| e.to_string())?;
+    audit_notifier(&conn, "notifier.delete", Some(&existing), None);
+    Ok(())
+}
+
+/// Send a sample payload through a notifier once, without retries, and
+/// return the endpoint's response.
+#[tauri::command]
+pub async fn test_notifier(app: AppHandle, db: State<'_, Database>, id: i64) -> Result<String, String> {
+    let (notifier, settings) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        (
+            queries::get_notifier_by_id(&conn, id).map_err(|e| e.to_string())?,
+            queries::get_settings(&conn).map_err(|e| e.to_string())?,
+        )
+    };
+
+    let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let payload = NotificationPayload {
+        event: "test".to_string(),
+        script_id: notifier.script_id.unwrap_or(0),
+        script_name: "Conduit test notification".to_string(),
+        run_id: 0,
+        status: "success".to_string(),
+        exit_code: Some(0),
+        duration_seconds: Some(0),
+        started_at: now.clone(),
+        finished_at: Some(now),
+        output_tail: "This is a test notification from Conduit.".to_string(),
+        matched_rule: None,
+    };
+
+    let result = deliver_once(&notifier, &payload, &settings);
+    log_delivery(&app, notifier.id, None, "test", 1, &result);
+    result
+}
+
+#[tauri::command]
+pub fn get_notification_log(
+    db: State<'_, Database>,
+    notifier_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<NotificationLogEntry>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_notification_log(&conn, notifier_id, limit.unwrap_or(100)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,186 @@
+use crate::commands::audit;
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewOutputRule, OutputRule, RunMatch, UpdateOutputRule};
+use regex::Regex;
+use std::collections::HashSet;
+use tauri::State;
+
+/// Matched lines stored per run; later matches still affect status and
+/// metadata but aren't recorded.
+const MAX_STORED_MATCHES: usize = 500;
+
+/// Matched lines longer than this are truncated before storage.
+const MAX_LINE_CHARS: usize = 1000;
+
+/// A rule that fired and asked to notify, reported back to the runner.
+pub(crate) struct RuleHit {
+    pub rule_name: String,
+    pub line: String,
+}
+
+/// Applies a script's output rules line by line as PTY output streams in.
+pub(crate) struct OutputMatcher {
+    rules: Vec<(OutputRule, Regex)>,
+    line_number: i64,
+    matches: Vec<RunMatch>,
+    metadata: serde_json::Map<String, serde_json::Value>,
+    severity: Option<&'static str>,
+    notified: HashSet<i64>,
+}
+
+impl OutputMatcher {
+    pub fn new(rules: Vec<OutputRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| Regex::new(&r.pattern).ok().map(|re| (r, re)))
+            .collect();
+        OutputMatcher {
+            rules,
+            line_number: 0,
+            matches: Vec::new(),
+            metadata: serde_json::Map::new(),
+            severity: None,
+            notified: HashSet::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Highest severity seen: "error", "warning" or None.
+    pub fn severity(&self) -> Option<&'static str> {
+        self.severity
+    }
+
+    pub fn matches(&self) -> &[RunMatch] {
+        &self.matches
+    }
+
+    pub fn metadata(&self) -> Option<serde_json::Value> {
+        if self.metadata.is_empty() {
+            None
+        } else {
+            Some(serde_json::Value::Object(self.metadata.clone()))
+        }
+    }
+
+    /// Evaluate one complete, ANSI-stripped output line.
+    pub fn check_line(&mut self, line: &str) -> Vec<RuleHit> {
+        self.line_number += 1;
+        let mut hits = Vec::new();
+        for (rule, re) in &self.rules {
+            let Some(caps) = re.captures(line) else {
+                continue;
+            };
+
+            let mut captures = serde_json::Map::new();
+            for name in re.capture_names().flatten() {
+                if let Some(m) = caps.name(name) {
+                    let value = capture_value(m.as_str());
+                    captures.insert(name.to_string(), value.clone());
+                    self.metadata.insert(name.to_string(), value);
+                }
+            }
+
+            match rule.severity.as_str() {
+                "error" => self.severity = Some("error"),
+                "warning" if self.severity.is_none() => self.severity = Some("warning"),
+                _ => {}
+            }
+
+            if self.matches.len() < MAX_STORED_MATCHES {
+                self.matches.push(RunMatch {
+                    id: 0,
+                    run_id: 0,
+                    rule_id: Some(rule.id),
+                    rule_name: rule.name.clone(),
+                    severity: rule.severity.clone(),
+                    line_number: self.line_number,
+                    line: line.chars().take(MAX_LINE_CHARS).collect(),
+                    captures: if captures.is_empty() {
+                        None
+                    } else {
+                        Some(serde_json::Value::Object(captures))
+                    },
+                });
+            }
+
+            // Only the first match of each rule per run sends a notification
+            if rule.notify && self.notified.insert(rule.id) {
+                hits.push(RuleHit {
+                    rule_name: rule.name.clone(),
+                    line: line.to_string(),
+                });
+            }
+        }
+        hits
+    }
+}
+
+/// Store numeric captures as JSON numbers so they can be charted.
+fn capture_value(text: &str) -> serde_json::Value {
+    let trimmed = text.trim();
+    if let Ok(n) = trimmed.parse::<i64>() {
+        return n.into();
+    }
+    if let Ok(f) = trimmed.parse::<f64>() {
+        if let Some(n) = serde_json::Number::from_f64(f) {
+            return serde_json::Value::Number(n);
+        }
+    }
+    serde_json::Value::String(text.to_string())
+}
+
+fn validate_rule(pattern: &str, severity: &str) -> Result<(), String> {
+    Regex::new(pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    if !["info", "warning", "error"].contains(&severity) {
+        return Err(format!("Unknown rule severity: {}", severity));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_output_rules(db: State<'_, Database>, script_id: i64) -> Result<Vec<OutputRule>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_output_rules(&conn, script_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_output_rule(db: State<'_, Database>, rule: NewOutputRule) -> Result<OutputRule, String> {
+    validate_rule(&rule.pattern, &rule.severity)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let created = queries::insert_output_rule(&conn, &rule).map_err(|e| e.to_string())?;
+    audit::log(&conn, "output_rule.add", "output_rule", Some(created.id), Some(&created.name), None, Some(&created));
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn update_output_rule(db: State<'_, Database>, id: i64, update: UpdateOutputRule) -> Result<OutputRule, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = queries::get_output_rule_by_id(&conn, id).map_err(|e| e.to_string())?;
+    validate_rule(
+        update.pattern.as_deref().unwrap_or(&existing.pattern),
+        update.severity.as_deref().unwrap_or(&existing.severity),
+    )?;
+    let updated = queries::update_output_rule(&conn, id, &update).map_err(|e| e.to_string())?;
+    audit::log(&conn, "output_rule.update", "output_rule", Some(id), Some(&updated.name), Some(&existing), Some(&updated));
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn delete_output_rule(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = queries::get_output_rule_by_id(&conn, id).map_err(|e| e.to_string())?;
+    queries::delete_output_rule(&conn, id).map_err(|e| e.to_string())?;
+    audit::log(&conn, "output_rule.delete", "output_rule", Some(id), Some(&existing.name), Some(&existing), None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_run_matches(db: State<'_, Database>, run_id: i64) -> Result<Vec<RunMatch>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_run_matches(&conn, run_id).map_err(|e| e.to_string())
+}
@@ -0,0 +1,190 @@
+use crate::commands::{audit, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewScriptProtection, Script, ScriptProtection};
+use chrono::{NaiveDateTime, NaiveTime};
+use rusqlite::Connection;
+use tauri::State;
+
+const TIME_FORMAT: &str = "%H:%M";
+
+fn parse_time(field: &str, text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text, TIME_FORMAT).map_err(|_| format!("{} must be HH:MM, got \"{}\"", field, text))
+}
+
+/// "1h 5m", "4m 10s" or "30s".
+fn format_wait(seconds: i64) -> String {
+    let (h, m, s) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if h > 0 {
+        format!("{}h {}m", h, m)
+    } else if m > 0 {
+        format!("{}m {}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Overnight windows (from later than until) span midnight.
+fn in_window(now: NaiveTime, from: NaiveTime, until: NaiveTime) -> bool {
+    if from <= until {
+        now >= from && now < until
+    } else {
+        now >= from || now < until
+    }
+}
+
+fn check_window(protection: &ScriptProtection) -> Result<(), String> {
+    let (Some(from), Some(until)) = (&protection.allowed_from, &protection.allowed_until) else {
+        return Ok(());
+    };
+    let now = chrono::Local::now().time();
+    if in_window(now, parse_time("Allowed from", from)?, parse_time("Allowed until", until)?) {
+        Ok(())
+    } else {
+        Err(format!("Runs are only allowed between {} and {} (local time)", from, until))
+    }
+}
+
+fn check_cooldown(protection: &ScriptProtection) -> Result<(), String> {
+    let (Some(cooldown), Some(last_run_at)) = (protection.cooldown_seconds, &protection.last_run_at) else {
+        return Ok(());
+    };
+    let Ok(last_run) = NaiveDateTime::parse_from_str(last_run_at, "%Y-%m-%d %H:%M:%S") else {
+        return Ok(());
+    };
+    let elapsed = (chrono::Utc::now().naive_utc() - last_run).num_seconds();
+    if elapsed < cooldown {
+        Err(format!(
+            "Last run was {} ago; wait {} before running again",
+            format_wait(elapsed.max(0)),
+            format_wait(cooldown - elapsed)
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_phrase(script: &Script, protection: &ScriptProtection, confirmation: Option<&str>) -> Result<(), String> {
+    if confirmation.map(str::trim) == Some(protection.confirm_phrase.as_str()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "\"{}\" is protected; type \"{}\" to confirm",
+            script.name, protection.confirm_phrase
+        ))
+    }
+}
+
+/// Why a run of `script` isn't allowed right now, if it's protected.
+/// Scheduled runs skip the phrase, which was typed when the schedule was
+/// created, but not the window or cooldown.
+pub(crate) fn check(
+    conn: &Connection,
+    script: &Script,
+    confirmation: Option<&str>,
+    scheduled: bool,
+) -> Result<(), String> {
+    let Some(protection) = queries::get_script_protection(conn, script.id).map_err(|e| e.to_string())? else {
+        return Ok(());
+    };
+    if !scheduled {
+        check_phrase(script, &protection, confirmation)?;
+    }
+    check_window(&protection)?;
+    check_cooldown(&protection)
+}
+
+/// `check`, recording refused runs in the audit log.
+pub(crate) fn enforce(
+    conn: &Connection,
+    script: &Script,
+    confirmation: Option<&str>,
+    scheduled: bool,
+) -> Result<(), String> {
+    check(conn, script, confirmation, scheduled).inspect_err(|reason| {
+        let details = serde_json::json!({ "reason": reason, "scheduled": scheduled });
+        audit::log(conn, "script.run_blocked", "script", Some(script.id), Some(&script.name), None, Some(&details));
+    })
+}
+
+/// Only the phrase, for actions that lead to unattended runs.
+pub(crate) fn confirm(conn: &Connection, script: &Script, confirmation: Option<&str>) -> Result<(), String> {
+    match queries::get_script_protection(conn, script.id).map_err(|e| e.to_string())? {
+        Some(protection) => check_phrase(script, &protection, confirmation),
+        None => Ok(()),
+    }
+}
+
+/// Start the cooldown, returning the previous run time for `release_run`.
+/// Call it under the same lock as `enforce` so two runs can't both pass the
+/// cooldown check. A no-op for unprotected scripts.
+pub(crate) fn mark_run(conn: &Connection, script_id: i64) -> Option<String> {
+    let previous = queries::get_script_protection(conn, script_id).ok().flatten().and_then(|p| p.last_run_at);
+    let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let _ = queries::set_protection_last_run(conn, script_id, Some(&now));
+    previous
+}
+
+/// Undo `mark_run` for a run that failed before it started.
+pub(crate) fn release_run(conn: &Connection, script_id: i64, previous: Option<&str>) {
+    let _ = queries::set_protection_last_run(conn, script_id, previous);
+}
+
+#[tauri::command]
+pub fn get_script_protection(db: State<'_, Database>, script_id: i64) -> Result<Option<ScriptProtection>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_protection(&conn, script_id).map_err(|e| e.to_string())
+}
+
+/// Protect a script, change its guards, or with None remove protection.
+#[tauri::command]
+pub fn set_script_protection(
+    db: State<'_, Database>,
+    script_id: i64,
+    protection: Option<NewScriptProtection>,
+) -> Result<Option<ScriptProtection>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    let before = queries::get_script_protection(&conn, script_id).map_err(|e| e.to_string())?;
+
+    match protection {
+        Some(new) => {
+            let phrase = new.confirm_phrase.as_deref().map(str::trim).unwrap_or("");
+            let phrase = if phrase.is_empty() { script.name.trim() } else { phrase };
+            if phrase.is_empty() {
+                return Err("Confirmation phrase can't be empty".to_string());
+            }
+            if new.cooldown_seconds.is_some_and(|c| c < 0) {
+                return Err("Cooldown can't be negative".to_string());
+            }
+            match (&new.allowed_from, &new.allowed_until) {
+                (Some(from), Some(until)) => {
+                    if parse_time("Allowed from", from)? == parse_time("Allowed until", until)? {
+                        return Err("Allowed window can't start and end at the same time".to_string());
+                    }
+                }
+                (None, None) => {}
+                _ => return Err("Set both ends of the allowed window, or neither".to_string()),
+            }
+            queries::upsert_script_protection(
+                &conn,
+                script_id,
+                phrase,
+                new.cooldown_seconds.filter(|c| *c > 0),
+                new.allowed_from.as_deref(),
+                new.allowed_until.as_deref(),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        None => queries::delete_script_protection(&conn, script_id).map_err(|e| e.to_string())?,
+    }
+
+    let after = queries::get_script_protection(&conn, script_id).map_err(|e| e.to_string())?;
+    // Scheduled runs of protected scripts go through `conduit --run-script`
+    // to check the window and cooldown
+    if before.is_some() != after.is_some() {
+        scheduler::reinstall_script_schedules(&conn, script_id)?;
+    }
+    audit::log(&conn, "script.protection_update", "script", Some(script_id), Some(&script.name), before.as_ref(), after.as_ref());
+    Ok(after)
+}
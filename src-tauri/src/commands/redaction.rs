@@ -0,0 +1,467 @@
+use crate::commands::audit;
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewRedactionRule, RedactionDetector, RedactionRule, UpdateRedactionRule};
+use regex::bytes::Regex;
+use rusqlite::Connection;
+use tauri::State;
+
+/// What a redacted value is replaced with in output.
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+struct Detector {
+    id: &'static str,
+    label: &'static str,
+    pattern: &'static str,
+}
+
+/// Built-in patterns for common credential formats. Where a pattern has a
+/// capture group, only the group is replaced, so `password=hunter2` keeps
+/// its `password=`.
+const DETECTORS: [Detector; 12] = [
+    Detector {
+        id: "aws_access_key",
+        label: "AWS access key ID",
+        pattern: r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+    },
+    Detector {
+        id: "github_token",
+        label: "GitHub token",
+        pattern: r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+    },
+    Detector {
+        id: "gitlab_token",
+        label: "GitLab token",
+        pattern: r"\bglpat-[A-Za-z0-9_-]{20,}",
+    },
+    Detector {
+        id: "slack_token",
+        label: "Slack token",
+        pattern: r"\bxox[abposr]-[A-Za-z0-9-]{10,}",
+    },
+    Detector {
+        id: "stripe_key",
+        label: "Stripe key",
+        pattern: r"\b[rs]k_(?:live|test)_[A-Za-z0-9]{16,}",
+    },
+    Detector {
+        id: "google_api_key",
+        label: "Google API key",
+        pattern: r"\bAIza[0-9A-Za-z_-]{35}",
+    },
+    Detector {
+        id: "llm_api_key",
+        label: "OpenAI / Anthropic API key",
+        pattern: r"\bsk-(?:proj-|ant-)?[A-Za-z0-9_-]{20,}",
+    },
+    Detector {
+        id: "jwt",
+        label: "JSON Web Token",
+        pattern: r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+    },
+    Detector {
+        id: "bearer_token",
+        label: "Bearer token",
+        pattern: r"(?i)\bbearer\s+([A-Za-z0-9._~+/-]{16,}=*)",
+    },
+    Detector {
+        id: "url_password",
+        label: "Password in URL",
+        pattern: r"\b[a-zA-Z][a-zA-Z0-9+.-]*://[^\s:/@]+:([^\s@/]+)@",
+    },
+    Detector {
+        id: "assignment",
+        label: "password=, token=, api_key= ...",
+        pattern: r#"(?i)\b(?:password|passwd|pwd|secret|token|api[_-]?key|access[_-]?key)\b["']?\s*[:=]\s*["']?([^\s"',;]{4,})"#,
+    },
+    Detector {
+        id: "private_key",
+        label: "Private key block",
+        pattern: r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY-----",
+    },
+];
+
+/// The private key detector blanks whole lines up to the END marker.
+const PRIVATE_KEY_DETECTOR: &str = "private_key";
+
+/// An unfinished line longer than this is redacted and released anyway, so
+/// output that never ends a line can't pile up.
+const MAX_PENDING_BYTES: usize = 8 * 1024;
+
+fn is_line_end(b: &u8) -> bool {
+    *b == b'\n' || *b == b'\r'
+}
+
+/// Compiled patterns that apply to one script's output.
+#[derive(Clone, Default)]
+pub(crate) struct RedactionRules {
+    patterns: Vec<Regex>,
+    key_blocks: Option<Regex>,
+}
+
+/// Enabled built-in detectors plus the enabled global and per-script rules.
+/// Rules that no longer compile are skipped.
+pub(crate) fn load_rules(conn: &Connection, script_id: i64) -> Result<RedactionRules, String> {
+    let disabled = queries::get_disabled_detectors(conn).map_err(|e| e.to_string())?;
+    let mut rules = RedactionRules::default();
+    for detector in DETECTORS.iter().filter(|d| !disabled.iter().any(|id| id == d.id)) {
+        let Ok(re) = Regex::new(detector.pattern) else {
+            continue;
+        };
+        if detector.id == PRIVATE_KEY_DETECTOR {
+            rules.key_blocks = Some(re);
+        } else {
+            rules.patterns.push(re);
+        }
+    }
+    let custom = queries::get_redaction_rules(conn, Some(script_id)).map_err(|e| e.to_string())?;
+    rules.patterns.extend(
+        custom
+            .into_iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| Regex::new(&r.pattern).ok()),
+    );
+    Ok(rules)
+}
+
+/// Replace each match (or its first capture group) in `text`.
+fn apply_pattern(re: &Regex, text: &[u8], count: &mut i64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut last = 0;
+    for caps in re.captures_iter(text) {
+        let Some(target) = caps.get(1).or_else(|| caps.get(0)) else {
+            continue;
+        };
+        // Overlapping or already redacted by an earlier pattern
+        if target.start() < last || target.is_empty() || target.as_bytes() == REDACTED.as_bytes() {
+            continue;
+        }
+        out.extend_from_slice(&text[last..target.start()]);
+        out.extend_from_slice(REDACTED.as_bytes());
+        last = target.end();
+        *count += 1;
+    }
+    out.extend_from_slice(&text[last..]);
+    out
+}
+
+/// Replaces secret values and sensitive patterns in streamed output.
+///
+/// Literal secrets are matched on raw bytes, holding back a chunk's tail that
+/// could be the start of one. Patterns need whole lines, so with any pattern
+/// active an unfinished line is held until a `\n` or `\r` ends it (progress
+/// bars redraw with `\r`), it grows past `MAX_PENDING_BYTES`, or the caller
+/// calls `flush` because the stream has gone quiet.
+pub(crate) struct Redactor {
+    /// Longest first, so a secret containing another is replaced whole.
+    needles: Vec<Vec<u8>>,
+    rules: RedactionRules,
+    pending: Vec<u8>,
+    in_key_block: bool,
+    count: i64,
+}
+
+impl Redactor {
+    /// Multi-line secret values are also matched line by line, since a PTY
+    /// rewrites their line endings.
+    pub(crate) fn new(values: &[String], rules: RedactionRules) -> Self {
+        let mut needles: Vec<Vec<u8>> = Vec::new();
+        for value in values {
+            let lines = value.lines().map(|line| line.trim_end_matches('\r'));
+            for needle in std::iter::once(value.as_str()).chain(lines) {
+                if needle.trim().len() >= crate::commands::secrets::MIN_SECRET_LEN {
+                    needles.push(needle.as_bytes().to_vec());
+                }
+            }
+        }
+        needles.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        needles.dedup();
+        Redactor {
+            needles,
+            rules,
+            pending: Vec::new(),
+            in_key_block: false,
+            count: 0,
+        }
+    }
+
+    /// Number of replacements made so far.
+    pub(crate) fn count(&self) -> i64 {
+        self.count
+    }
+
+    fn line_based(&self) -> bool {
+        !self.rules.patterns.is_empty() || self.rules.key_blocks.is_some()
+    }
+
+    /// Redact the next chunk of output. What comes back may be shorter than
+    /// the input when its end is held back.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.needles.is_empty() && !self.line_based() {
+            return chunk.to_vec();
+        }
+        self.pending.extend_from_slice(chunk);
+        if self.line_based() {
+            let Some(pos) = self.pending.iter().rposition(is_line_end) else {
+                if self.pending.len() > MAX_PENDING_BYTES {
+                    return self.flush();
+                }
+                return Vec::new();
+            };
+            let lines: Vec<u8> = self.pending.drain(..=pos).collect();
+            return self.redact_lines(&lines);
+        }
+        let (out, consumed) = self.replace_needles(&self.pending.clone(), false);
+        self.pending.drain(..consumed);
+        out
+    }
+
+    /// Release everything held back, e.g. once the stream has ended or gone
+    /// quiet so a prompt without a newline still shows.
+    pub(crate) fn flush(&mut self) -> Vec<u8> {
+        let pending = std::mem::take(&mut self.pending);
+        if self.line_based() {
+            self.redact_lines(&pending)
+        } else {
+            self.replace_needles(&pending, true).0
+        }
+    }
+
+    fn redact_lines(&mut self, text: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(text.len());
+        for line in text.split_inclusive(is_line_end) {
+            let body_len = line.len() - usize::from(line.last().is_some_and(is_line_end));
+            let (body, ending) = line.split_at(body_len);
+            // The \n of a \r\n, or a blank line
+            if body.is_empty() {
+                out.extend_from_slice(ending);
+                continue;
+            }
+
+            if let Some(ref begin) = self.rules.key_blocks {
+                if !self.in_key_block && begin.is_match(body) {
+                    self.in_key_block = true;
+                    self.count += 1;
+                }
+            }
+            if self.in_key_block {
+                if body.windows(8).any(|w| w == b"-----END") {
+                    self.in_key_block = false;
+                }
+                out.extend_from_slice(REDACTED.as_bytes());
+                out.extend_from_slice(ending);
+                continue;
+            }
+
+            let mut body = self.replace_needles(body, true).0;
+            for re in &self.rules.patterns {
+                body = apply_pattern(re, &body, &mut self.count);
+            }
+            out.extend_from_slice(&body);
+            out.extend_from_slice(ending);
+        }
+        out
+    }
+
+    /// Returns the redacted text and how much of `buf` it covers; without
+    /// `flush`, a tail that may be the start of a secret is left over.
+    fn replace_needles(&mut self, buf: &[u8], flush: bool) -> (Vec<u8>, usize) {
+        let mut out = Vec::with_capacity(buf.len());
+        let mut i = 0;
+        while i < buf.len() {
+            let rest = &buf[i..];
+            if !flush && self.needles.iter().any(|n| n.len() > rest.len() && n.starts_with(rest)) {
+                break;
+            }
+            if let Some(needle) = self.needles.iter().find(|n| rest.starts_with(n)) {
+                out.extend_from_slice(REDACTED.as_bytes());
+                i += needle.len();
+                self.count += 1;
+            } else {
+                out.push(buf[i]);
+                i += 1;
+            }
+        }
+        (out, i)
+    }
+}
+
+fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if pattern.is_empty() {
+        return Err("Pattern must not be empty".to_string());
+    }
+    let re = Regex::new(pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    if re.is_match(b"") {
+        return Err("Pattern matches empty text".to_string());
+    }
+    Ok(())
+}
+
+/// All rules when `script_id` is omitted; otherwise those applying to the
+/// script, global ones included.
+#[tauri::command]
+pub fn get_redaction_rules(db: State<'_, Database>, script_id: Option<i64>) -> Result<Vec<RedactionRule>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_redaction_rules(&conn, script_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_redaction_rule(db: State<'_, Database>, rule: NewRedactionRule) -> Result<RedactionRule, String> {
+    validate_pattern(&rule.pattern)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let created = queries::insert_redaction_rule(&conn, &rule).map_err(|e| e.to_string())?;
+    audit::log(&conn, "redaction_rule.add", "redaction_rule", Some(created.id), Some(&created.name), None, Some(&created));
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn update_redaction_rule(
+    db: State<'_, Database>,
+    id: i64,
+    update: UpdateRedactionRule,
+) -> Result<RedactionRule, String> {
+    if let Some(ref pattern) = update.pattern {
+        validate_pattern(pattern)?;
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = queries::get_redaction_rule_by_id(&conn, id).map_err(|e| e.to_string())?;
+    let updated = queries::update_redaction_rule(&conn, id, &update).map_err(|e| e.to_string())?;
+    audit::log(&conn, "redaction_rule.update", "redaction_rule", Some(id), Some(&updated.name), Some(&existing), Some(&updated));
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn delete_redaction_rule(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = queries::get_redaction_rule_by_id(&conn, id).map_err(|e| e.to_string())?;
+    queries::delete_redaction_rule(&conn, id).map_err(|e| e.to_string())?;
+    audit::log(&conn, "redaction_rule.delete", "redaction_rule", Some(id), Some(&existing.name), Some(&existing), None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_redaction_detectors(db: State<'_, Database>) -> Result<Vec<RedactionDetector>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let disabled = queries::get_disabled_detectors(&conn).map_err(|e| e.to_string())?;
+    Ok(DETECTORS
+        .iter()
+        .map(|d| RedactionDetector {
+            id: d.id.to_string(),
+            label: d.label.to_string(),
+            pattern: d.pattern.to_string(),
+            enabled: !disabled.iter().any(|id| id == d.id),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn set_redaction_detector(db: State<'_, Database>, id: String, enabled: bool) -> Result<(), String> {
+    if !DETECTORS.iter().any(|d| d.id == id) {
+        return Err(format!("Unknown detector: {}", id));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let was_enabled = !queries::get_disabled_detectors(&conn).map_err(|e| e.to_string())?.contains(&id);
+    queries::set_detector_enabled(&conn, &id, enabled).map_err(|e| e.to_string())?;
+    audit::log(
+        &conn,
+        "redaction_detector.toggle",
+        "redaction_detector",
+        None,
+        Some(&id),
+        Some(&serde_json::json!({ "enabled": was_enabled })),
+        Some(&serde_json::json!({ "enabled": enabled })),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detectors() -> RedactionRules {
+        let mut rules = RedactionRules::default();
+        for detector in &DETECTORS {
+            let re = Regex::new(detector.pattern).unwrap();
+            if detector.id == PRIVATE_KEY_DETECTOR {
+                rules.key_blocks = Some(re);
+            } else {
+                rules.patterns.push(re);
+            }
+        }
+        rules
+    }
+
+    fn run(redactor: &mut Redactor, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for chunk in chunks {
+            out.extend(redactor.push(chunk));
+        }
+        out.extend(redactor.flush());
+        out
+    }
+
+    #[test]
+    fn needle_split_across_chunks() {
+        let mut redactor = Redactor::new(&["hunter2secret".to_string()], RedactionRules::default());
+        assert_eq!(redactor.push(b"pass: hunter2"), b"pass: ");
+        assert_eq!(redactor.push(b"secret ok\n"), b"[REDACTED] ok\n");
+        assert_eq!(redactor.count(), 1);
+    }
+
+    #[test]
+    fn held_prefix_that_is_not_a_secret_is_released() {
+        let mut redactor = Redactor::new(&["hunter2secret".to_string()], RedactionRules::default());
+        assert_eq!(redactor.push(b"hunter"), b"");
+        assert_eq!(redactor.push(b"s\n"), b"hunters\n");
+        assert_eq!(redactor.count(), 0);
+    }
+
+    #[test]
+    fn needle_split_across_chunks_with_patterns() {
+        let mut redactor = Redactor::new(&["hunter2secret".to_string()], detectors());
+        let out = run(&mut redactor, &[b"pass: hunt", b"er2secret\n"]);
+        assert_eq!(out, b"pass: [REDACTED]\n");
+    }
+
+    #[test]
+    fn key_block_is_blanked_to_end_marker() {
+        let mut redactor = Redactor::new(&[], detectors());
+        let out = run(
+            &mut redactor,
+            &[
+                b"before\r\n-----BEGIN RSA PRIV",
+                b"ATE KEY-----\r\nMIIEow\r",
+                b"\nabc\r\n-----END RSA PRIVATE KEY-----\r\nafter\r\n",
+            ],
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "before\r\n[REDACTED]\r\n[REDACTED]\r\n[REDACTED]\r\n[REDACTED]\r\nafter\r\n"
+        );
+        assert_eq!(redactor.count(), 1);
+    }
+
+    #[test]
+    fn flush_releases_an_unfinished_line() {
+        let mut redactor = Redactor::new(&[], detectors());
+        assert_eq!(redactor.push(b"Password: "), b"");
+        assert_eq!(redactor.flush(), b"Password: ");
+        assert_eq!(redactor.push(b"token=abcdef12\n"), b"token=[REDACTED]\n");
+    }
+
+    #[test]
+    fn carriage_return_ends_a_line() {
+        let mut redactor = Redactor::new(&[], detectors());
+        assert_eq!(redactor.push(b"\r 10%"), b"\r");
+        assert_eq!(redactor.push(b"\r 20%\r 30%"), b" 10%\r 20%\r");
+    }
+
+    #[test]
+    fn long_unfinished_line_is_released() {
+        let mut redactor = Redactor::new(&[], detectors());
+        let chunk = vec![b'.'; 4096];
+        assert!(redactor.push(&chunk).is_empty());
+        assert!(redactor.push(&chunk).is_empty());
+        assert_eq!(redactor.push(b"."), vec![b'.'; 8193]);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::commands::audit;
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{PruneReport, PrunedScript, ScriptRetention, Settings};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, State};
+
+/// How often the background worker re-applies retention rules.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Effective limits for one script after applying its override. 0 = unlimited.
+struct Policy {
+    keep_runs: i64,
+    keep_days: i64,
+    keep_failures_days: i64,
+}
+
+impl Policy {
+    fn resolve(settings: &Settings, script: Option<&ScriptRetention>) -> Self {
+        Policy {
+            keep_runs: script
+                .and_then(|p| p.keep_runs)
+                .unwrap_or(settings.retention_keep_runs),
+            keep_days: script
+                .and_then(|p| p.keep_days)
+                .unwrap_or(settings.retention_keep_days),
+            keep_failures_days: script
+                .and_then(|p| p.keep_failures_days)
+                .unwrap_or(settings.retention_failure_days),
+        }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.keep_runs <= 0 && self.keep_days <= 0 && self.keep_failures_days <= 0
+    }
+}
+
+fn cutoff(days: i64) -> String {
+    (chrono::Utc::now() - chrono::Duration::days(days))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// Pick runs to delete from a newest-first list. Failed runs with a failure
+/// window only expire by age, so they can outlive the run-count limit.
+fn runs_to_prune(runs: &[(i64, String, String)], policy: &Policy) -> Vec<i64> {
+    let age_cutoff = (policy.keep_days > 0).then(|| cutoff(policy.keep_days));
+    let failure_cutoff = (policy.keep_failures_days > 0).then(|| cutoff(policy.keep_failures_days));
+
+    runs.iter()
+        .enumerate()
+        .filter(|(index, (_, started_at, status))| {
+            if status == "error" {
+                if let Some(ref failure_cutoff) = failure_cutoff {
+                    return started_at < failure_cutoff;
+                }
+            }
+            let over_count = policy.keep_runs > 0 && *index as i64 >= policy.keep_runs;
+            let too_old = age_cutoff.as_ref().is_some_and(|c| started_at < c);
+            over_count || too_old
+        })
+        .map(|(_, (id, _, _))| *id)
+        .collect()
+}
+
+/// Scheduler log files untouched for longer than `days`.
+fn stale_log_files(days: i64) -> Vec<(std::path::PathBuf, u64)> {
+    if days <= 0 {
+        return Vec::new();
+    }
+    let max_age = Duration::from_secs(days as u64 * 24 * 60 * 60);
+    let now = SystemTime::now();
+    let Ok(entries) = std::fs::read_dir(crate::commands::scheduler::get_logs_dir()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let age = now.duration_since(meta.modified().ok()?).ok()?;
+            (meta.is_file() && age > max_age).then(|| (entry.path(), meta.len()))
+        })
+        .collect()
+}
+
+/// Apply retention rules, or only report what they would delete when
+/// `dry_run` is set. The database is vacuumed after a real prune.
+pub(crate) fn prune(db: &Database, dry_run: bool) -> Result<PruneReport, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+    let scripts = queries::get_all_scripts(&conn).map_err(|e| e.to_string())?;
+
+    let mut report = PruneReport {
+        dry_run,
+        total_runs: 0,
+        scripts: Vec::new(),
+        log_files: Vec::new(),
+        log_bytes: 0,
+    };
+
+    for script in scripts {
+        let override_policy = queries::get_script_retention(&conn, script.id).map_err(|e| e.to_string())?;
+        let policy = Policy::resolve(&settings, override_policy.as_ref());
+        if policy.is_unlimited() {
+            continue;
+        }
+        let runs = queries::get_run_summaries(&conn, script.id).map_err(|e| e.to_string())?;
+        let ids = runs_to_prune(&runs, &policy);
+        if ids.is_empty() {
+            continue;
+        }
+        if !dry_run {
+            queries::delete_run_records(&conn, &ids).map_err(|e| e.to_string())?;
+        }
+        report.total_runs += ids.len() as i64;
+        report.scripts.push(PrunedScript {
+            script_id: script.id,
+            script_name: script.name,
+            runs: ids.len() as i64,
+        });
+    }
+
+    for (path, size) in stale_log_files(settings.log_retention_days) {
+        if !dry_run && std::fs::remove_file(&path).is_err() {
+            continue;
+        }
+        report.log_bytes += size;
+        report.log_files.push(path.to_string_lossy().to_string());
+    }
+
+    if !dry_run && report.total_runs > 0 {
+        conn.execute_batch("VACUUM").map_err(|e| e.to_string())?;
+    }
+
+    Ok(report)
+}
+
+/// Prune once at startup and then every few hours.
+pub(crate) fn start_retention_worker(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let _ = prune(&app.state::<Database>(), false);
+        std::thread::sleep(PRUNE_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_script_retention(db: State<'_, Database>, script_id: i64) -> Result<Option<ScriptRetention>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_retention(&conn, script_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_script_retention(db: State<'_, Database>, policy: ScriptRetention) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, policy.script_id).map_err(|e| e.to_string())?;
+    let before = queries::get_script_retention(&conn, policy.script_id).map_err(|e| e.to_string())?;
+    queries::upsert_script_retention(&conn, &policy).map_err(|e| e.to_string())?;
+    audit::log(&conn, "script.retention_update", "script", Some(script.id), Some(&script.name), before.as_ref(), Some(&policy));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_script_retention(db: State<'_, Database>, script_id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    let before = queries::get_script_retention(&conn, script_id).map_err(|e| e.to_string())?;
+    queries::delete_script_retention(&conn, script_id).map_err(|e| e.to_string())?;
+    audit::log(&conn, "script.retention_update", "script", Some(script_id), Some(&script.name), before.as_ref(), None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn prune_history(db: State<'_, Database>, dry_run: bool) -> Result<PruneReport, String> {
+    let report = prune(&db, dry_run)?;
+    if !dry_run {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        audit::log::<PruneReport>(&conn, "history.prune", "history", None, None, None, Some(&report));
+    }
+    Ok(report)
+}
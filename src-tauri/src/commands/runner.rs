@@ -1,13 +1,43 @@
+use crate::commands::{audit, elevation, header, health, lint, protection, sandbox, secrets, versions};
+use crate::commands::metrics;
+use crate::commands::notifications;
+use crate::commands::output_rules::{OutputMatcher, RuleHit};
+use crate::commands::redaction::{self, Redactor};
+use crate::commands::secrets::VaultState;
 use crate::db::queries;
 use crate::db::Database;
+use crate::models::{NotificationPayload, PreviewEnvVar, RunPreview, RunUsage};
 use base64::Engine;
 use portable_pty::{CommandBuilder, MasterPty, NativePtySystem, PtySize, PtySystem};
 use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager, State};
 
+/// How long output may pause before a held-back partial line is emitted.
+const REDACTION_IDLE_FLUSH: Duration = Duration::from_millis(50);
+
+/// Output kept on a run record; later output is only streamed.
+const MAX_STORED_OUTPUT: usize = 50 * 1024;
+
+/// Append ANSI-stripped output to a run's stored output, up to
+/// `MAX_STORED_OUTPUT`.
+fn store_output(stored: &mut String, stripped: Vec<u8>) {
+    if stored.len() >= MAX_STORED_OUTPUT {
+        return;
+    }
+    if let Ok(text) = String::from_utf8(stripped) {
+        let mut end = text.len().min(MAX_STORED_OUTPUT - stored.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        stored.push_str(&text[..end]);
+    }
+}
+
 pub(crate) struct PtyProcess {
     writer: Box<dyn Write + Send>,
     master: Box<dyn MasterPty + Send>,
@@ -41,9 +71,88 @@ struct ScriptFinishedEvent {
     script_id: i64,
     exit_code: i32,
     record_id: i64,
+    status: String,
+}
+
+/// Splits streamed PTY text into complete lines for output rules and metrics.
+#[derive(Default)]
+struct LineBuffer {
+    partial: String,
+}
+
+impl LineBuffer {
+    fn push(&mut self, text: &str) -> Vec<String> {
+        self.partial.push_str(text);
+        let mut lines = Vec::new();
+        while let Some(pos) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=pos).collect();
+            lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        lines
+    }
+
+    /// Whatever is left once the stream closes without a final newline.
+    fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.partial);
+        let rest = rest.trim_end_matches('\r');
+        if rest.trim().is_empty() {
+            None
+        } else {
+            Some(rest.to_string())
+        }
+    }
+}
+
+fn notify_rule_hit(app: &AppHandle, script_id: i64, script_name: &str, run_id: i64, started_at: &str, hit: RuleHit) {
+    notifications::notify(
+        app,
+        NotificationPayload {
+            event: "match".to_string(),
+            script_id,
+            script_name: script_name.to_string(),
+            run_id,
+            status: "running".to_string(),
+            exit_code: None,
+            duration_seconds: None,
+            started_at: started_at.to_string(),
+            finished_at: None,
+            output_tail: hit.line,
+            matched_rule: Some(hit.rule_name),
+        },
+    );
+}
+
+#[cfg(unix)]
+fn has_shebang(script_path: &str) -> bool {
+    let mut magic = [0u8; 2];
+    std::fs::File::open(script_path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok_and(|_| &magic == b"#!")
+}
+
+/// The argv prefix that applies a script's resource limits; empty when it has
+/// none.
+fn limits_prefix(conn: &rusqlite::Connection, script_id: i64) -> Result<Vec<String>, String> {
+    #[cfg(unix)]
+    if let Some(script_limits) = queries::get_script_limits(conn, script_id).map_err(|e| e.to_string())? {
+        return crate::commands::limits::wrapper(&script_limits);
+    }
+    #[cfg(target_os = "windows")]
+    let _ = (conn, script_id);
+    Ok(Vec::new())
 }
 
-fn build_script_command(script_path: &str, run_as_admin: bool, powershell_exe: &str) -> CommandBuilder {
+/// `env` is the script's own environment, applied over Conduit's defaults.
+/// `elevation` is set for admin scripts, `sandbox` for sandboxed ones, and
+/// `limits` comes from `limits_prefix`.
+fn build_script_command(
+    script_path: &str,
+    elevation: Option<&elevation::Launch>,
+    sandbox: Option<&sandbox::Sandbox>,
+    limits: &[String],
+    powershell_exe: &str,
+    env: &[(String, String)],
+) -> CommandBuilder {
     #[cfg(unix)]
     {
         let _ = powershell_exe;
@@ -52,22 +161,42 @@ fn build_script_command(script_path: &str, run_as_admin: bool, powershell_exe: &
         } else {
             "/usr/local/bin:/usr/bin:/bin:/usr/local/sbin:/usr/sbin:/sbin"
         };
-        let mut cmd = if run_as_admin {
-            let mut c = CommandBuilder::new("sudo");
-            c.args(["/bin/bash", script_path]);
-            c
+        // Executable files with a shebang pick their own interpreter (python,
+        // node, ...); everything else goes through bash as before
+        let direct = crate::db::queries::check_is_executable(script_path) && has_shebang(script_path);
+        let mut target: Vec<String> = if direct {
+            vec![script_path.to_string()]
         } else {
-            let mut c = CommandBuilder::new("/bin/bash");
-            c.arg(script_path);
-            c
+            vec!["/bin/bash".to_string(), script_path.to_string()]
         };
+        // Inside elevation, since sudo can't raise privileges in a sandbox
+        if let Some(sandbox) = sandbox {
+            target.splice(0..0, sandbox.argv.iter().cloned());
+        }
+        let argv = match elevation {
+            Some(launch) => {
+                // sudo resets the environment unless told which variables to keep
+                let names: Vec<&str> = env.iter().map(|(name, _)| name.as_str()).collect();
+                launch.wrap(target, &names)
+            }
+            None => target,
+        };
+        // Limits go outermost so sudo and the script inherit them
+        let mut cmd = CommandBuilder::from_argv(limits.iter().cloned().chain(argv).map(Into::into).collect());
+        for (name, value) in elevation.map(|launch| launch.env()).unwrap_or_default() {
+            cmd.env(name, value);
+        }
         cmd.env("PATH", path_env);
         cmd.env("TERM", "xterm-256color");
+        for (name, value) in env {
+            cmd.env(name, value);
+        }
         cmd
     }
     #[cfg(target_os = "windows")]
     {
-        let mut cmd = if run_as_admin {
+        let _ = (sandbox, limits);
+        let mut cmd = if elevation.is_some() {
             let mut c = CommandBuilder::new(powershell_exe);
             c.args([
                 "-Command",
@@ -103,64 +232,268 @@ fn build_script_command(script_path: &str, run_as_admin: bool, powershell_exe: &
             }
         };
         cmd.env("TERM", "xterm-256color");
+        // Not passed on through an elevated Start-Process
+        for (name, value) in env {
+            cmd.env(name, value);
+        }
         cmd
     }
 }
 
+/// Reap `pid` with the resource usage of it and every descendant it waited
+/// for. Returns its exit code, or None if a signal ended it.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // timeval and rusage field types vary by platform
+fn wait_with_usage(pid: u32) -> std::io::Result<(Option<i32>, RunUsage)> {
+    let mut status: libc::c_int = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    while unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut usage) } == -1 {
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+    let ms = |tv: libc::timeval| tv.tv_sec as i64 * 1000 + tv.tv_usec as i64 / 1000;
+    // ru_maxrss is in kilobytes on Linux but bytes on macOS
+    let peak_rss_kb = if cfg!(target_os = "macos") {
+        usage.ru_maxrss as i64 / 1024
+    } else {
+        usage.ru_maxrss as i64
+    };
+    let exit_code = libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status));
+    Ok((
+        exit_code,
+        RunUsage {
+            peak_rss_kb: Some(peak_rss_kb),
+            user_cpu_ms: Some(ms(usage.ru_utime)),
+            system_cpu_ms: Some(ms(usage.ru_stime)),
+            wall_ms: None,
+        },
+    ))
+}
+
+/// Wait for a run's child: 0 for a clean exit, 1 for a failure and -1 when
+/// waiting failed. Resource usage is collected on Unix.
+fn wait_child(child: &mut (dyn portable_pty::Child + Send + Sync), pid: u32) -> (i32, RunUsage) {
+    #[cfg(unix)]
+    if pid != 0 {
+        return match wait_with_usage(pid) {
+            Ok((exit_code, usage)) => (if exit_code == Some(0) { 0 } else { 1 }, usage),
+            Err(_) => (-1, RunUsage::default()),
+        };
+    }
+    let _ = pid;
+    let exit_code = match child.wait() {
+        Ok(status) => {
+            if status.success() {
+                0
+            } else {
+                1
+            }
+        }
+        Err(_) => -1,
+    };
+    (exit_code, RunUsage::default())
+}
+
+/// Kill a running script along with the processes it started.
+fn terminate_process(active_processes: &Mutex<HashMap<i64, PtyProcess>>, script_id: i64, pid: u32) {
+    #[cfg(unix)]
+    {
+        let _ = (active_processes, script_id);
+        // A pid of 0 would signal our own process group
+        if pid != 0 {
+            unsafe {
+                // Kill the entire process group (negative PID) so child processes are also terminated
+                libc::kill(-(pid as i32), libc::SIGTERM);
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Fire-and-forget: don't block waiting for taskkill to finish,
+        // as tree enumeration (/T) can be slow.
+        let _ = std::process::Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+
+        // Drop the PtyProcess to close the ConPTY master handle. This breaks
+        // the pipe and unblocks the reader thread, which would otherwise block
+        // indefinitely on read() since taskkill doesn't close ConPTY pipes.
+        if let Ok(mut procs) = active_processes.lock() {
+            procs.remove(&script_id);
+        }
+    }
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn run_script(
     app: AppHandle,
     db: State<'_, Database>,
     runner: State<'_, RunnerState>,
+    vault: State<'_, VaultState>,
     script_id: i64,
     cols: Option<u16>,
     rows: Option<u16>,
+    confirmation: Option<String>,
 ) -> Result<i64, String> {
     let pty_cols = cols.unwrap_or(80);
     let pty_rows = rows.unwrap_or(24);
 
     // Get script and settings from DB
-    let (script_path, run_as_admin, powershell_exe) = {
+    let (
+        script_name,
+        script_path,
+        elevation_method,
+        sandbox,
+        limits,
+        timeout_seconds,
+        powershell_exe,
+        output_rules,
+        env,
+        redactor,
+        previous_run,
+    ) = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
         let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+        health::ensure_runnable(&conn, &script)?;
+        protection::enforce(&conn, &script, confirmation.as_deref(), false)?;
+        // Header edits made since the header was last applied take effect for
+        // this run, so a changed @timeout applies; the library is left alone
+        let script = header::preview_header(&conn, &script)?;
         let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+        let output_rules = queries::get_output_rules(&conn, script_id).map_err(|e| e.to_string())?;
+        let env = secrets::resolve_script_env(&conn, &vault, script_id)?;
+        // Secret values and redaction rule matches never reach the terminal,
+        // the stored output or output rules
+        let redactor = Redactor::new(&env.secrets, redaction::load_rules(&conn, script_id)?);
+        let elevation_method = if script.run_as_admin {
+            Some(elevation::resolve_method(&settings.elevation_method, !env.vars.is_empty())?)
+        } else {
+            None
+        };
+        let sandbox = sandbox::resolve(&conn, script_id)?;
+        let limits = limits_prefix(&conn, script_id)?;
+        // Start the cooldown while still holding the lock the check ran under
+        let previous_run = protection::mark_run(&conn, script_id);
         (
+            script.name,
             script.path,
-            script.run_as_admin,
+            elevation_method,
+            sandbox,
+            limits,
+            script.timeout_seconds,
             crate::commands::powershell_exe(&settings.powershell_version),
+            output_rules,
+            env.vars,
+            redactor,
+            previous_run,
         )
     };
+    let release_run = |e: String| {
+        if let Ok(conn) = db.conn.lock() {
+            protection::release_run(&conn, script_id, previous_run.as_deref());
+        }
+        e
+    };
+
+    lint::pre_run_check(&app, &db, script_id).map_err(release_run)?;
+
+    let mut launch = elevation_method
+        .map(|method| elevation::prepare(method, Some(&app), script_id))
+        .transpose()
+        .map_err(release_run)?;
 
     // Create run record
     let started_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let record = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        queries::insert_run_record(&conn, script_id, &started_at).map_err(|e| e.to_string())?
+        let record = match queries::insert_run_record(&conn, script_id, &started_at) {
+            Ok(record) => record,
+            Err(e) => {
+                protection::release_run(&conn, script_id, previous_run.as_deref());
+                if let Some(ref mut launch) = launch {
+                    launch.finish();
+                    let details = serde_json::json!({ "method": launch.method.as_str(), "error": e.to_string() });
+                    audit_elevated_run(&conn, script_id, &script_name, details);
+                }
+                return Err(e.to_string());
+            }
+        };
+        if let Some(ref sandbox) = sandbox {
+            let _ = queries::set_run_sandbox(&conn, record.id, &sandbox.profile);
+        }
+        // Keep the content this run executes so later failures can be diffed
+        let version_id = queries::get_script_by_id(&conn, script_id)
+            .ok()
+            .and_then(|script| versions::snapshot_script(&conn, &script).ok().flatten());
+        if let Some(version_id) = version_id {
+            let _ = queries::set_run_version(&conn, record.id, version_id);
+        }
+        let details = serde_json::json!({ "runId": record.id });
+        audit::log(&conn, "script.run", "script", Some(script_id), Some(&script_name), None, Some(&details));
+        record
     };
     let record_id = record.id;
+    let run_started = std::time::Instant::now();
 
-    // Open PTY pair
-    let pty_system = NativePtySystem::default();
-    let pair = pty_system
-        .openpty(PtySize {
-            rows: pty_rows,
-            cols: pty_cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })
-        .map_err(|e| e.to_string())?;
-
-    // Spawn child on the slave
-    let cmd = build_script_command(&script_path, run_as_admin, powershell_exe);
-    let child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
+    // Open a PTY and spawn the child on its slave, with a reader from the
+    // master and a writer for stdin
+    let started = (|| {
+        let pair = NativePtySystem::default()
+            .openpty(PtySize {
+                rows: pty_rows,
+                cols: pty_cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| e.to_string())?;
+        let cmd = build_script_command(&script_path, launch.as_ref(), sandbox.as_ref(), &limits, powershell_exe, &env);
+        let mut child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
+        // Drop slave — the child owns its end now
+        drop(pair.slave);
+        let pipes = pair
+            .master
+            .try_clone_reader()
+            .and_then(|reader| Ok((reader, pair.master.take_writer()?)));
+        match pipes {
+            Ok((reader, writer)) => Ok((child, pair.master, reader, writer)),
+            Err(e) => {
+                let _ = child.kill();
+                Err(e.to_string())
+            }
+        }
+    })();
+    let (child, master, reader, writer) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            abandon_run(&db, record_id, script_id, &script_name, previous_run.as_deref(), launch.as_mut(), &e);
+            return Err(e);
+        }
+    };
     let child_pid = child.process_id().unwrap_or(0);
+    let spawned_at = std::time::Instant::now();
 
-    // Drop slave — the child owns its end now
-    drop(pair.slave);
-
-    // Get a reader from the master and a writer for stdin
-    let reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
-    let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
+    notifications::notify(
+        &app,
+        NotificationPayload {
+            event: "start".to_string(),
+            script_id,
+            script_name: script_name.clone(),
+            run_id: record_id,
+            status: "running".to_string(),
+            exit_code: None,
+            duration_seconds: None,
+            started_at: started_at.clone(),
+            finished_at: None,
+            output_tail: String::new(),
+            matched_rule: None,
+        },
+    );
 
     // Store PtyProcess in active_processes
     {
@@ -169,7 +502,7 @@ pub async fn run_script(
             script_id,
             PtyProcess {
                 writer,
-                master: pair.master,
+                master,
                 child_pid,
             },
         );
@@ -179,12 +512,44 @@ pub async fn run_script(
     let cancelled = Arc::clone(&runner.cancelled_scripts);
     let app_handle = app.clone();
 
+    // The watchdog gives up once the reader thread drops `run_done`
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let (run_done, run_done_rx) = mpsc::channel::<()>();
+    if let Some(timeout) = timeout_seconds.filter(|t| *t > 0) {
+        let procs = Arc::clone(&runner.active_processes);
+        let timed_out = Arc::clone(&timed_out);
+        std::thread::spawn(move || {
+            if run_done_rx.recv_timeout(Duration::from_secs(timeout as u64)) == Err(mpsc::RecvTimeoutError::Timeout) {
+                timed_out.store(true, Ordering::SeqCst);
+                terminate_process(&procs, script_id, child_pid);
+            }
+        });
+    }
+
     // Spawn a std::thread for blocking PTY reads
     std::thread::spawn(move || {
+        let _run_done = run_done;
         let mut reader = reader;
         let mut buf = [0u8; 4096];
         let mut output_acc = String::new();
-        let max_output_bytes: usize = 50 * 1024;
+        let mut matcher = OutputMatcher::new(output_rules);
+        let mut lines = LineBuffer::default();
+        let mut run_metrics: Vec<(String, f64, String)> = Vec::new();
+        let mut redactor = redactor;
+        let mut handle_line = |line: &str| {
+            if !matcher.is_empty() {
+                for hit in matcher.check_line(line) {
+                    notify_rule_hit(&app_handle, script_id, &script_name, record_id, &started_at, hit);
+                }
+            }
+            let room = metrics::MAX_METRICS_PER_RUN.saturating_sub(run_metrics.len());
+            if room > 0 {
+                let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                for (name, value) in metrics::parse_metric_line(line).into_iter().take(room) {
+                    run_metrics.push((name, value, now.clone()));
+                }
+            }
+        };
         let b64 = base64::engine::general_purpose::STANDARD;
 
         // Spawn a waiter thread that blocks on child.wait(). When the child
@@ -197,58 +562,69 @@ pub async fn run_script(
         let active_procs_waiter = Arc::clone(&active_procs);
         let child_waiter = std::thread::spawn(move || {
             let mut child = child;
-            let exit_code = match child.wait() {
-                Ok(status) => {
-                    if status.success() {
-                        0
-                    } else {
-                        1
-                    }
-                }
-                Err(_) => -1,
-            };
+            let (exit_code, mut usage) = wait_child(child.as_mut(), child_pid);
+            usage.wall_ms = Some(spawned_at.elapsed().as_millis() as i64);
             // Drop the PtyProcess to close the master and unblock the reader
             if let Ok(mut procs) = active_procs_waiter.lock() {
                 procs.remove(&script_id);
             }
-            exit_code
+            (exit_code, usage)
         });
 
-        loop {
+        let mut handle_chunk = |chunk: &[u8]| {
+            if chunk.is_empty() {
+                return;
+            }
+
+            // Emit base64-encoded chunk
+            let encoded = b64.encode(chunk);
+            let _ = app_handle.emit(
+                "script-output",
+                ScriptOutputEvent {
+                    script_id,
+                    data: encoded,
+                },
+            );
+
+            // Accumulate ANSI-stripped text for DB. Output rules and
+            // metrics keep seeing lines after the stored text is capped.
+            let stripped = strip_ansi_escapes::strip(chunk);
+            for line in lines.push(&String::from_utf8_lossy(&stripped)) {
+                handle_line(&line);
+            }
+            store_output(&mut output_acc, stripped);
+        };
+
+        // Reads happen on their own thread so a partial line the redactor
+        // is holding back (such as a prompt) is released once output pauses
+        let (chunk_tx, chunk_rx) = mpsc::channel::<Vec<u8>>();
+        std::thread::spawn(move || loop {
             match reader.read(&mut buf) {
-                Ok(0) => break,
+                Ok(0) | Err(_) => break,
                 Ok(n) => {
-                    let chunk = &buf[..n];
-
-                    // Emit base64-encoded chunk
-                    let encoded = b64.encode(chunk);
-                    let _ = app_handle.emit(
-                        "script-output",
-                        ScriptOutputEvent {
-                            script_id,
-                            data: encoded,
-                        },
-                    );
-
-                    // Accumulate ANSI-stripped text for DB
-                    if output_acc.len() < max_output_bytes {
-                        let stripped = strip_ansi_escapes::strip(chunk);
-                        if let Ok(text) = String::from_utf8(stripped) {
-                            let remaining = max_output_bytes - output_acc.len();
-                            if text.len() <= remaining {
-                                output_acc.push_str(&text);
-                            } else {
-                                output_acc.push_str(&text[..remaining]);
-                            }
-                        }
+                    if chunk_tx.send(buf[..n].to_vec()).is_err() {
+                        break;
                     }
                 }
-                Err(_) => break,
+            }
+        });
+        loop {
+            match chunk_rx.recv_timeout(REDACTION_IDLE_FLUSH) {
+                Ok(chunk) => handle_chunk(&redactor.push(&chunk)),
+                Err(mpsc::RecvTimeoutError::Timeout) => handle_chunk(&redactor.flush()),
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
             }
         }
+        handle_chunk(&redactor.flush());
+
+        if let Some(line) = lines.finish() {
+            handle_line(&line);
+        }
 
         // The child waiter has already finished (it's what unblocked us)
-        let exit_code = child_waiter.join().unwrap_or(-1);
+        let (exit_code, usage) = child_waiter.join().unwrap_or((-1, RunUsage::default()));
+        let mut launch = launch;
+        let elevation_error = launch.as_mut().and_then(elevation::Launch::finish);
 
         // Check if this script was explicitly cancelled
         let was_cancelled = cancelled
@@ -256,8 +632,19 @@ pub async fn run_script(
             .map(|mut set| set.remove(&script_id))
             .unwrap_or(false);
 
-        let status = if exit_code == 0 {
-            "success"
+        let timed_out = timed_out.load(Ordering::SeqCst);
+        if timed_out {
+            output_acc.push_str(&format!("\n[Timed out after {}s]\n", timeout_seconds.unwrap_or(0)));
+        }
+        if let Some(ref error) = elevation_error {
+            output_acc.push_str(&format!("\n[{}]\n", error));
+        }
+
+        // Output rules can downgrade a clean exit to a warning or failure
+        let status = if timed_out {
+            "error"
+        } else if exit_code == 0 {
+            matcher.severity().unwrap_or("success")
         } else if was_cancelled {
             "cancelled"
         } else {
@@ -276,6 +663,35 @@ pub async fn run_script(
                 Some(&output_acc),
                 status,
             );
+            if !matcher.matches().is_empty() {
+                let _ = queries::insert_run_matches(&conn, record_id, matcher.matches());
+            }
+            if let Some(metadata) = matcher.metadata() {
+                let _ = queries::set_run_metadata(&conn, record_id, &metadata);
+            }
+            for (name, value, recorded_at) in &run_metrics {
+                let _ = queries::insert_run_metric(&conn, record_id, name, *value, recorded_at);
+            }
+            if redactor.count() > 0 {
+                let _ = queries::set_run_redactions(&conn, record_id, redactor.count());
+            }
+            let _ = queries::set_run_usage(&conn, record_id, &usage);
+            if let Some(ref launch) = launch {
+                let method = launch.method.as_str();
+                let _ = queries::set_run_elevation(&conn, record_id, method, elevation_error.as_deref());
+                audit_elevated_run(
+                    &conn,
+                    script_id,
+                    &script_name,
+                    serde_json::json!({
+                        "runId": record_id,
+                        "method": method,
+                        "status": status,
+                        "exitCode": exit_code,
+                        "error": elevation_error,
+                    }),
+                );
+            }
         }
 
         // Remove from active processes (no-op if waiter already removed it)
@@ -283,6 +699,23 @@ pub async fn run_script(
             procs.remove(&script_id);
         }
 
+        notifications::notify(
+            &app_handle,
+            NotificationPayload {
+                event: if status == "error" { "failure" } else { "finish" }.to_string(),
+                script_id,
+                script_name,
+                run_id: record_id,
+                status: status.to_string(),
+                exit_code: Some(exit_code),
+                duration_seconds: Some(run_started.elapsed().as_secs() as i64),
+                started_at,
+                finished_at: Some(finished_at),
+                output_tail: notifications::output_tail(&output_acc),
+                matched_rule: None,
+            },
+        );
+
         // Emit finished event
         let _ = app_handle.emit(
             "script-finished",
@@ -290,6 +723,7 @@ pub async fn run_script(
                 script_id,
                 exit_code,
                 record_id,
+                status: status.to_string(),
             },
         );
     });
@@ -297,6 +731,293 @@ pub async fn run_script(
     Ok(record_id)
 }
 
+/// Close out a run whose child couldn't be started: record the error on its
+/// run record, give back the cooldown it took and, for admin scripts, shut
+/// the elevation down and audit the attempt.
+fn abandon_run(
+    db: &Database,
+    record_id: i64,
+    script_id: i64,
+    script_name: &str,
+    previous_run: Option<&str>,
+    mut launch: Option<&mut elevation::Launch>,
+    error: &str,
+) {
+    // The askpass socket and its directory go even if the lock is poisoned
+    if let Some(launch) = launch.as_mut() {
+        launch.finish();
+    }
+    let Ok(conn) = db.conn.lock() else {
+        return;
+    };
+    let finished_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let output = format!("[Failed to start: {}]\n", error);
+    let _ = queries::update_run_record(&conn, record_id, &finished_at, None, Some(&output), "error");
+    protection::release_run(&conn, script_id, previous_run);
+    if let Some(launch) = launch {
+        let method = launch.method.as_str();
+        let _ = queries::set_run_elevation(&conn, record_id, method, Some(error));
+        audit_elevated_run(
+            &conn,
+            script_id,
+            script_name,
+            serde_json::json!({ "runId": record_id, "method": method, "status": "error", "error": error }),
+        );
+    }
+}
+
+fn audit_elevated_run(conn: &rusqlite::Connection, script_id: i64, script_name: &str, details: serde_json::Value) {
+    audit::log(conn, "script.run_elevated", "script", Some(script_id), Some(script_name), None, Some(&details));
+}
+
+/// Resolve everything `run_script` would spawn for a script — argv,
+/// environment, working directory and PTY size — without running it or
+/// creating a run record.
+#[tauri::command]
+pub fn preview_run(
+    db: State<'_, Database>,
+    script_id: i64,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<RunPreview, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    // The confirmation phrase is asked for at run time, so only the window
+    // and cooldown can block here
+    let mut blocked = health::check_runnable(&conn, &script)
+        .and_then(|_| protection::check(&conn, &script, None, true))
+        .err();
+    let script = header::preview_header(&conn, &script)?;
+    let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+    let lint_policy = lint::effective_policy(&conn, &script)?;
+    let elevation_method = if script.run_as_admin {
+        let has_env = queries::script_has_env(&conn, script_id).map_err(|e| e.to_string())?;
+        match elevation::resolve_method(&settings.elevation_method, has_env) {
+            Ok(method) => Some(method),
+            Err(e) => {
+                blocked = blocked.or(Some(e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // Secrets are shown by name only, so the vault needn't be unlocked
+    let script_env = secrets::masked_script_env(&conn, script_id)?;
+    let secret_names: HashSet<&str> = script_env
+        .iter()
+        .filter(|(_, _, secret)| *secret)
+        .map(|(name, _, _)| name.as_str())
+        .collect();
+    let env_vars: Vec<(String, String)> = script_env
+        .iter()
+        .map(|(name, value, _)| (name.clone(), value.clone()))
+        .collect();
+
+    let sandbox = match sandbox::resolve(&conn, script_id) {
+        Ok(sandbox) => sandbox,
+        Err(e) => {
+            blocked = blocked.or(Some(e));
+            None
+        }
+    };
+    let limits = match limits_prefix(&conn, script_id) {
+        Ok(prefix) => prefix,
+        Err(e) => {
+            blocked = blocked.or(Some(e));
+            Vec::new()
+        }
+    };
+    let launch = elevation_method.map(elevation::Launch::preview);
+    let cmd = build_script_command(
+        &script.path,
+        launch.as_ref(),
+        sandbox.as_ref(),
+        &limits,
+        crate::commands::powershell_exe(&settings.powershell_version),
+        &env_vars,
+    );
+    let overridden: HashSet<&str> = cmd.iter_extra_env_as_str().map(|(name, _)| name).collect();
+    let env = cmd
+        .iter_full_env_as_str()
+        .map(|(name, value)| PreviewEnvVar {
+            name: name.to_string(),
+            value: value.to_string(),
+            overridden: overridden.contains(name),
+            secret: secret_names.contains(name),
+        })
+        .collect();
+    // portable-pty starts children in the home directory unless told otherwise
+    let cwd = cmd
+        .get_cwd()
+        .map(std::path::PathBuf::from)
+        .or_else(dirs::home_dir)
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok(RunPreview {
+        script_id,
+        argv: cmd.get_argv().iter().map(|arg| arg.to_string_lossy().to_string()).collect(),
+        env,
+        cwd,
+        cols: cols.unwrap_or(80),
+        rows: rows.unwrap_or(24),
+        run_as_admin: script.run_as_admin,
+        elevation: elevation_method.map(|method| method.as_str().to_string()),
+        sandbox: sandbox.map(|sandbox| sandbox.profile),
+        timeout_seconds: script.timeout_seconds.filter(|t| *t > 0),
+        lint_policy,
+        blocked,
+    })
+}
+
+/// Copy redacted output through, keeping a copy for the run record.
+fn pipe_redacted(mut from: impl Read, mut to: impl Write, mut redactor: Redactor, stored: &Mutex<String>) {
+    let mut buf = [0u8; 4096];
+    let mut write = |chunk: Vec<u8>| {
+        let _ = to.write_all(&chunk);
+        let _ = to.flush();
+        if let Ok(mut stored) = stored.lock() {
+            store_output(&mut stored, strip_ansi_escapes::strip(&chunk));
+        }
+    };
+    while let Ok(n) = from.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        write(redactor.push(&buf[..n]));
+    }
+    write(redactor.flush());
+}
+
+fn run_scheduled_script(script_id: &str) -> Result<i32, String> {
+    let script_id: i64 = script_id.parse().map_err(|_| format!("Invalid script id: {}", script_id))?;
+    let db = Database::new().map_err(|e| e.to_string())?;
+    lint::scheduled_check(&db, script_id)?;
+    let (cmd, secrets, rules, mut launch, script_name, record_id, previous_run) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+        health::ensure_runnable(&conn, &script)?;
+        protection::enforce(&conn, &script, None, true)?;
+        let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+        let env = secrets::resolve_script_env(&conn, &VaultState::new(), script_id)?;
+        let powershell_exe = crate::commands::powershell_exe(&settings.powershell_version);
+        let rules = redaction::load_rules(&conn, script_id)?;
+        let launch = elevation::scheduled_launch(script.run_as_admin).inspect_err(|e| {
+            audit_elevated_run(&conn, script_id, &script.name, serde_json::json!({ "scheduled": true, "error": e }));
+        })?;
+        let sandbox = sandbox::resolve(&conn, script_id)?;
+        // On Linux scheduled runs get resource limits from their systemd unit
+        let limits = if cfg!(target_os = "linux") { Vec::new() } else { limits_prefix(&conn, script_id)? };
+        let cmd = build_script_command(&script.path, launch.as_ref(), sandbox.as_ref(), &limits, powershell_exe, &env.vars);
+
+        // Recorded like an interactive run, so its output and resource
+        // usage show up in the run history
+        let started_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let record = queries::insert_run_record(&conn, script_id, &started_at).map_err(|e| e.to_string())?;
+        let previous_run = protection::mark_run(&conn, script_id);
+        if let Some(ref sandbox) = sandbox {
+            let _ = queries::set_run_sandbox(&conn, record.id, &sandbox.profile);
+        }
+        if let Ok(Some(version_id)) = versions::snapshot_script(&conn, &script) {
+            let _ = queries::set_run_version(&conn, record.id, version_id);
+        }
+        let details = serde_json::json!({ "runId": record.id, "scheduled": true });
+        audit::log(&conn, "script.run", "script", Some(script_id), Some(&script.name), None, Some(&details));
+        (cmd, env.secrets, rules, launch, script.name, record.id, previous_run)
+    };
+
+    let argv = cmd.get_argv();
+    let mut command = std::process::Command::new(&argv[0]);
+    command
+        .args(&argv[1..])
+        .envs(cmd.iter_extra_env_as_str())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let error = format!("Failed to start {}: {}", argv[0].to_string_lossy(), e);
+            abandon_run(&db, record_id, script_id, &script_name, previous_run.as_deref(), launch.as_mut(), &error);
+            return Err(error);
+        }
+    };
+    let spawned_at = std::time::Instant::now();
+
+    let output = Arc::new(Mutex::new(String::new()));
+    let stderr = child.stderr.take();
+    let stderr_redactor = Redactor::new(&secrets, rules.clone());
+    let stderr_output = Arc::clone(&output);
+    let stderr_thread = std::thread::spawn(move || {
+        if let Some(stderr) = stderr {
+            pipe_redacted(stderr, std::io::stderr(), stderr_redactor, &stderr_output);
+        }
+    });
+    if let Some(stdout) = child.stdout.take() {
+        pipe_redacted(stdout, std::io::stdout(), Redactor::new(&secrets, rules), &output);
+    }
+    let _ = stderr_thread.join();
+    // -1 when waiting failed, as for interactive runs
+    #[cfg(unix)]
+    let (exit_code, mut usage) = match wait_with_usage(child.id()) {
+        Ok((exit_code, usage)) => (exit_code.unwrap_or(1), usage),
+        Err(_) => (-1, RunUsage::default()),
+    };
+    #[cfg(target_os = "windows")]
+    let (exit_code, mut usage) = (
+        child.wait().map(|status| status.code().unwrap_or(1)).unwrap_or(-1),
+        RunUsage::default(),
+    );
+    usage.wall_ms = Some(spawned_at.elapsed().as_millis() as i64);
+
+    let elevation_error = launch.as_mut().and_then(elevation::Launch::finish);
+    let mut output = output.lock().map(|o| o.clone()).unwrap_or_default();
+    if let Some(ref error) = elevation_error {
+        eprintln!("conduit: {}", error);
+        output.push_str(&format!("\n[{}]\n", error));
+    }
+    let status = if exit_code == 0 { "success" } else { "error" };
+    let finished_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let _ = queries::update_run_record(&conn, record_id, &finished_at, Some(exit_code), Some(&output), status);
+    let _ = queries::set_run_usage(&conn, record_id, &usage);
+    if let Some(ref launch) = launch {
+        let method = launch.method.as_str();
+        let _ = queries::set_run_elevation(&conn, record_id, method, elevation_error.as_deref());
+        audit_elevated_run(
+            &conn,
+            script_id,
+            &script_name,
+            serde_json::json!({
+                "runId": record_id,
+                "scheduled": true,
+                "method": method,
+                "status": status,
+                "exitCode": exit_code,
+                "error": elevation_error,
+            }),
+        );
+    }
+    Ok(exit_code)
+}
+
+/// Entry point for `conduit --run-script <id>`, which scheduled tasks use for
+/// scripts with environment variables or that run as admin. Secrets are
+/// decrypted with the vault key remembered in the OS keyring and redacted
+/// from the output the task logs; admin scripts elevate with `sudo -n`.
+/// The run, its output and its resource usage are kept in the run history.
+/// Returns the script's exit code.
+pub fn run_scheduled(script_id: &str) -> i32 {
+    match run_scheduled_script(script_id) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("conduit: {}", e);
+            1
+        }
+    }
+}
+
 #[tauri::command]
 pub fn write_script_input(
     runner: State<'_, RunnerState>,
@@ -339,7 +1060,7 @@ pub fn resize_script_pty(
 }
 
 #[tauri::command]
-pub fn cancel_script(runner: State<'_, RunnerState>, script_id: i64) -> Result<(), String> {
+pub fn cancel_script(db: State<'_, Database>, runner: State<'_, RunnerState>, script_id: i64) -> Result<(), String> {
     // Extract PID while holding the lock briefly
     let pid = {
         let procs = runner.active_processes.lock().map_err(|e| e.to_string())?;
@@ -354,30 +1075,11 @@ pub fn cancel_script(runner: State<'_, RunnerState>, script_id: i64) -> Result<(
         set.insert(script_id);
     }
 
-    #[cfg(unix)]
-    unsafe {
-        // Kill the entire process group (negative PID) so child processes are also terminated
-        libc::kill(-(pid as i32), libc::SIGTERM);
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        // Fire-and-forget: don't block waiting for taskkill to finish,
-        // as tree enumeration (/T) can be slow.
-        let _ = std::process::Command::new("taskkill")
-            .args(["/T", "/F", "/PID", &pid.to_string()])
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn();
-
-        // Drop the PtyProcess to close the ConPTY master handle. This breaks
-        // the pipe and unblocks the reader thread, which would otherwise block
-        // indefinitely on read() since taskkill doesn't close ConPTY pipes.
-        if let Ok(mut procs) = runner.active_processes.lock() {
-            procs.remove(&script_id);
-        }
-    }
+    terminate_process(&runner.active_processes, script_id, pid);
 
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script_name = queries::get_script_by_id(&conn, script_id).map(|s| s.name).ok();
+    audit::log::<()>(&conn, "script.cancel", "script", Some(script_id), script_name.as_deref(), None, None);
     Ok(())
 }
 
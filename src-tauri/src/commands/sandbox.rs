@@ -0,0 +1,304 @@
+use crate::commands::{audit, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewSandboxConfig, SandboxConfig, SandboxProfile, SandboxTools};
+use rusqlite::Connection;
+use tauri::State;
+
+const TOOLS: [&str; 3] = ["auto", "bwrap", "firejail"];
+
+/// A script's sandbox resolved for a run: the argv prefix that starts a
+/// command inside it, and the profile recorded with the run.
+pub(crate) struct Sandbox {
+    pub(crate) argv: Vec<String>,
+    pub(crate) profile: SandboxProfile,
+}
+
+#[cfg(unix)]
+fn find(tool: &str) -> Option<String> {
+    crate::commands::elevation::find_tool(tool).map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn find(_tool: &str) -> Option<String> {
+    None
+}
+
+/// The tool to use under `setting` ("auto" prefers bubblewrap) and its path.
+fn locate(setting: &str) -> Result<(&'static str, String), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("Sandboxed runs are only supported on Linux".to_string());
+    }
+    let candidates: &[&'static str] = match setting {
+        "bwrap" => &["bwrap"],
+        "firejail" => &["firejail"],
+        _ => &["bwrap", "firejail"],
+    };
+    candidates
+        .iter()
+        .find_map(|tool| find(tool).map(|path| (*tool, path)))
+        .ok_or_else(|| match setting {
+            "bwrap" => "This script runs sandboxed with bubblewrap, but bwrap isn't installed".to_string(),
+            "firejail" => "This script runs sandboxed with firejail, but firejail isn't installed".to_string(),
+            _ => "This script runs sandboxed, but neither bubblewrap (bwrap) nor firejail is installed".to_string(),
+        })
+}
+
+/// Every namespace is unshared (the network too unless it's allowed) and the
+/// command gets its own session, so it can't reach the caller's terminal
+/// with TIOCSTI. `/` is bound read-only with a fresh /proc, /dev is rebound
+/// writable for the terminal and /dev/null, and /tmp and the runtime dir get
+/// empty tmpfs mounts, hiding the sockets (D-Bus, the session manager) that
+/// could run commands outside the sandbox. Write paths are bound last so ones
+/// under those stay visible.
+fn bwrap_argv(path: String, config: &SandboxConfig, runtime_dir: Option<&str>) -> Vec<String> {
+    let mut argv: Vec<String> = [path.as_str(), "--unshare-all"].map(String::from).to_vec();
+    if config.allow_network {
+        argv.push("--share-net".to_string());
+    }
+    argv.extend(
+        [
+            "--new-session",
+            "--die-with-parent",
+            "--unsetenv",
+            "DBUS_SESSION_BUS_ADDRESS",
+            "--ro-bind",
+            "/",
+            "/",
+            "--proc",
+            "/proc",
+            "--dev-bind",
+            "/dev",
+            "/dev",
+            "--tmpfs",
+            "/tmp",
+        ]
+        .map(String::from),
+    );
+    if let Some(dir) = runtime_dir.filter(|dir| !dir.is_empty()) {
+        argv.extend(["--tmpfs".to_string(), dir.to_string()]);
+    }
+    for write_path in &config.write_paths {
+        argv.extend(["--bind".to_string(), write_path.clone(), write_path.clone()]);
+    }
+    argv.push("--".to_string());
+    argv
+}
+
+/// The same isolation as `bwrap_argv`: D-Bus is cut off, /tmp is private
+/// and the runtime dir hidden. `--noprofile` drops firejail's default
+/// seccomp filter and no-new-privileges, so they're asked for explicitly;
+/// the filter blocks the TIOCSTI ioctl.
+fn firejail_argv(path: String, config: &SandboxConfig, runtime_dir: Option<&str>) -> Vec<String> {
+    let mut argv = vec![
+        path,
+        "--quiet".to_string(),
+        "--noprofile".to_string(),
+        "--read-only=/".to_string(),
+        "--dbus-user=none".to_string(),
+        "--dbus-system=none".to_string(),
+        "--private-tmp".to_string(),
+        "--nonewprivs".to_string(),
+        "--seccomp".to_string(),
+    ];
+    if let Some(dir) = runtime_dir.filter(|dir| !dir.is_empty()) {
+        argv.push(format!("--blacklist={}", dir));
+    }
+    argv.extend(config.write_paths.iter().map(|p| format!("--read-write={}", p)));
+    if !config.allow_network {
+        argv.push("--net=none".to_string());
+    }
+    argv.push("--".to_string());
+    argv
+}
+
+/// The sandbox a script's runs start in, or None if it isn't sandboxed.
+/// Fails rather than running unsandboxed when the tool is missing.
+pub(crate) fn resolve(conn: &Connection, script_id: i64) -> Result<Option<Sandbox>, String> {
+    let Some(config) = queries::get_script_sandbox(conn, script_id).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let (tool, path) = locate(&config.tool)?;
+    if let Some(missing) = config.write_paths.iter().find(|p| !std::path::Path::new(p).exists()) {
+        return Err(format!("Sandbox write path {} doesn't exist", missing));
+    }
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").ok();
+    // firejail can't bind paths back into its private /tmp or the hidden
+    // runtime dir the way bubblewrap does
+    if tool == "firejail" {
+        let hidden = ["/tmp"].into_iter().chain(runtime_dir.as_deref().filter(|dir| !dir.is_empty()));
+        for dir in hidden {
+            if let Some(path) = config.write_paths.iter().find(|p| std::path::Path::new(p).starts_with(dir)) {
+                return Err(format!("Sandbox write path {} is under {}, which firejail hides; use bubblewrap", path, dir));
+            }
+        }
+    }
+    let argv = match tool {
+        "bwrap" => bwrap_argv(path, &config, runtime_dir.as_deref()),
+        _ => firejail_argv(path, &config, runtime_dir.as_deref()),
+    };
+    Ok(Some(Sandbox {
+        argv,
+        profile: SandboxProfile {
+            tool: tool.to_string(),
+            read_only_root: true,
+            write_paths: config.write_paths,
+            network: config.allow_network,
+        },
+    }))
+}
+
+fn validate(sandbox: &NewSandboxConfig) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("Sandboxed runs are only supported on Linux".to_string());
+    }
+    if let Some(ref tool) = sandbox.tool {
+        if !TOOLS.contains(&tool.as_str()) {
+            return Err(format!("Unknown sandbox tool: {}", tool));
+        }
+    }
+    for path in &sandbox.write_paths {
+        if !std::path::Path::new(path).is_absolute() {
+            return Err(format!("Sandbox write paths must be absolute: {}", path));
+        }
+        if std::path::Path::new(path).parent().is_none() {
+            return Err("The whole filesystem can't be writable in the sandbox".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_script_sandbox(db: State<'_, Database>, script_id: i64) -> Result<Option<SandboxConfig>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_sandbox(&conn, script_id).map_err(|e| e.to_string())
+}
+
+/// Sandbox a script's runs, change the sandbox, or with None stop
+/// sandboxing it.
+#[tauri::command]
+pub fn set_script_sandbox(
+    db: State<'_, Database>,
+    script_id: i64,
+    sandbox: Option<NewSandboxConfig>,
+) -> Result<Option<SandboxConfig>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    let before = queries::get_script_sandbox(&conn, script_id).map_err(|e| e.to_string())?;
+    match sandbox {
+        Some(ref new) => {
+            validate(new)?;
+            let mut write_paths: Vec<String> = Vec::new();
+            for path in &new.write_paths {
+                let path = path.trim_end_matches('/').to_string();
+                if !write_paths.contains(&path) {
+                    write_paths.push(path);
+                }
+            }
+            queries::upsert_script_sandbox(
+                &conn,
+                script_id,
+                new.tool.as_deref().unwrap_or("auto"),
+                &write_paths,
+                new.allow_network.unwrap_or(true),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        None => queries::delete_script_sandbox(&conn, script_id).map_err(|e| e.to_string())?,
+    }
+    let after = queries::get_script_sandbox(&conn, script_id).map_err(|e| e.to_string())?;
+    // Scheduled runs of sandboxed scripts go through `conduit --run-script`
+    // so they start in the sandbox too
+    if before.is_some() != after.is_some() {
+        scheduler::reinstall_script_schedules(&conn, script_id)?;
+    }
+    audit::log(&conn, "script.sandbox_update", "script", Some(script_id), Some(&script.name), before.as_ref(), after.as_ref());
+    Ok(after)
+}
+
+/// Which sandbox tools are installed.
+#[tauri::command]
+pub fn get_sandbox_tools() -> SandboxTools {
+    SandboxTools {
+        bwrap: find("bwrap").is_some(),
+        firejail: find("firejail").is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(write_paths: &[&str], allow_network: bool) -> SandboxConfig {
+        SandboxConfig {
+            script_id: 1,
+            tool: "auto".to_string(),
+            write_paths: write_paths.iter().map(|p| p.to_string()).collect(),
+            allow_network,
+        }
+    }
+
+    fn position(argv: &[String], args: &[&str]) -> usize {
+        argv.windows(args.len())
+            .position(|window| window == args)
+            .unwrap_or_else(|| panic!("{:?} not in {:?}", args, argv))
+    }
+
+    #[test]
+    fn bwrap_isolates_namespaces_session_and_sockets() {
+        let argv = bwrap_argv("/usr/bin/bwrap".to_string(), &config(&[], false), Some("/run/user/1000"));
+        assert_eq!(argv[0], "/usr/bin/bwrap");
+        for flag in ["--unshare-all", "--new-session", "--die-with-parent"] {
+            assert!(argv.iter().any(|a| a == flag), "missing {}", flag);
+        }
+        assert!(!argv.iter().any(|a| a == "--share-net"));
+        position(&argv, &["--unsetenv", "DBUS_SESSION_BUS_ADDRESS"]);
+        position(&argv, &["--ro-bind", "/", "/"]);
+        position(&argv, &["--tmpfs", "/tmp"]);
+        position(&argv, &["--tmpfs", "/run/user/1000"]);
+        assert_eq!(argv.last().map(String::as_str), Some("--"));
+    }
+
+    #[test]
+    fn bwrap_shares_the_network_only_when_allowed() {
+        let argv = bwrap_argv("bwrap".to_string(), &config(&[], true), None);
+        assert!(argv.iter().any(|a| a == "--share-net"));
+        assert!(!argv.iter().any(|a| a == "--unshare-net"));
+        assert!(!argv.windows(2).any(|w| w[0] == "--tmpfs" && w[1] != "/tmp"));
+    }
+
+    #[test]
+    fn bwrap_binds_write_paths_after_the_tmpfs_mounts() {
+        let argv = bwrap_argv("bwrap".to_string(), &config(&["/tmp/out", "/home/me/logs"], false), Some("/run/user/1000"));
+        let runtime_tmpfs = position(&argv, &["--tmpfs", "/run/user/1000"]);
+        let tmp_bind = position(&argv, &["--bind", "/tmp/out", "/tmp/out"]);
+        assert!(position(&argv, &["--tmpfs", "/tmp"]) < tmp_bind);
+        assert!(runtime_tmpfs < tmp_bind);
+        assert!(runtime_tmpfs < position(&argv, &["--bind", "/home/me/logs", "/home/me/logs"]));
+    }
+
+    #[test]
+    fn firejail_blocks_dbus_and_the_network() {
+        let argv = firejail_argv("firejail".to_string(), &config(&["/srv/data"], false), Some("/run/user/1000"));
+        assert_eq!(
+            argv,
+            [
+                "firejail",
+                "--quiet",
+                "--noprofile",
+                "--read-only=/",
+                "--dbus-user=none",
+                "--dbus-system=none",
+                "--private-tmp",
+                "--nonewprivs",
+                "--seccomp",
+                "--blacklist=/run/user/1000",
+                "--read-write=/srv/data",
+                "--net=none",
+                "--",
+            ]
+        );
+        let argv = firejail_argv("firejail".to_string(), &config(&[], true), None);
+        assert!(!argv.iter().any(|a| a.starts_with("--net") || a.starts_with("--blacklist")));
+    }
+}
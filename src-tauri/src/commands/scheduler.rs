@@ -1,6 +1,7 @@
+use crate::commands::{audit, lint, protection};
 use crate::db::queries;
 use crate::db::Database;
-use crate::models::{NewSchedule, Schedule};
+use crate::models::{NewSchedule, PreviewFile, ResourceLimits, Schedule, SchedulePreview, ScheduleUsage, Script};
 use std::path::PathBuf;
 use tauri::State;
 use uuid::Uuid;
@@ -10,7 +11,7 @@ fn generate_task_label(script_id: i64) -> String {
     format!("com.conduit.script.{}.{}", script_id, short_uuid)
 }
 
-fn get_logs_dir() -> PathBuf {
+pub(crate) fn get_logs_dir() -> PathBuf {
     let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
     path.push(".conduit");
     path.push("logs");
@@ -41,7 +42,7 @@ mod platform {
         path
     }
 
-    fn build_plist(label: &str, script_path: &str, schedule: &NewSchedule) -> Value {
+    fn build_plist(label: &str, script_path: &str, launcher: Option<&[String]>, schedule: &NewSchedule) -> Value {
         let logs_dir = get_logs_dir();
         let stdout_log = logs_dir.join(format!("{}.stdout.log", label));
         let stderr_log = logs_dir.join(format!("{}.stderr.log", label));
@@ -50,10 +51,13 @@ mod platform {
         dict.insert("Label".to_string(), Value::String(label.to_string()));
 
         // ProgramArguments
-        let args = Value::Array(vec![
-            Value::String("/bin/bash".to_string()),
-            Value::String(script_path.to_string()),
-        ]);
+        let args = match launcher {
+            Some(launcher) => Value::Array(launcher.iter().map(|a| Value::String(a.clone())).collect()),
+            None => Value::Array(vec![
+                Value::String("/bin/bash".to_string()),
+                Value::String(script_path.to_string()),
+            ]),
+        };
         dict.insert("ProgramArguments".to_string(), args);
 
         // Environment variables for PATH
@@ -126,8 +130,8 @@ mod platform {
         Value::Dictionary(dict.into_iter().collect())
     }
 
-    pub fn create_scheduled_task(label: &str, script_path: &str, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
-        let plist_value = build_plist(label, script_path, schedule);
+    pub fn create_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, _limits: Option<&ResourceLimits>, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
+        let plist_value = build_plist(label, script_path, launcher, schedule);
         let plist_path = get_plist_path(label);
 
         // Ensure LaunchAgents dir exists
@@ -148,6 +152,27 @@ mod platform {
         Ok(())
     }
 
+    pub fn preview_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, _limits: Option<&ResourceLimits>, schedule: &NewSchedule, _powershell_exe: &str) -> Result<SchedulePreview, String> {
+        let plist_path = get_plist_path(label);
+        let mut content = Vec::new();
+        build_plist(label, script_path, launcher, schedule)
+            .to_writer_xml(&mut content)
+            .map_err(|e| e.to_string())?;
+
+        Ok(SchedulePreview {
+            label: label.to_string(),
+            files: vec![PreviewFile {
+                path: plist_path.to_string_lossy().to_string(),
+                content: String::from_utf8_lossy(&content).to_string(),
+            }],
+            commands: vec![vec![
+                "launchctl".to_string(),
+                "load".to_string(),
+                plist_path.to_string_lossy().to_string(),
+            ]],
+        })
+    }
+
     pub fn delete_scheduled_task(label: &str) -> Result<(), String> {
         let plist_path = get_plist_path(label);
         if plist_path.exists() {
@@ -160,8 +185,8 @@ mod platform {
         Ok(())
     }
 
-    pub fn enable_scheduled_task(label: &str, script_path: &str, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
-        create_scheduled_task(label, script_path, schedule, _powershell_exe)
+    pub fn enable_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, limits: Option<&ResourceLimits>, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
+        create_scheduled_task(label, script_path, launcher, limits, schedule, _powershell_exe)
     }
 
     pub fn disable_scheduled_task(label: &str) -> Result<(), String> {
@@ -178,6 +203,10 @@ mod platform {
     pub fn task_exists(label: &str) -> bool {
         get_plist_path(label).exists()
     }
+
+    pub fn last_run_usage(_schedule_id: i64, _label: &str) -> Result<Option<ScheduleUsage>, String> {
+        Ok(None)
+    }
 }
 
 // ──────────────────────────────────────────────
@@ -214,21 +243,49 @@ mod platform {
         get_systemd_user_dir().join(format!("{}.timer", unit))
     }
 
-    fn build_service_content(label: &str, script_path: &str) -> String {
+    /// Quote arguments for an `ExecStart=` line where they need it.
+    fn exec_line(args: &[String]) -> String {
+        args.iter()
+            .map(|a| {
+                if a.contains(char::is_whitespace) || a.contains('"') {
+                    format!("\"{}\"", a.replace('\\', "\\\\").replace('"', "\\\""))
+                } else {
+                    a.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn build_service_content(label: &str, script_path: &str, launcher: Option<&[String]>, limits: Option<&ResourceLimits>) -> String {
         let logs_dir = get_logs_dir();
         let unit = label_to_unit_name(label);
+        let exec_start = match launcher {
+            Some(launcher) => exec_line(launcher),
+            None => format!("/bin/bash {}", script_path),
+        };
+        let limits: String = limits
+            .map(crate::commands::limits::unit_directives)
+            .unwrap_or_default()
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect();
         format!(
             "[Unit]\n\
              Description=Conduit scheduled script: {unit}\n\
              \n\
              [Service]\n\
              Type=oneshot\n\
-             ExecStart=/bin/bash {script_path}\n\
+             ExecStart={exec_start}\n\
              Environment=PATH=/usr/local/bin:/usr/bin:/bin:/usr/local/sbin:/usr/sbin:/sbin\n\
              StandardOutput=append:{stdout}\n\
-             StandardError=append:{stderr}\n",
+             StandardError=append:{stderr}\n\
+             CPUAccounting=yes\n\
+             MemoryAccounting=yes\n\
+             {limits}",
             unit = unit,
-            script_path = script_path,
+            exec_start = exec_start,
+            limits = limits,
             stdout = logs_dir.join(format!("{}.stdout.log", unit)).to_string_lossy(),
             stderr = logs_dir.join(format!("{}.stderr.log", unit)).to_string_lossy(),
         )
@@ -297,11 +354,11 @@ mod platform {
         Ok(())
     }
 
-    fn write_unit_files(label: &str, script_path: &str, schedule: &NewSchedule) -> Result<(), String> {
+    fn write_unit_files(label: &str, script_path: &str, launcher: Option<&[String]>, limits: Option<&ResourceLimits>, schedule: &NewSchedule) -> Result<(), String> {
         let systemd_dir = get_systemd_user_dir();
         std::fs::create_dir_all(&systemd_dir).map_err(|e| e.to_string())?;
 
-        let service_content = build_service_content(label, script_path);
+        let service_content = build_service_content(label, script_path, launcher, limits);
         let timer_content = build_timer_content(label, schedule);
 
         std::fs::write(get_service_path(label), service_content).map_err(|e| e.to_string())?;
@@ -310,8 +367,8 @@ mod platform {
         Ok(())
     }
 
-    pub fn create_scheduled_task(label: &str, script_path: &str, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
-        write_unit_files(label, script_path, schedule)?;
+    pub fn create_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, limits: Option<&ResourceLimits>, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
+        write_unit_files(label, script_path, launcher, limits, schedule)?;
 
         let timer_unit = format!("{}.timer", label_to_unit_name(label));
         systemctl(&["daemon-reload"])?;
@@ -320,6 +377,35 @@ mod platform {
         Ok(())
     }
 
+    pub fn preview_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, limits: Option<&ResourceLimits>, schedule: &NewSchedule, _powershell_exe: &str) -> Result<SchedulePreview, String> {
+        let timer_unit = format!("{}.timer", label_to_unit_name(label));
+        let systemctl_user = |args: &[&str]| {
+            ["systemctl", "--user"]
+                .iter()
+                .chain(args)
+                .map(|a| a.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        Ok(SchedulePreview {
+            label: label.to_string(),
+            files: vec![
+                PreviewFile {
+                    path: get_service_path(label).to_string_lossy().to_string(),
+                    content: build_service_content(label, script_path, launcher, limits),
+                },
+                PreviewFile {
+                    path: get_timer_path(label).to_string_lossy().to_string(),
+                    content: build_timer_content(label, schedule),
+                },
+            ],
+            commands: vec![
+                systemctl_user(&["daemon-reload"]),
+                systemctl_user(&["enable", "--now", &timer_unit]),
+            ],
+        })
+    }
+
     pub fn delete_scheduled_task(label: &str) -> Result<(), String> {
         let timer_unit = format!("{}.timer", label_to_unit_name(label));
 
@@ -335,10 +421,10 @@ mod platform {
         Ok(())
     }
 
-    pub fn enable_scheduled_task(label: &str, script_path: &str, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
+    pub fn enable_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, limits: Option<&ResourceLimits>, schedule: &NewSchedule, _powershell_exe: &str) -> Result<(), String> {
         // Re-write files in case they were cleaned up
         if !get_timer_path(label).exists() {
-            write_unit_files(label, script_path, schedule)?;
+            write_unit_files(label, script_path, launcher, limits, schedule)?;
         }
 
         let timer_unit = format!("{}.timer", label_to_unit_name(label));
@@ -357,6 +443,51 @@ mod platform {
     pub fn task_exists(label: &str) -> bool {
         get_timer_path(label).exists()
     }
+
+    /// Read from the service's properties, which systemd keeps after the
+    /// run finishes.
+    pub fn last_run_usage(schedule_id: i64, label: &str) -> Result<Option<ScheduleUsage>, String> {
+        let service_unit = format!("{}.service", label_to_unit_name(label));
+        let output = std::process::Command::new("systemctl")
+            .args(["--user", "show", &service_unit, "--timestamp=unix"])
+            .args(["-p", "ExecMainStartTimestamp", "-p", "ExecMainStartTimestampMonotonic"])
+            .args(["-p", "ExecMainExitTimestampMonotonic", "-p", "ExecMainStatus"])
+            .args(["-p", "CPUUsageNSec", "-p", "MemoryPeak"])
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).to_string());
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let properties: std::collections::HashMap<&str, &str> = stdout.lines().filter_map(|line| line.split_once('=')).collect();
+        // Unset values show as "[not set]", or the maximum integer
+        let number = |name: &str| {
+            properties
+                .get(name)
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v != u64::MAX)
+        };
+        let Some(started) = properties
+            .get("ExecMainStartTimestamp")
+            .and_then(|v| v.strip_prefix('@'))
+            .and_then(|v| v.parse::<i64>().ok())
+        else {
+            return Ok(None);
+        };
+        let wall_ms = match (number("ExecMainStartTimestampMonotonic"), number("ExecMainExitTimestampMonotonic")) {
+            (Some(start), Some(exit)) if exit > start => Some(((exit - start) / 1000) as i64),
+            _ => None,
+        };
+        Ok(Some(ScheduleUsage {
+            schedule_id,
+            started_at: chrono::DateTime::from_timestamp(started, 0)
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+            exit_code: properties.get("ExecMainStatus").and_then(|v| v.parse().ok()),
+            cpu_ms: number("CPUUsageNSec").map(|ns| (ns / 1_000_000) as i64),
+            peak_memory_kb: number("MemoryPeak").map(|bytes| (bytes / 1024) as i64),
+            wall_ms,
+        }))
+    }
 }
 
 // ──────────────────────────────────────────────
@@ -367,7 +498,7 @@ mod platform {
 mod platform {
     use super::*;
 
-    fn build_schtasks_command(label: &str, script_path: &str, schedule: &NewSchedule, powershell_exe: &str) -> Vec<String> {
+    fn build_schtasks_command(label: &str, script_path: &str, launcher: Option<&[String]>, schedule: &NewSchedule, powershell_exe: &str) -> Vec<String> {
         let logs_dir = get_logs_dir();
         let stdout_log = logs_dir.join(format!("{}.stdout.log", label));
 
@@ -377,14 +508,20 @@ mod platform {
             .unwrap_or("")
             .to_lowercase();
 
-        let tr = match ext.as_str() {
-            "ps1" => format!(
+        let tr = match (launcher, ext.as_str()) {
+            (Some([exe, args @ ..]), _) => format!(
+                "\"{}\" {} > \"{}\" 2>&1",
+                exe,
+                args.join(" "),
+                stdout_log.to_string_lossy()
+            ),
+            (_, "ps1") => format!(
                 "{} -ExecutionPolicy Bypass -File \"{}\" > \"{}\" 2>&1",
                 powershell_exe,
                 script_path,
                 stdout_log.to_string_lossy()
             ),
-            "cmd" | "bat" => format!(
+            (_, "cmd" | "bat") => format!(
                 "cmd.exe /C \"{}\" > \"{}\" 2>&1",
                 script_path,
                 stdout_log.to_string_lossy()
@@ -451,8 +588,8 @@ mod platform {
         args
     }
 
-    pub fn create_scheduled_task(label: &str, script_path: &str, schedule: &NewSchedule, powershell_exe: &str) -> Result<(), String> {
-        let args = build_schtasks_command(label, script_path, schedule, powershell_exe);
+    pub fn create_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, _limits: Option<&ResourceLimits>, schedule: &NewSchedule, powershell_exe: &str) -> Result<(), String> {
+        let args = build_schtasks_command(label, script_path, launcher, schedule, powershell_exe);
         let output = std::process::Command::new("schtasks")
             .args(&args)
             .output()
@@ -464,6 +601,17 @@ mod platform {
         Ok(())
     }
 
+    pub fn preview_scheduled_task(label: &str, script_path: &str, launcher: Option<&[String]>, _limits: Option<&ResourceLimits>, schedule: &NewSchedule, powershell_exe: &str) -> Result<SchedulePreview, String> {
+        // schtasks keeps the task definition itself; there is no file to show
+        let mut command = vec!["schtasks".to_string()];
+        command.extend(build_schtasks_command(label, script_path, launcher, schedule, powershell_exe));
+        Ok(SchedulePreview {
+            label: label.to_string(),
+            files: Vec::new(),
+            commands: vec![command],
+        })
+    }
+
     pub fn delete_scheduled_task(label: &str) -> Result<(), String> {
         let output = std::process::Command::new("schtasks")
             .args(["/Delete", "/TN", label, "/F"])
@@ -479,9 +627,9 @@ mod platform {
         Ok(())
     }
 
-    pub fn enable_scheduled_task(_label: &str, script_path: &str, schedule: &NewSchedule, powershell_exe: &str) -> Result<(), String> {
+    pub fn enable_scheduled_task(_label: &str, script_path: &str, launcher: Option<&[String]>, limits: Option<&ResourceLimits>, schedule: &NewSchedule, powershell_exe: &str) -> Result<(), String> {
         // On Windows, re-creating the task effectively enables it
-        create_scheduled_task(_label, script_path, schedule, powershell_exe)?;
+        create_scheduled_task(_label, script_path, launcher, limits, schedule, powershell_exe)?;
         let output = std::process::Command::new("schtasks")
             .args(["/Change", "/TN", _label, "/ENABLE"])
             .output()
@@ -512,6 +660,10 @@ mod platform {
             .map(|o| o.status.success())
             .unwrap_or(false)
     }
+
+    pub fn last_run_usage(_schedule_id: i64, _label: &str) -> Result<Option<ScheduleUsage>, String> {
+        Ok(None)
+    }
 }
 
 // ──────────────────────────────────────────────
@@ -526,11 +678,13 @@ pub fn create_schedule(
     time: Option<String>,
     weekday: Option<i32>,
     interval_seconds: Option<i64>,
+    confirmation: Option<String>,
 ) -> Result<Schedule, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-    // Get script path
+    ensure_schedule_editable(&conn, script_id)?;
+    // Scheduled runs can't be confirmed when they fire, so confirm up front
     let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    protection::confirm(&conn, &script, confirmation.as_deref())?;
 
     let new_schedule = NewSchedule {
         script_id,
@@ -540,54 +694,172 @@ pub fn create_schedule(
         interval_seconds,
     };
 
-    let task_label = generate_task_label(script_id);
+    let created = install_schedule(&conn, &new_schedule, true)?;
+    audit_schedule(&conn, "schedule.create", &created, None, Some(&created));
+    Ok(created)
+}
+
+/// Scripts with environment variables run through `conduit --run-script`, so
+/// their secrets are decrypted when the task fires instead of being written
+/// into the task definition. On Unix admin scripts do too, to elevate with
+/// passwordless sudo, and so do pinned scripts, to verify the approved hash,
+/// protected scripts, to check their allowed window and cooldown, and
+/// sandboxed scripts, to start in the sandbox, and scripts whose lint
+/// policy is "block", to be linted first. Outside Linux, where there's no
+/// systemd unit to carry them, so do scripts with resource limits.
+fn task_launcher(conn: &rusqlite::Connection, script: &Script) -> Result<Option<Vec<String>>, String> {
+    let elevate = cfg!(unix) && script.run_as_admin;
+    let checked = script.pinned
+        || script.protected
+        || script.sandboxed
+        || lint::effective_policy(conn, script)? == "block";
+    let limited = cfg!(all(unix, not(target_os = "linux")))
+        && queries::get_script_limits(conn, script.id).map_err(|e| e.to_string())?.is_some();
+    if !elevate && !checked && !limited && !queries::script_has_env(conn, script.id).map_err(|e| e.to_string())? {
+        return Ok(None);
+    }
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the Conduit executable: {}", e))?;
+    Ok(Some(vec![
+        exe.to_string_lossy().to_string(),
+        "--run-script".to_string(),
+        script.id.to_string(),
+    ]))
+}
+
+/// Create the OS-level task for a schedule and record it. A disabled schedule
+/// is installed and then immediately disabled so it can be toggled later.
+pub(crate) fn install_schedule(
+    conn: &rusqlite::Connection,
+    new_schedule: &NewSchedule,
+    enabled: bool,
+) -> Result<Schedule, String> {
+    // Get script path
+    let script = queries::get_script_by_id(conn, new_schedule.script_id).map_err(|e| e.to_string())?;
+
+    let task_label = generate_task_label(new_schedule.script_id);
 
     // Create the OS-level scheduled task
-    let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+    let settings = queries::get_settings(conn).map_err(|e| e.to_string())?;
     let ps_exe = crate::commands::powershell_exe(&settings.powershell_version);
-    platform::create_scheduled_task(&task_label, &script.path, &new_schedule, ps_exe)?;
+    let launcher = task_launcher(conn, &script)?;
+    let limits = queries::get_script_limits(conn, script.id).map_err(|e| e.to_string())?;
+    platform::create_scheduled_task(&task_label, &script.path, launcher.as_deref(), limits.as_ref(), new_schedule, ps_exe)?;
 
     // Insert into DB
-    let schedule = queries::insert_schedule(&conn, &new_schedule, &task_label).map_err(|e| e.to_string())?;
+    let mut schedule = queries::insert_schedule(conn, new_schedule, &task_label).map_err(|e| e.to_string())?;
+
+    if !enabled {
+        platform::disable_scheduled_task(&task_label)?;
+        queries::update_schedule_enabled(conn, schedule.id, false).map_err(|e| e.to_string())?;
+        schedule.enabled = false;
+    }
 
     Ok(schedule)
 }
 
-#[tauri::command]
-pub fn update_schedule(db: State<'_, Database>, schedule_id: i64, enabled: bool) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let schedule = queries::get_schedule_by_id(&conn, schedule_id).map_err(|e| e.to_string())?;
-
+/// Enable or disable the OS-level task for a schedule and record the change.
+pub(crate) fn set_schedule_enabled(
+    conn: &rusqlite::Connection,
+    schedule: &Schedule,
+    enabled: bool,
+) -> Result<(), String> {
     if enabled {
-        let script = queries::get_script_by_id(&conn, schedule.script_id).map_err(|e| e.to_string())?;
+        let script = queries::get_script_by_id(conn, schedule.script_id).map_err(|e| e.to_string())?;
         let new_schedule = NewSchedule {
             script_id: schedule.script_id,
-            schedule_type: schedule.schedule_type,
-            time: schedule.time,
+            schedule_type: schedule.schedule_type.clone(),
+            time: schedule.time.clone(),
             weekday: schedule.weekday,
             interval_seconds: schedule.interval_seconds,
         };
-        let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+        let settings = queries::get_settings(conn).map_err(|e| e.to_string())?;
         let ps_exe = crate::commands::powershell_exe(&settings.powershell_version);
-        platform::enable_scheduled_task(&schedule.plist_label, &script.path, &new_schedule, ps_exe)?;
+        let launcher = task_launcher(conn, &script)?;
+        let limits = queries::get_script_limits(conn, script.id).map_err(|e| e.to_string())?;
+        platform::enable_scheduled_task(&schedule.plist_label, &script.path, launcher.as_deref(), limits.as_ref(), &new_schedule, ps_exe)?;
     } else {
         platform::disable_scheduled_task(&schedule.plist_label)?;
     }
 
-    queries::update_schedule_enabled(&conn, schedule_id, enabled).map_err(|e| e.to_string())?;
-    Ok(())
+    queries::update_schedule_enabled(conn, schedule.id, enabled).map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-pub fn delete_schedule(db: State<'_, Database>, schedule_id: i64) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let schedule = queries::get_schedule_by_id(&conn, schedule_id).map_err(|e| e.to_string())?;
+/// Rewrite the OS-level tasks of a script's schedules, keeping their labels
+/// and enabled state.
+pub(crate) fn reinstall_script_schedules(conn: &rusqlite::Connection, script_id: i64) -> Result<(), String> {
+    let script = queries::get_script_by_id(conn, script_id).map_err(|e| e.to_string())?;
+    let settings = queries::get_settings(conn).map_err(|e| e.to_string())?;
+    let ps_exe = crate::commands::powershell_exe(&settings.powershell_version);
+    let launcher = task_launcher(conn, &script)?;
+    let limits = queries::get_script_limits(conn, script.id).map_err(|e| e.to_string())?;
+    for schedule in queries::get_schedules_for_script(conn, script_id).map_err(|e| e.to_string())? {
+        let new_schedule = NewSchedule {
+            script_id,
+            schedule_type: schedule.schedule_type,
+            time: schedule.time,
+            weekday: schedule.weekday,
+            interval_seconds: schedule.interval_seconds,
+        };
+        platform::create_scheduled_task(&schedule.plist_label, &script.path, launcher.as_deref(), limits.as_ref(), &new_schedule, ps_exe)?;
+        if !schedule.enabled {
+            platform::disable_scheduled_task(&schedule.plist_label)?;
+        }
+    }
+    Ok(())
+}
 
+/// Remove the OS-level task for a schedule and its database row.
+pub(crate) fn remove_schedule(conn: &rusqlite::Connection, schedule: &Schedule) -> Result<(), String> {
     // Remove OS-level scheduled task
     platform::delete_scheduled_task(&schedule.plist_label)?;
 
     // Remove from DB
-    queries::delete_schedule(&conn, schedule_id).map_err(|e| e.to_string())?;
+    queries::delete_schedule(conn, schedule.id).map_err(|e| e.to_string())
+}
+
+/// Schedule entries are named after their script.
+pub(crate) fn audit_schedule(
+    conn: &rusqlite::Connection,
+    action: &str,
+    schedule: &Schedule,
+    before: Option<&Schedule>,
+    after: Option<&Schedule>,
+) {
+    let script_name = queries::get_script_by_id(conn, schedule.script_id).map(|s| s.name).ok();
+    audit::log(conn, action, "schedule", Some(schedule.id), script_name.as_deref(), before, after);
+}
+
+pub(crate) fn audit_schedule_toggle(conn: &rusqlite::Connection, schedule: &Schedule, enabled: bool) {
+    let after = Schedule {
+        enabled,
+        ..schedule.clone()
+    };
+    audit_schedule(conn, "schedule.toggle", schedule, Some(schedule), Some(&after));
+}
+
+/// Schedules of config-managed scripts come from the config file.
+fn ensure_schedule_editable(conn: &rusqlite::Connection, script_id: i64) -> Result<(), String> {
+    let script = queries::get_script_by_id(conn, script_id).map_err(|e| e.to_string())?;
+    crate::commands::config::ensure_editable(&script)
+}
+
+#[tauri::command]
+pub fn update_schedule(db: State<'_, Database>, schedule_id: i64, enabled: bool) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let schedule = queries::get_schedule_by_id(&conn, schedule_id).map_err(|e| e.to_string())?;
+    ensure_schedule_editable(&conn, schedule.script_id)?;
+    set_schedule_enabled(&conn, &schedule, enabled)?;
+    audit_schedule_toggle(&conn, &schedule, enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_schedule(db: State<'_, Database>, schedule_id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let schedule = queries::get_schedule_by_id(&conn, schedule_id).map_err(|e| e.to_string())?;
+    ensure_schedule_editable(&conn, schedule.script_id)?;
+    remove_schedule(&conn, &schedule)?;
+    audit_schedule(&conn, "schedule.delete", &schedule, Some(&schedule), None);
     Ok(())
 }
 
@@ -595,26 +867,58 @@ pub fn delete_schedule(db: State<'_, Database>, schedule_id: i64) -> Result<(),
 pub fn toggle_schedule(db: State<'_, Database>, schedule_id: i64) -> Result<bool, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let schedule = queries::get_schedule_by_id(&conn, schedule_id).map_err(|e| e.to_string())?;
+    ensure_schedule_editable(&conn, schedule.script_id)?;
     let new_enabled = !schedule.enabled;
+    set_schedule_enabled(&conn, &schedule, new_enabled)?;
+    audit_schedule_toggle(&conn, &schedule, new_enabled);
+    Ok(new_enabled)
+}
 
-    if new_enabled {
-        let script = queries::get_script_by_id(&conn, schedule.script_id).map_err(|e| e.to_string())?;
-        let new_schedule = NewSchedule {
-            script_id: schedule.script_id,
-            schedule_type: schedule.schedule_type,
-            time: schedule.time,
-            weekday: schedule.weekday,
-            interval_seconds: schedule.interval_seconds,
-        };
-        let settings = queries::get_settings(&conn).map_err(|e| e.to_string())?;
-        let ps_exe = crate::commands::powershell_exe(&settings.powershell_version);
-        platform::enable_scheduled_task(&schedule.plist_label, &script.path, &new_schedule, ps_exe)?;
-    } else {
-        platform::disable_scheduled_task(&schedule.plist_label)?;
-    }
+fn preview(conn: &rusqlite::Connection, label: &str, new_schedule: &NewSchedule) -> Result<SchedulePreview, String> {
+    let script = queries::get_script_by_id(conn, new_schedule.script_id).map_err(|e| e.to_string())?;
+    let settings = queries::get_settings(conn).map_err(|e| e.to_string())?;
+    let ps_exe = crate::commands::powershell_exe(&settings.powershell_version);
+    let launcher = task_launcher(conn, &script)?;
+    let limits = queries::get_script_limits(conn, script.id).map_err(|e| e.to_string())?;
+    platform::preview_scheduled_task(label, &script.path, launcher.as_deref(), limits.as_ref(), new_schedule, ps_exe)
+}
 
-    queries::update_schedule_enabled(&conn, schedule_id, new_enabled).map_err(|e| e.to_string())?;
-    Ok(new_enabled)
+/// Show what installing an existing schedule writes and runs, without
+/// touching the OS scheduler.
+#[tauri::command]
+pub fn preview_schedule(db: State<'_, Database>, schedule_id: i64) -> Result<SchedulePreview, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let schedule = queries::get_schedule_by_id(&conn, schedule_id).map_err(|e| e.to_string())?;
+    let new_schedule = NewSchedule {
+        script_id: schedule.script_id,
+        schedule_type: schedule.schedule_type,
+        time: schedule.time,
+        weekday: schedule.weekday,
+        interval_seconds: schedule.interval_seconds,
+    };
+    preview(&conn, &schedule.plist_label, &new_schedule)
+}
+
+/// Same as `preview_schedule` for a schedule that hasn't been created yet.
+/// The label is a sample; a real one is generated on creation.
+#[tauri::command]
+pub fn preview_new_schedule(
+    db: State<'_, Database>,
+    script_id: i64,
+    schedule_type: String,
+    time: Option<String>,
+    weekday: Option<i32>,
+    interval_seconds: Option<i64>,
+) -> Result<SchedulePreview, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let new_schedule = NewSchedule {
+        script_id,
+        schedule_type,
+        time,
+        weekday,
+        interval_seconds,
+    };
+    preview(&conn, &generate_task_label(script_id), &new_schedule)
 }
 
 #[tauri::command]
@@ -623,6 +927,18 @@ pub fn get_schedules(db: State<'_, Database>, script_id: i64) -> Result<Vec<Sche
     queries::get_schedules_for_script(&conn, script_id).map_err(|e| e.to_string())
 }
 
+/// Resource usage of a schedule's most recent run, as accounted by systemd.
+/// None before it first runs, and on macOS and Windows. Runs that go
+/// through `conduit --run-script` also keep their usage on their run record.
+#[tauri::command]
+pub fn get_schedule_usage(db: State<'_, Database>, schedule_id: i64) -> Result<Option<ScheduleUsage>, String> {
+    let schedule = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        queries::get_schedule_by_id(&conn, schedule_id).map_err(|e| e.to_string())?
+    };
+    platform::last_run_usage(schedule.id, &schedule.plist_label)
+}
+
 #[tauri::command]
 pub fn sync_schedules(db: State<'_, Database>) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -642,7 +958,16 @@ pub fn sync_schedules(db: State<'_, Database>) -> Result<(), String> {
                         weekday: schedule.weekday,
                         interval_seconds: schedule.interval_seconds,
                     };
-                    let _ = platform::create_scheduled_task(&schedule.plist_label, &script.path, &new_schedule, ps_exe);
+                    let launcher = task_launcher(&conn, &script)?;
+                    let limits = queries::get_script_limits(&conn, script.id).map_err(|e| e.to_string())?;
+                    let _ = platform::create_scheduled_task(
+                        &schedule.plist_label,
+                        &script.path,
+                        launcher.as_deref(),
+                        limits.as_ref(),
+                        &new_schedule,
+                        ps_exe,
+                    );
                 }
             }
         } else {
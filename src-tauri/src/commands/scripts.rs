@@ -1,12 +1,16 @@
+use crate::commands::{audit, config, header, scheduler};
 use crate::db::queries;
 use crate::db::Database;
-use crate::models::{NewScript, Script, UpdateScript};
+use crate::models::{NewScript, Script, ScriptParam, UpdateScript};
 use tauri::State;
 
 #[tauri::command]
 pub fn add_script(db: State<'_, Database>, script: NewScript) -> Result<Script, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::insert_script(&conn, &script).map_err(|e| e.to_string())
+    let created = queries::insert_script(&conn, &script).map_err(|e| e.to_string())?;
+    let created = header::sync_header(&conn, &created, true)?.unwrap_or(created);
+    audit::log(&conn, "script.add", "script", Some(created.id), Some(&created.name), None, Some(&created));
+    Ok(created)
 }
 
 #[tauri::command]
@@ -21,11 +25,34 @@ pub fn get_scripts(db: State<'_, Database>, category_id: Option<i64>) -> Result<
 #[tauri::command]
 pub fn update_script(db: State<'_, Database>, id: i64, update: UpdateScript) -> Result<Script, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::update_script(&conn, id, &update).map_err(|e| e.to_string())
+    let existing = queries::get_script_by_id(&conn, id).map_err(|e| e.to_string())?;
+    config::ensure_editable(&existing)?;
+    let updated = queries::update_script(&conn, id, &update).map_err(|e| e.to_string())?;
+    // Scheduled admin runs go through the elevating launcher on Unix
+    if cfg!(unix) && update.run_as_admin.is_some_and(|admin| admin != existing.run_as_admin) {
+        scheduler::reinstall_script_schedules(&conn, id)?;
+    }
+    let updated = if update.path.as_ref().is_some_and(|p| *p != existing.path) {
+        header::sync_header(&conn, &updated, true)?.unwrap_or(updated)
+    } else {
+        updated
+    };
+    audit::log(&conn, "script.update", "script", Some(id), Some(&updated.name), Some(&existing), Some(&updated));
+    Ok(updated)
 }
 
 #[tauri::command]
 pub fn delete_script(db: State<'_, Database>, id: i64) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::delete_script(&conn, id).map_err(|e| e.to_string())
+    let existing = queries::get_script_by_id(&conn, id).map_err(|e| e.to_string())?;
+    config::ensure_editable(&existing)?;
+    queries::delete_script(&conn, id).map_err(|e| e.to_string())?;
+    audit::log(&conn, "script.delete", "script", Some(id), Some(&existing.name), Some(&existing), None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_script_params(db: State<'_, Database>, script_id: i64) -> Result<Vec<ScriptParam>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_params(&conn, script_id).map_err(|e| e.to_string())
 }
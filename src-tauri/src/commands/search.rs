@@ -0,0 +1,54 @@
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{RunSearchFilter, RunSearchHit, ScriptSearchHit};
+use tauri::State;
+
+/// Snippet highlight markers. Control characters can't collide with script
+/// output, so the frontend can split on them instead of parsing HTML.
+const HIGHLIGHT_START: &str = "\u{2}";
+const HIGHLIGHT_END: &str = "\u{3}";
+
+/// Turn free text into an FTS5 query: every word must appear, the last one
+/// as a prefix. Words are quoted so punctuation isn't read as FTS syntax.
+fn to_fts_query(input: &str) -> Option<String> {
+    let words: Vec<String> = input
+        .split_whitespace()
+        .map(|w| format!("\"{}\"", w.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", words.join(" ")))
+}
+
+#[tauri::command]
+pub fn search_runs(
+    db: State<'_, Database>,
+    query: String,
+    filter: Option<RunSearchFilter>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<RunSearchHit>, String> {
+    let Some(fts_query) = to_fts_query(&query) else {
+        return Ok(Vec::new());
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::search_run_output(
+        &conn,
+        &fts_query,
+        &filter.unwrap_or_default(),
+        (HIGHLIGHT_START, HIGHLIGHT_END),
+        limit.unwrap_or(50),
+        offset.unwrap_or(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn search_scripts(db: State<'_, Database>, query: String, limit: Option<i64>) -> Result<Vec<ScriptSearchHit>, String> {
+    let Some(fts_query) = to_fts_query(&query) else {
+        return Ok(Vec::new());
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::search_scripts(&conn, &fts_query, limit.unwrap_or(20)).map_err(|e| e.to_string())
+}
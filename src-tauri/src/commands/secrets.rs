@@ -0,0 +1,403 @@
+use crate::commands::{audit, scheduler};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewScriptEnvVar, ScriptEnvVar, SecretInfo, VaultStatus};
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rusqlite::Connection;
+use std::sync::Mutex;
+use tauri::State;
+
+/// Keyring slot holding the vault key when the user chose to remember it.
+const VAULT_KEY_ENTRY: &str = "vault-key";
+
+/// Encrypted under the vault key (with this as associated data) to tell a
+/// right passphrase from a wrong one.
+const VERIFIER: &str = "conduit-vault";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Shorter values would be redacted all over unrelated output.
+pub(crate) const MIN_SECRET_LEN: usize = 4;
+
+type Key = [u8; 32];
+
+/// The vault key while the vault is unlocked.
+pub struct VaultState {
+    key: Mutex<Option<Key>>,
+}
+
+impl VaultState {
+    pub fn new() -> Self {
+        VaultState { key: Mutex::new(None) }
+    }
+}
+
+/// The values a script's run needs: every variable, plus the secret values
+/// among them for redaction.
+#[derive(Default)]
+pub(crate) struct ScriptEnv {
+    pub vars: Vec<(String, String)>,
+    pub secrets: Vec<String>,
+}
+
+/// Argon2id with the crate's default cost parameters.
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive the vault key: {}", e))?;
+    Ok(key)
+}
+
+/// XChaCha20-Poly1305 with a random nonce stored in front of the ciphertext.
+/// The secret's name is bound in as associated data, so ciphertexts can't be
+/// swapped between names.
+fn encrypt(key: &Key, name: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: name.as_bytes() })
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(blob)
+}
+
+fn decrypt(key: &Key, name: &str, blob: &[u8]) -> Option<Vec<u8>> {
+    if blob.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: name.as_bytes() })
+        .ok()
+}
+
+fn new_verifier(key: &Key) -> Result<Vec<u8>, String> {
+    encrypt(key, VERIFIER, VERIFIER.as_bytes())
+}
+
+fn verify(key: &Key, verifier: &[u8]) -> bool {
+    decrypt(key, VERIFIER, verifier).is_some()
+}
+
+/// Secret and environment variable names: `[A-Za-z_][A-Za-z0-9_]*`.
+fn validate_name(kind: &str, name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid {} name \"{}\": use letters, digits and underscores, not starting with a digit",
+            kind, name
+        ))
+    }
+}
+
+fn remembered_key() -> Option<Key> {
+    let encoded = crate::commands::keyring_entry(VAULT_KEY_ENTRY).ok()?.get_password().ok()?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+fn remember_key(key: &Key) -> Result<(), String> {
+    crate::commands::keyring_entry(VAULT_KEY_ENTRY)?
+        .set_password(&base64::engine::general_purpose::STANDARD.encode(key))
+        .map_err(|e| format!("Failed to store the vault key: {}", e))
+}
+
+fn forget_key() -> Result<(), String> {
+    match crate::commands::keyring_entry(VAULT_KEY_ENTRY)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to remove the vault key from the keyring: {}", e)),
+    }
+}
+
+/// The vault key if the vault is unlocked, falling back to one remembered in
+/// the OS keyring.
+pub(crate) fn current_key(vault: &VaultState, conn: &Connection) -> Result<Option<Key>, String> {
+    let mut current = vault.key.lock().map_err(|e| e.to_string())?;
+    if current.is_some() {
+        return Ok(*current);
+    }
+    let Some(info) = queries::get_vault(conn).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    if let Some(key) = remembered_key().filter(|key| verify(key, &info.verifier)) {
+        *current = Some(key);
+    }
+    Ok(*current)
+}
+
+fn require_key(vault: &VaultState, conn: &Connection) -> Result<Key, String> {
+    if queries::get_vault(conn).map_err(|e| e.to_string())?.is_none() {
+        return Err("No secrets vault has been set up yet".to_string());
+    }
+    current_key(vault, conn)?.ok_or_else(|| "The secrets vault is locked; unlock it with the master passphrase".to_string())
+}
+
+fn status(vault: &VaultState, conn: &Connection) -> Result<VaultStatus, String> {
+    let initialized = queries::get_vault(conn).map_err(|e| e.to_string())?.is_some();
+    Ok(VaultStatus {
+        initialized,
+        unlocked: initialized && current_key(vault, conn)?.is_some(),
+        remembered: initialized && remembered_key().is_some(),
+        secret_count: queries::count_secrets(conn).map_err(|e| e.to_string())?,
+    })
+}
+
+/// Put the key in memory and, if asked, in the OS keyring. A keyring that
+/// isn't available doesn't stop the unlock; the status shows it wasn't kept.
+fn unlock_with(vault: &VaultState, key: Key, remember: bool) -> Result<(), String> {
+    *vault.key.lock().map_err(|e| e.to_string())? = Some(key);
+    if remember {
+        let _ = remember_key(&key);
+    }
+    Ok(())
+}
+
+/// A script's environment with secrets decrypted, for a run.
+pub(crate) fn resolve_script_env(conn: &Connection, vault: &VaultState, script_id: i64) -> Result<ScriptEnv, String> {
+    let mut env = ScriptEnv::default();
+    let mut key = None;
+    for var in queries::get_script_env(conn, script_id).map_err(|e| e.to_string())? {
+        let Some(secret) = var.secret else {
+            env.vars.push((var.name, var.value.unwrap_or_default()));
+            continue;
+        };
+        let key = match key {
+            Some(key) => key,
+            None => *key.insert(require_key(vault, conn)?),
+        };
+        let blob = queries::get_secret_ciphertext(conn, &secret)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Secret \"{}\" (used for {}) doesn't exist", secret, var.name))?;
+        let value = decrypt(&key, &secret, &blob)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| format!("Secret \"{}\" could not be decrypted", secret))?;
+        env.secrets.push(value.clone());
+        env.vars.push((var.name, value));
+    }
+    Ok(env)
+}
+
+/// A script's environment with secret values masked, for display. Returns
+/// `(name, value, is_secret)`.
+pub(crate) fn masked_script_env(conn: &Connection, script_id: i64) -> Result<Vec<(String, String, bool)>, String> {
+    Ok(queries::get_script_env(conn, script_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|var| match var.secret {
+            Some(secret) => (var.name, format!("[secret:{}]", secret), true),
+            None => (var.name, var.value.unwrap_or_default(), false),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn get_vault_status(db: State<'_, Database>, vault: State<'_, VaultState>) -> Result<VaultStatus, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    status(&vault, &conn)
+}
+
+/// Create the vault with a master passphrase. With `remember`, the derived
+/// key is also stored in the OS keyring so scheduled runs can use secrets.
+#[tauri::command]
+pub fn setup_vault(
+    db: State<'_, Database>,
+    vault: State<'_, VaultState>,
+    passphrase: String,
+    remember: Option<bool>,
+) -> Result<VaultStatus, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("The passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if queries::get_vault(&conn).map_err(|e| e.to_string())?.is_some() {
+        return Err("The secrets vault is already set up".to_string());
+    }
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(&passphrase, &salt)?;
+    queries::insert_vault(&conn, &salt, &new_verifier(&key)?).map_err(|e| e.to_string())?;
+    unlock_with(&vault, key, remember.unwrap_or(false))?;
+    status(&vault, &conn)
+}
+
+#[tauri::command]
+pub fn unlock_vault(
+    db: State<'_, Database>,
+    vault: State<'_, VaultState>,
+    passphrase: String,
+    remember: Option<bool>,
+) -> Result<VaultStatus, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let info = queries::get_vault(&conn)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "No secrets vault has been set up yet".to_string())?;
+    let key = derive_key(&passphrase, &info.salt)?;
+    if !verify(&key, &info.verifier) {
+        return Err("Incorrect passphrase".to_string());
+    }
+    unlock_with(&vault, key, remember.unwrap_or(false))?;
+    status(&vault, &conn)
+}
+
+/// Lock the vault and drop any key remembered in the OS keyring. Scheduled
+/// runs that need secrets fail until it is unlocked with `remember` again.
+#[tauri::command]
+pub fn lock_vault(db: State<'_, Database>, vault: State<'_, VaultState>) -> Result<VaultStatus, String> {
+    *vault.key.lock().map_err(|e| e.to_string())? = None;
+    forget_key()?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    status(&vault, &conn)
+}
+
+/// Re-encrypt every secret under a key derived from a new passphrase.
+#[tauri::command]
+pub fn change_vault_passphrase(
+    db: State<'_, Database>,
+    vault: State<'_, VaultState>,
+    current: String,
+    new_passphrase: String,
+) -> Result<VaultStatus, String> {
+    if new_passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("The passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let info = queries::get_vault(&conn)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "No secrets vault has been set up yet".to_string())?;
+    let old_key = derive_key(&current, &info.salt)?;
+    if !verify(&old_key, &info.verifier) {
+        return Err("Incorrect passphrase".to_string());
+    }
+
+    let mut new_salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut new_salt);
+    let new_key = derive_key(&new_passphrase, &new_salt)?;
+    let mut secrets = Vec::new();
+    for (name, blob) in queries::get_secret_ciphertexts(&conn).map_err(|e| e.to_string())? {
+        let value = decrypt(&old_key, &name, &blob).ok_or_else(|| format!("Secret \"{}\" could not be decrypted", name))?;
+        secrets.push((name.clone(), encrypt(&new_key, &name, &value)?));
+    }
+    queries::rekey_vault(&conn, &new_salt, &new_verifier(&new_key)?, &secrets).map_err(|e| e.to_string())?;
+    let remembered = remembered_key().is_some();
+    unlock_with(&vault, new_key, remembered)?;
+    status(&vault, &conn)
+}
+
+/// Delete the vault and all secrets, for when the passphrase is lost.
+#[tauri::command]
+pub fn reset_vault(db: State<'_, Database>, vault: State<'_, VaultState>) -> Result<VaultStatus, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::reset_vault(&conn).map_err(|e| e.to_string())?;
+    audit::log::<()>(&conn, "vault.reset", "vault", None, None, None, None);
+    *vault.key.lock().map_err(|e| e.to_string())? = None;
+    forget_key()?;
+    status(&vault, &conn)
+}
+
+#[tauri::command]
+pub fn get_secrets(db: State<'_, Database>) -> Result<Vec<SecretInfo>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_secrets(&conn).map_err(|e| e.to_string())
+}
+
+/// Add a secret or replace its value. Needs the vault unlocked.
+#[tauri::command]
+pub fn set_secret(
+    db: State<'_, Database>,
+    vault: State<'_, VaultState>,
+    name: String,
+    value: String,
+) -> Result<(), String> {
+    validate_name("secret", &name)?;
+    if value.trim().len() < MIN_SECRET_LEN {
+        return Err(format!(
+            "Secret values must be at least {} characters so they can be redacted from output",
+            MIN_SECRET_LEN
+        ));
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let key = require_key(&vault, &conn)?;
+    queries::upsert_secret(&conn, &name, &encrypt(&key, &name, value.as_bytes())?).map_err(|e| e.to_string())?;
+    // Only the name is recorded, never the value
+    audit::log::<()>(&conn, "secret.set", "secret", None, Some(&name), None, None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_secret(db: State<'_, Database>, name: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let used_by = queries::get_secrets(&conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|s| s.name == name)
+        .map_or(0, |s| s.used_by);
+    if used_by > 0 {
+        return Err(format!(
+            "Secret \"{}\" is used by {} script(s); remove it from their environment first",
+            name, used_by
+        ));
+    }
+    queries::delete_secret(&conn, &name).map_err(|e| e.to_string())?;
+    audit::log::<()>(&conn, "secret.delete", "secret", None, Some(&name), None, None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_script_env(db: State<'_, Database>, script_id: i64) -> Result<Vec<ScriptEnvVar>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_env(&conn, script_id).map_err(|e| e.to_string())
+}
+
+/// Replace a script's environment variables. Each sets either a literal
+/// `value` or the name of a `secret`. Scheduled tasks are reinstalled when
+/// the script gains or loses its environment, since that changes whether
+/// they launch through Conduit.
+#[tauri::command]
+pub fn set_script_env(
+    db: State<'_, Database>,
+    script_id: i64,
+    vars: Vec<NewScriptEnvVar>,
+) -> Result<Vec<ScriptEnvVar>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let secrets = queries::get_secrets(&conn).map_err(|e| e.to_string())?;
+    let mut seen = std::collections::HashSet::new();
+    for var in &vars {
+        validate_name("variable", &var.name)?;
+        if !seen.insert(var.name.as_str()) {
+            return Err(format!("Variable {} is set more than once", var.name));
+        }
+        match (&var.value, &var.secret) {
+            (Some(_), Some(_)) => return Err(format!("Variable {} can't have both a value and a secret", var.name)),
+            (_, Some(secret)) if !secrets.iter().any(|s| &s.name == secret) => {
+                return Err(format!("Secret \"{}\" doesn't exist", secret));
+            }
+            _ => {}
+        }
+    }
+    let before = queries::get_script_env(&conn, script_id).map_err(|e| e.to_string())?;
+    let has_env = !vars.is_empty();
+    queries::replace_script_env(&conn, script_id, &vars).map_err(|e| e.to_string())?;
+    if before.is_empty() == has_env {
+        scheduler::reinstall_script_schedules(&conn, script_id)?;
+    }
+    let after = queries::get_script_env(&conn, script_id).map_err(|e| e.to_string())?;
+    // Secret-backed variables hold the secret's name, not its value
+    let script_name = queries::get_script_by_id(&conn, script_id).map(|s| s.name).ok();
+    audit::log(&conn, "script.env_update", "script", Some(script_id), script_name.as_deref(), Some(&before), Some(&after));
+    Ok(after)
+}
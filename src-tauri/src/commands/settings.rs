@@ -1,3 +1,4 @@
+use crate::commands::audit;
 use crate::db::queries;
 use crate::db::Database;
 use crate::models::{Settings, UpdateSettings};
@@ -9,10 +10,44 @@ pub fn get_settings(db: State<'_, Database>) -> Result<Settings, String> {
     queries::get_settings(&conn).map_err(|e| e.to_string())
 }
 
+/// Validate an update, normalizing the digest time to `HH:MM`.
+pub(crate) fn validate_settings(update: &mut UpdateSettings) -> Result<(), String> {
+    if let Some(port) = update.smtp_port {
+        if !(1..=65535).contains(&port) {
+            return Err(format!("SMTP port must be between 1 and 65535, got {}", port));
+        }
+    }
+    if let Some(ref time) = update.digest_time {
+        let parsed = chrono::NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|_| format!("Digest time must be HH:MM, got '{}'", time))?;
+        update.digest_time = Some(parsed.format("%H:%M").to_string());
+    }
+    if let Some(ref security) = update.smtp_security {
+        if !["none", "starttls", "tls"].contains(&security.as_str()) {
+            return Err(format!("Unknown SMTP security mode: {}", security));
+        }
+    }
+    if let Some(ref policy) = update.lint_policy {
+        crate::commands::lint::validate_policy(policy)?;
+    }
+    if let Some(ref method) = update.elevation_method {
+        crate::commands::elevation::validate_method(method)?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
-pub fn update_settings(db: State<'_, Database>, update: UpdateSettings) -> Result<Settings, String> {
+pub fn update_settings(db: State<'_, Database>, mut update: UpdateSettings) -> Result<Settings, String> {
+    validate_settings(&mut update)?;
+    if let Some(ref password) = update.smtp_password {
+        crate::commands::notifications::set_smtp_password(password)?;
+    }
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    queries::upsert_settings(&conn, &update).map_err(|e| e.to_string())
+    let before = queries::get_settings(&conn).map_err(|e| e.to_string())?;
+    let after = queries::upsert_settings(&conn, &update).map_err(|e| e.to_string())?;
+    crate::commands::lint::reinstall_for_global_policy(&conn, &before.lint_policy, &after.lint_policy)?;
+    audit::log(&conn, "settings.update", "settings", None, None, Some(&before), Some(&after));
+    Ok(after)
 }
 
 #[tauri::command]
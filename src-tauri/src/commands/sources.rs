@@ -0,0 +1,380 @@
+use crate::commands::{audit, header};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{NewScript, NewScriptSource, ScanReport, Script, ScriptSource, UpdateScriptSource};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use walkdir::WalkDir;
+
+/// Used when a source doesn't list its own include globs.
+const DEFAULT_INCLUDE_GLOBS: [&str; 10] = [
+    "*.sh", "*.bash", "*.zsh", "*.ps1", "*.py", "*.rb", "*.pl", "*.js", "*.bat", "*.cmd",
+];
+
+/// Upper bound on files registered from a single scan, so pointing a source
+/// at a home directory can't flood the library.
+const MAX_FILES_PER_SCAN: usize = 5000;
+
+/// Batch bursts of filesystem events (checkouts, builds) into one rescan.
+const RESCAN_DEBOUNCE: Duration = Duration::from_secs(1);
+
+const DEFAULT_COLOR: &str = "#00d4aa";
+
+/// Holds the recursive watcher over all enabled sources.
+pub struct SourceState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl SourceState {
+    pub fn new() -> Self {
+        SourceState {
+            watcher: Mutex::new(None),
+        }
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| format!("Invalid glob \"{}\": {}", pattern, e))?);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+fn include_globs(source: &ScriptSource) -> Vec<String> {
+    if source.include_globs.is_empty() {
+        DEFAULT_INCLUDE_GLOBS.iter().map(|g| g.to_string()).collect()
+    } else {
+        source.include_globs.clone()
+    }
+}
+
+/// `backup_db-nightly.sh` -> `Backup db nightly`
+fn name_from_file(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let words = stem.replace(['_', '-'], " ");
+    let words = words.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => stem,
+    }
+}
+
+fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// Files under the source folder matching its globs. Hidden directories
+/// (`.git` and friends) are never descended into.
+fn discover_files(source: &ScriptSource) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let root = Path::new(&source.path);
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", source.path));
+    }
+    let include = build_globset(&include_globs(source))?;
+    let exclude = build_globset(&source.exclude_globs)?;
+
+    let mut files = Vec::new();
+    let entries = WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| !is_hidden(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file());
+    for entry in entries {
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        if include.is_match(relative) && !exclude.is_match(relative) {
+            files.push((entry.path().to_path_buf(), relative.to_path_buf()));
+            if files.len() >= MAX_FILES_PER_SCAN {
+                break;
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Add new files, apply header changes to known ones and flag scripts whose
+/// file has gone. Scripts added by hand, by another source or by the config
+/// file are never touched.
+pub(crate) fn scan_source(conn: &Connection, source: &ScriptSource) -> Result<ScanReport, String> {
+    let mut report = ScanReport {
+        source_id: source.id,
+        ..ScanReport::default()
+    };
+    let files = discover_files(source)?;
+    let truncated = files.len() >= MAX_FILES_PER_SCAN;
+    if truncated {
+        report.errors.push(format!(
+            "Stopped after {} files; narrow the include globs",
+            MAX_FILES_PER_SCAN
+        ));
+    }
+
+    let scripts = queries::get_all_scripts(conn).map_err(|e| e.to_string())?;
+    let by_path: HashMap<&str, &Script> = scripts.iter().map(|s| (s.path.as_str(), s)).collect();
+    let mut categories: HashMap<String, i64> = queries::get_all_categories(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| (c.name, c.id))
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+
+    for (path, relative) in files {
+        let path_str = path.to_string_lossy().to_string();
+
+        match by_path.get(path_str.as_str()) {
+            Some(existing) if existing.source_id == Some(source.id) => {
+                seen.insert(existing.id);
+                let mut changed = false;
+                if existing.missing {
+                    queries::set_script_missing(conn, existing.id, false).map_err(|e| e.to_string())?;
+                    changed = true;
+                }
+                // Header changes are applied; edits made in the app are kept
+                // until the header is edited again
+                if header::sync_header(conn, existing, false)?.is_some() {
+                    changed = true;
+                }
+                if changed {
+                    report.updated += 1;
+                }
+            }
+            Some(_) => {}
+            None => {
+                // Files in subfolders take the top-level folder as category
+                let folder = relative
+                    .parent()
+                    .and_then(|p| p.components().next())
+                    .map(|c| c.as_os_str().to_string_lossy().to_string());
+                let category_id = match folder {
+                    Some(name) => match categories.get(&name) {
+                        Some(id) => *id,
+                        None => {
+                            let created =
+                                queries::insert_category(conn, &name, DEFAULT_COLOR).map_err(|e| e.to_string())?;
+                            audit::log(conn, "category.add", "category", Some(created.id), Some(&created.name), None, Some(&created));
+                            categories.insert(name, created.id);
+                            created.id
+                        }
+                    },
+                    None => source.category_id.unwrap_or(1),
+                };
+                let script = queries::insert_script(
+                    conn,
+                    &NewScript {
+                        name: name_from_file(&path),
+                        path: path_str,
+                        description: None,
+                        category_id,
+                        color: DEFAULT_COLOR.to_string(),
+                        run_as_admin: false,
+                    },
+                )
+                .map_err(|e| e.to_string())?;
+                queries::set_script_source(conn, script.id, source.id).map_err(|e| e.to_string())?;
+                // Schedules declared in discovered files are left for the
+                // user to apply; they show up as header drift
+                let script = header::sync_header(conn, &script, false)?.unwrap_or(script);
+                audit::log(conn, "script.add", "script", Some(script.id), Some(&script.name), None, Some(&script));
+                report.added += 1;
+            }
+        }
+    }
+
+    // A scan that stopped early didn't reach every file, so only scripts
+    // whose file is actually gone are flagged then
+    for script in scripts
+        .iter()
+        .filter(|s| s.source_id == Some(source.id) && !s.missing && !seen.contains(&s.id))
+        .filter(|s| !truncated || !Path::new(&s.path).exists())
+    {
+        queries::set_script_missing(conn, script.id, true).map_err(|e| e.to_string())?;
+        report.missing += 1;
+    }
+
+    queries::set_source_scanned(conn, source.id).map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+fn scan_sources(conn: &Connection, filter: impl Fn(&ScriptSource) -> bool) -> Result<Vec<ScanReport>, String> {
+    let sources = queries::get_script_sources(conn).map_err(|e| e.to_string())?;
+    Ok(sources
+        .iter()
+        .filter(|s| s.enabled && filter(s))
+        .map(|source| {
+            scan_source(conn, source).unwrap_or_else(|e| ScanReport {
+                source_id: source.id,
+                errors: vec![e],
+                ..ScanReport::default()
+            })
+        })
+        .collect())
+}
+
+/// Rescan the sources containing any of `paths` and let the frontend know
+/// when something changed.
+fn rescan_for_paths(app: &AppHandle, paths: &[PathBuf]) {
+    let db = app.state::<Database>();
+    let Ok(conn) = db.conn.lock() else {
+        return;
+    };
+    let Ok(reports) = scan_sources(&conn, |source| paths.iter().any(|p| p.starts_with(&source.path))) else {
+        return;
+    };
+    drop(conn);
+    if reports
+        .iter()
+        .any(|r| r.added > 0 || r.updated > 0 || r.missing > 0)
+    {
+        let _ = app.emit("scripts-discovered", reports);
+    }
+}
+
+/// Rebuild the watcher over every enabled source.
+fn rewatch(app: &AppHandle) -> Result<(), String> {
+    let sources = {
+        let db = app.state::<Database>();
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        queries::get_script_sources(&conn).map_err(|e| e.to_string())?
+    };
+
+    let state = app.state::<SourceState>();
+    let mut slot = state.watcher.lock().map_err(|e| e.to_string())?;
+    // Dropping the old watcher closes its channel and ends its thread
+    *slot = None;
+    if !sources.iter().any(|s| s.enabled) {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<Vec<PathBuf>>();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if !event.kind.is_access() {
+                let _ = tx.send(event.paths);
+            }
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    for source in sources.iter().filter(|s| s.enabled) {
+        // A source folder that was removed is reported by its next scan
+        let _ = watcher.watch(Path::new(&source.path), RecursiveMode::Recursive);
+    }
+    *slot = Some(watcher);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while let Ok(mut paths) = rx.recv() {
+            std::thread::sleep(RESCAN_DEBOUNCE);
+            while let Ok(more) = rx.try_recv() {
+                paths.extend(more);
+            }
+            rescan_for_paths(&app, &paths);
+        }
+    });
+    Ok(())
+}
+
+/// Scan every enabled source at startup, then watch them for changes.
+pub(crate) fn start_source_watcher(app: AppHandle) {
+    std::thread::spawn(move || {
+        {
+            let db = app.state::<Database>();
+            let Ok(conn) = db.conn.lock() else {
+                return;
+            };
+            if let Ok(reports) = scan_sources(&conn, |_| true) {
+                drop(conn);
+                let _ = app.emit("scripts-discovered", reports);
+            }
+        }
+        let _ = rewatch(&app);
+    });
+}
+
+#[tauri::command]
+pub fn get_script_sources(db: State<'_, Database>) -> Result<Vec<ScriptSource>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_sources(&conn).map_err(|e| e.to_string())
+}
+
+/// Register a folder, scan it right away and start watching it.
+#[tauri::command]
+pub fn add_script_source(
+    app: AppHandle,
+    db: State<'_, Database>,
+    source: NewScriptSource,
+) -> Result<(ScriptSource, ScanReport), String> {
+    if !Path::new(&source.path).is_dir() {
+        return Err(format!("{} is not a directory", source.path));
+    }
+    build_globset(&source.include_globs)?;
+    build_globset(&source.exclude_globs)?;
+
+    let result = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let created = queries::insert_script_source(&conn, &source).map_err(|e| e.to_string())?;
+        let report = scan_source(&conn, &created)?;
+        let created = queries::get_script_source_by_id(&conn, created.id).map_err(|e| e.to_string())?;
+        audit::log(&conn, "source.add", "source", Some(created.id), Some(&created.path), None, Some(&created));
+        (created, report)
+    };
+    rewatch(&app)?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn update_script_source(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    update: UpdateScriptSource,
+) -> Result<ScriptSource, String> {
+    if let Some(ref globs) = update.include_globs {
+        build_globset(globs)?;
+    }
+    if let Some(ref globs) = update.exclude_globs {
+        build_globset(globs)?;
+    }
+    let source = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let before = queries::get_script_source_by_id(&conn, id).map_err(|e| e.to_string())?;
+        let source = queries::update_script_source(&conn, id, &update).map_err(|e| e.to_string())?;
+        audit::log(&conn, "source.update", "source", Some(id), Some(&source.path), Some(&before), Some(&source));
+        if source.enabled {
+            scan_source(&conn, &source)?;
+        }
+        queries::get_script_source_by_id(&conn, id).map_err(|e| e.to_string())?
+    };
+    rewatch(&app)?;
+    Ok(source)
+}
+
+/// Stop watching a folder. Its scripts stay in the library as ordinary ones.
+#[tauri::command]
+pub fn delete_script_source(app: AppHandle, db: State<'_, Database>, id: i64) -> Result<(), String> {
+    {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let before = queries::get_script_source_by_id(&conn, id).map_err(|e| e.to_string())?;
+        queries::delete_script_source(&conn, id).map_err(|e| e.to_string())?;
+        audit::log(&conn, "source.delete", "source", Some(id), Some(&before.path), Some(&before), None);
+    }
+    rewatch(&app)
+}
+
+#[tauri::command]
+pub fn scan_script_sources(db: State<'_, Database>) -> Result<Vec<ScanReport>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    scan_sources(&conn, |_| true)
+}
@@ -0,0 +1,107 @@
+use crate::commands::{audit, config, health};
+use crate::db::queries;
+use crate::db::Database;
+use crate::models::{Script, ScriptDiff, ScriptHealth, ScriptVersion};
+use rusqlite::Connection;
+use similar::{ChangeTag, TextDiff};
+use std::path::Path;
+use tauri::State;
+
+/// Larger files (bundled binaries, data dumps) aren't snapshotted.
+const MAX_SNAPSHOT_BYTES: u64 = 1024 * 1024;
+
+/// Store the script's current content if this exact content hasn't been seen
+/// before. Returns the version id, or None when the file can't be read or is
+/// too large to keep.
+pub(crate) fn snapshot_script(conn: &Connection, script: &Script) -> Result<Option<i64>, String> {
+    let path = Path::new(&script.path);
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_file() && meta.len() <= MAX_SNAPSHOT_BYTES => {}
+        _ => return Ok(None),
+    }
+    let Ok(content) = std::fs::read(path) else {
+        return Ok(None);
+    };
+    let hash = health::hash_bytes(&content);
+    queries::get_or_insert_script_version(conn, script.id, &hash, &content)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_script_versions(db: State<'_, Database>, script_id: i64) -> Result<Vec<ScriptVersion>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    queries::get_script_versions(&conn, script_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_script_version_content(db: State<'_, Database>, version_id: i64) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (_, content) = queries::get_script_version_content(&conn, version_id).map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&content).to_string())
+}
+
+/// Unified diff from one version to another. Without `to_version_id` the
+/// comparison is against the file as it is on disk now.
+#[tauri::command]
+pub fn diff_script_versions(
+    db: State<'_, Database>,
+    from_version_id: i64,
+    to_version_id: Option<i64>,
+) -> Result<ScriptDiff, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (script_id, old) = queries::get_script_version_content(&conn, from_version_id).map_err(|e| e.to_string())?;
+    let (new, new_label) = match to_version_id {
+        Some(id) => {
+            let (other_script, content) = queries::get_script_version_content(&conn, id).map_err(|e| e.to_string())?;
+            if other_script != script_id {
+                return Err("Both versions must belong to the same script".to_string());
+            }
+            (content, format!("version {}", id))
+        }
+        None => {
+            let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+            let content = std::fs::read(&script.path).map_err(|e| format!("Failed to read {}: {}", script.path, e))?;
+            (content, "current".to_string())
+        }
+    };
+
+    let old = String::from_utf8_lossy(&old);
+    let new = String::from_utf8_lossy(&new);
+    let diff = TextDiff::from_lines(old.as_ref(), new.as_ref());
+    let (mut additions, mut deletions) = (0, 0);
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => additions += 1,
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+    let unified = diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("version {}", from_version_id), &new_label)
+        .to_string();
+    Ok(ScriptDiff {
+        unified,
+        additions,
+        deletions,
+    })
+}
+
+/// Write a stored version back over the script's file. The file is
+/// re-checked afterwards; a pinned script still needs the restored content
+/// approved unless it is the approved version.
+#[tauri::command]
+pub fn restore_script_version(db: State<'_, Database>, version_id: i64) -> Result<ScriptHealth, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (script_id, content) = queries::get_script_version_content(&conn, version_id).map_err(|e| e.to_string())?;
+    let script = queries::get_script_by_id(&conn, script_id).map_err(|e| e.to_string())?;
+    config::ensure_editable(&script)?;
+    let before = queries::get_script_health(&conn, script_id).map_err(|e| e.to_string())?;
+    crate::commands::write_atomic(Path::new(&script.path), &content)
+        .map_err(|e| format!("Failed to write {}: {}", script.path, e))?;
+    let after = health::check_script(&conn, &script)?;
+    audit::log(&conn, "script.version_restore", "script", Some(script_id), Some(&script.name), before.as_ref(), Some(&after));
+    Ok(after)
+}
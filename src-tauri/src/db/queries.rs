@@ -4,7 +4,7 @@ use crate::models::*;
 
 // --- Script queries ---
 
-fn check_is_executable(path: &str) -> bool {
+pub(crate) fn check_is_executable(path: &str) -> bool {
     #[cfg(unix)]
     {
         std::fs::metadata(path)
@@ -28,6 +28,31 @@ fn check_is_executable(path: &str) -> bool {
     }
 }
 
+const SCRIPT_COLUMNS: &str = "id, name, path, description, category_id, color, is_executable, run_as_admin, created_at, updated_at, config_managed, source_id, missing, timeout_seconds, pinned, lint_policy, EXISTS(SELECT 1 FROM script_protection p WHERE p.script_id = scripts.id), EXISTS(SELECT 1 FROM script_sandbox b WHERE b.script_id = scripts.id)";
+
+fn row_to_script(row: &rusqlite::Row) -> Result<Script, rusqlite::Error> {
+    Ok(Script {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        path: row.get(2)?,
+        description: row.get(3)?,
+        category_id: row.get(4)?,
+        color: row.get(5)?,
+        is_executable: row.get(6)?,
+        run_as_admin: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        config_managed: row.get(10)?,
+        source_id: row.get(11)?,
+        missing: row.get(12)?,
+        timeout_seconds: row.get(13)?,
+        pinned: row.get(14)?,
+        lint_policy: row.get(15)?,
+        protected: row.get(16)?,
+        sandboxed: row.get(17)?,
+    })
+}
+
 pub fn insert_script(conn: &Connection, new: &NewScript) -> Result<Script, rusqlite::Error> {
     // Check if path is executable
     let is_exec = check_is_executable(&new.path);
@@ -38,64 +63,22 @@ pub fn insert_script(conn: &Connection, new: &NewScript) -> Result<Script, rusql
     )?;
     let id = conn.last_insert_rowid();
 
-    let mut stmt = conn.prepare(
-        "SELECT id, name, path, description, category_id, color, is_executable, run_as_admin, created_at, updated_at FROM scripts WHERE id = ?1",
-    )?;
-    stmt.query_row(params![id], |row| {
-        Ok(Script {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            path: row.get(2)?,
-            description: row.get(3)?,
-            category_id: row.get(4)?,
-            color: row.get(5)?,
-            is_executable: row.get(6)?,
-            run_as_admin: row.get(7)?,
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    })
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM scripts WHERE id = ?1", SCRIPT_COLUMNS))?;
+    stmt.query_row(params![id], row_to_script)
 }
 
 pub fn get_all_scripts(conn: &Connection) -> Result<Vec<Script>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
-        "SELECT id, name, path, description, category_id, color, is_executable, run_as_admin, created_at, updated_at FROM scripts ORDER BY name",
-    )?;
-    let rows = stmt.query_map([], |row| {
-        Ok(Script {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            path: row.get(2)?,
-            description: row.get(3)?,
-            category_id: row.get(4)?,
-            color: row.get(5)?,
-            is_executable: row.get(6)?,
-            run_as_admin: row.get(7)?,
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM scripts ORDER BY name", SCRIPT_COLUMNS))?;
+    let rows = stmt.query_map([], row_to_script)?;
     rows.collect()
 }
 
 pub fn get_scripts_by_category(conn: &Connection, category_id: i64) -> Result<Vec<Script>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
-        "SELECT id, name, path, description, category_id, color, is_executable, run_as_admin, created_at, updated_at FROM scripts WHERE category_id = ?1 ORDER BY name",
-    )?;
-    let rows = stmt.query_map(params![category_id], |row| {
-        Ok(Script {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            path: row.get(2)?,
-            description: row.get(3)?,
-            category_id: row.get(4)?,
-            color: row.get(5)?,
-            is_executable: row.get(6)?,
-            run_as_admin: row.get(7)?,
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM scripts WHERE category_id = ?1 ORDER BY name",
+        SCRIPT_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![category_id], row_to_script)?;
     rows.collect()
 }
 
@@ -119,24 +102,15 @@ pub fn update_script(conn: &Connection, id: i64, update: &UpdateScript) -> Resul
     if let Some(run_as_admin) = update.run_as_admin {
         conn.execute("UPDATE scripts SET run_as_admin = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2", params![run_as_admin, id])?;
     }
+    if let Some(timeout_seconds) = update.timeout_seconds {
+        conn.execute(
+            "UPDATE scripts SET timeout_seconds = NULLIF(?1, 0), updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![timeout_seconds, id],
+        )?;
+    }
 
-    let mut stmt = conn.prepare(
-        "SELECT id, name, path, description, category_id, color, is_executable, run_as_admin, created_at, updated_at FROM scripts WHERE id = ?1",
-    )?;
-    stmt.query_row(params![id], |row| {
-        Ok(Script {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            path: row.get(2)?,
-            description: row.get(3)?,
-            category_id: row.get(4)?,
-            color: row.get(5)?,
-            is_executable: row.get(6)?,
-            run_as_admin: row.get(7)?,
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
-        })
-    })
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM scripts WHERE id = ?1", SCRIPT_COLUMNS))?;
+    stmt.query_row(params![id], row_to_script)
 }
 
 pub fn delete_script(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
@@ -192,6 +166,19 @@ pub fn update_category(conn: &Connection, id: i64, name: &str, color: &str) -> R
     })
 }
 
+pub fn get_category_by_id(conn: &Connection, id: i64) -> Result<Option<Category>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT id, name, color, sort_order FROM categories WHERE id = ?1")?;
+    let mut rows = stmt.query_map(params![id], |row| {
+        Ok(Category {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            sort_order: row.get(3)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
 pub fn delete_category(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
     // Reassign scripts from deleted category to General (id=1)
     conn.execute(
@@ -214,6 +201,14 @@ pub fn reorder_categories(conn: &Connection, ids: &[i64]) -> Result<(), rusqlite
 
 // --- Run history queries ---
 
+/// Run duration in whole seconds, NULL while the run is still going.
+const RUN_DURATION_SQL: &str = "CAST(strftime('%s', finished_at) - strftime('%s', started_at) AS REAL)";
+
+/// Decode a JSON TEXT column, treating malformed values as absent.
+fn parse_json_column(text: Option<String>) -> Option<serde_json::Value> {
+    text.and_then(|t| serde_json::from_str(&t).ok())
+}
+
 pub fn insert_run_record(conn: &Connection, script_id: i64, started_at: &str) -> Result<RunRecord, rusqlite::Error> {
     conn.execute(
         "INSERT INTO run_history (script_id, started_at, status) VALUES (?1, ?2, 'running')",
@@ -228,6 +223,16 @@ pub fn insert_run_record(conn: &Connection, script_id: i64, started_at: &str) ->
         exit_code: None,
         output: None,
         status: "running".to_string(),
+        metadata: None,
+        version_id: None,
+        redactions: 0,
+        elevation: None,
+        elevation_error: None,
+        peak_rss_kb: None,
+        user_cpu_ms: None,
+        system_cpu_ms: None,
+        wall_ms: None,
+        sandbox: None,
     })
 }
 
@@ -246,39 +251,82 @@ pub fn update_run_record(
     Ok(())
 }
 
-pub fn get_run_history(conn: &Connection, script_id: i64, limit: i64) -> Result<Vec<RunRecord>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
-        "SELECT id, script_id, started_at, finished_at, exit_code, output, status FROM run_history WHERE script_id = ?1 ORDER BY started_at DESC LIMIT ?2",
+pub fn set_run_redactions(conn: &Connection, id: i64, redactions: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("UPDATE run_history SET redactions = ?1 WHERE id = ?2", params![redactions, id])?;
+    Ok(())
+}
+
+/// `error` is set when authentication failed and the script never ran.
+pub fn set_run_elevation(conn: &Connection, id: i64, method: &str, error: Option<&str>) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE run_history SET elevation = ?1, elevation_error = ?2 WHERE id = ?3",
+        params![method, error, id],
     )?;
-    let rows = stmt.query_map(params![script_id, limit], |row| {
-        Ok(RunRecord {
-            id: row.get(0)?,
-            script_id: row.get(1)?,
-            started_at: row.get(2)?,
-            finished_at: row.get(3)?,
-            exit_code: row.get(4)?,
-            output: row.get(5)?,
-            status: row.get(6)?,
-        })
-    })?;
+    Ok(())
+}
+
+pub fn set_run_usage(conn: &Connection, id: i64, usage: &RunUsage) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE run_history SET peak_rss_kb = ?1, user_cpu_ms = ?2, system_cpu_ms = ?3, wall_ms = ?4 WHERE id = ?5",
+        params![usage.peak_rss_kb, usage.user_cpu_ms, usage.system_cpu_ms, usage.wall_ms, id],
+    )?;
+    Ok(())
+}
+
+pub fn set_run_sandbox(conn: &Connection, id: i64, profile: &SandboxProfile) -> Result<(), rusqlite::Error> {
+    let profile = serde_json::to_string(profile).unwrap_or_default();
+    conn.execute("UPDATE run_history SET sandbox = ?1 WHERE id = ?2", params![profile, id])?;
+    Ok(())
+}
+
+pub fn set_run_metadata(conn: &Connection, id: i64, metadata: &serde_json::Value) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE run_history SET metadata = ?1 WHERE id = ?2",
+        params![metadata.to_string(), id],
+    )?;
+    Ok(())
+}
+
+/// Selected from `run_history r`, in the order `row_to_run_record` reads them.
+const RUN_COLUMNS: &str = "r.id, r.script_id, r.started_at, r.finished_at, r.exit_code, r.output, r.status, r.metadata, r.version_id, r.redactions, r.elevation, r.elevation_error, r.peak_rss_kb, r.user_cpu_ms, r.system_cpu_ms, r.wall_ms, r.sandbox";
+
+fn row_to_run_record(row: &rusqlite::Row) -> Result<RunRecord, rusqlite::Error> {
+    Ok(RunRecord {
+        id: row.get(0)?,
+        script_id: row.get(1)?,
+        started_at: row.get(2)?,
+        finished_at: row.get(3)?,
+        exit_code: row.get(4)?,
+        output: row.get(5)?,
+        status: row.get(6)?,
+        metadata: parse_json_column(row.get(7)?),
+        version_id: row.get(8)?,
+        redactions: row.get(9)?,
+        elevation: row.get(10)?,
+        elevation_error: row.get(11)?,
+        peak_rss_kb: row.get(12)?,
+        user_cpu_ms: row.get(13)?,
+        system_cpu_ms: row.get(14)?,
+        wall_ms: row.get(15)?,
+        sandbox: row.get::<_, Option<String>>(16)?.and_then(|t| serde_json::from_str(&t).ok()),
+    })
+}
+
+pub fn get_run_history(conn: &Connection, script_id: i64, limit: i64) -> Result<Vec<RunRecord>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM run_history r WHERE r.script_id = ?1 ORDER BY r.started_at DESC LIMIT ?2",
+        RUN_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![script_id, limit], row_to_run_record)?;
     rows.collect()
 }
 
 pub fn get_latest_run(conn: &Connection, script_id: i64) -> Result<Option<RunRecord>, rusqlite::Error> {
-    let mut stmt = conn.prepare(
-        "SELECT id, script_id, started_at, finished_at, exit_code, output, status FROM run_history WHERE script_id = ?1 ORDER BY started_at DESC LIMIT 1",
-    )?;
-    let mut rows = stmt.query_map(params![script_id], |row| {
-        Ok(RunRecord {
-            id: row.get(0)?,
-            script_id: row.get(1)?,
-            started_at: row.get(2)?,
-            finished_at: row.get(3)?,
-            exit_code: row.get(4)?,
-            output: row.get(5)?,
-            status: row.get(6)?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM run_history r WHERE r.script_id = ?1 ORDER BY r.started_at DESC LIMIT 1",
+        RUN_COLUMNS
+    ))?;
+    let mut rows = stmt.query_map(params![script_id], row_to_run_record)?;
     Ok(rows.next().transpose()?)
 }
 
@@ -389,18 +437,38 @@ pub fn get_schedule_by_id(conn: &Connection, id: i64) -> Result<Schedule, rusqli
 // --- Settings queries ---
 
 pub fn get_settings(conn: &Connection) -> Result<Settings, rusqlite::Error> {
-    let mut stmt =
-        conn.prepare("SELECT id, editor_path, theme, powershell_version FROM settings WHERE id = 1")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, editor_path, theme, powershell_version, smtp_host, smtp_port, smtp_security, smtp_username, smtp_password_set, smtp_from, digest_time, retention_keep_runs, retention_keep_days, retention_failure_days, log_retention_days, config_dir, lint_policy, elevation_method FROM settings WHERE id = 1",
+    )?;
     stmt.query_row([], |row| {
         Ok(Settings {
             id: row.get(0)?,
             editor_path: row.get(1)?,
             theme: row.get(2)?,
             powershell_version: row.get(3)?,
+            smtp_host: row.get(4)?,
+            smtp_port: row.get(5)?,
+            smtp_security: row.get(6)?,
+            smtp_username: row.get(7)?,
+            smtp_password_set: row.get(8)?,
+            smtp_from: row.get(9)?,
+            digest_time: row.get(10)?,
+            retention_keep_runs: row.get(11)?,
+            retention_keep_days: row.get(12)?,
+            retention_failure_days: row.get(13)?,
+            log_retention_days: row.get(14)?,
+            config_dir: row.get(15)?,
+            lint_policy: row.get(16)?,
+            elevation_method: row.get(17)?,
         })
     })
 }
 
+pub fn set_config_dir(conn: &Connection, dir: &str) -> Result<(), rusqlite::Error> {
+    conn.execute("UPDATE settings SET config_dir = ?1 WHERE id = 1", params![dir])?;
+    Ok(())
+}
+
 pub fn upsert_settings(conn: &Connection, update: &UpdateSettings) -> Result<Settings, rusqlite::Error> {
     if let Some(ref editor_path) = update.editor_path {
         conn.execute(
@@ -420,25 +488,1628 @@ pub fn upsert_settings(conn: &Connection, update: &UpdateSettings) -> Result<Set
             params![powershell_version],
         )?;
     }
+    if let Some(ref smtp_host) = update.smtp_host {
+        conn.execute("UPDATE settings SET smtp_host = ?1 WHERE id = 1", params![smtp_host])?;
+    }
+    if let Some(smtp_port) = update.smtp_port {
+        conn.execute("UPDATE settings SET smtp_port = ?1 WHERE id = 1", params![smtp_port])?;
+    }
+    if let Some(ref smtp_security) = update.smtp_security {
+        conn.execute("UPDATE settings SET smtp_security = ?1 WHERE id = 1", params![smtp_security])?;
+    }
+    if let Some(ref smtp_username) = update.smtp_username {
+        conn.execute("UPDATE settings SET smtp_username = ?1 WHERE id = 1", params![smtp_username])?;
+    }
+    if let Some(ref smtp_password) = update.smtp_password {
+        conn.execute(
+            "UPDATE settings SET smtp_password_set = ?1 WHERE id = 1",
+            params![!smtp_password.is_empty()],
+        )?;
+    }
+    if let Some(ref smtp_from) = update.smtp_from {
+        conn.execute("UPDATE settings SET smtp_from = ?1 WHERE id = 1", params![smtp_from])?;
+    }
+    if let Some(ref digest_time) = update.digest_time {
+        conn.execute("UPDATE settings SET digest_time = ?1 WHERE id = 1", params![digest_time])?;
+    }
+    if let Some(keep_runs) = update.retention_keep_runs {
+        conn.execute("UPDATE settings SET retention_keep_runs = ?1 WHERE id = 1", params![keep_runs.max(0)])?;
+    }
+    if let Some(keep_days) = update.retention_keep_days {
+        conn.execute("UPDATE settings SET retention_keep_days = ?1 WHERE id = 1", params![keep_days.max(0)])?;
+    }
+    if let Some(failure_days) = update.retention_failure_days {
+        conn.execute("UPDATE settings SET retention_failure_days = ?1 WHERE id = 1", params![failure_days.max(0)])?;
+    }
+    if let Some(log_days) = update.log_retention_days {
+        conn.execute("UPDATE settings SET log_retention_days = ?1 WHERE id = 1", params![log_days.max(0)])?;
+    }
+    if let Some(ref lint_policy) = update.lint_policy {
+        conn.execute("UPDATE settings SET lint_policy = ?1 WHERE id = 1", params![lint_policy])?;
+    }
+    if let Some(ref elevation_method) = update.elevation_method {
+        conn.execute("UPDATE settings SET elevation_method = ?1 WHERE id = 1", params![elevation_method])?;
+    }
     get_settings(conn)
 }
 
 pub fn get_script_by_id(conn: &Connection, id: i64) -> Result<Script, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM scripts WHERE id = ?1", SCRIPT_COLUMNS))?;
+    stmt.query_row(params![id], row_to_script)
+}
+
+// --- Notifier queries ---
+
+fn row_to_notifier(row: &rusqlite::Row) -> Result<Notifier, rusqlite::Error> {
+    let events: String = row.get(4)?;
+    Ok(Notifier {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        target: row.get(3)?,
+        events: events
+            .split(',')
+            .map(|e| e.trim().to_string())
+            .filter(|e| !e.is_empty())
+            .collect(),
+        script_id: row.get(5)?,
+        template: row.get(6)?,
+        headers: row.get(7)?,
+        max_retries: row.get(8)?,
+        enabled: row.get(9)?,
+        created_at: row.get(10)?,
+    })
+}
+
+const NOTIFIER_COLUMNS: &str =
+    "id, name, kind, target, events, script_id, template, headers, max_retries, enabled, created_at";
+
+pub fn get_all_notifiers(conn: &Connection) -> Result<Vec<Notifier>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM notifiers ORDER BY name", NOTIFIER_COLUMNS))?;
+    let rows = stmt.query_map([], row_to_notifier)?;
+    rows.collect()
+}
+
+pub fn get_notifier_by_id(conn: &Connection, id: i64) -> Result<Notifier, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM notifiers WHERE id = ?1", NOTIFIER_COLUMNS))?;
+    stmt.query_row(params![id], row_to_notifier)
+}
+
+/// Enabled notifiers that apply to a script: global ones plus those bound to it.
+pub fn get_notifiers_for_script(conn: &Connection, script_id: i64) -> Result<Vec<Notifier>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM notifiers WHERE enabled = 1 AND (script_id IS NULL OR script_id = ?1) ORDER BY id",
+        NOTIFIER_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![script_id], row_to_notifier)?;
+    rows.collect()
+}
+
+pub fn insert_notifier(conn: &Connection, new: &NewNotifier) -> Result<Notifier, rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO notifiers (name, kind, target, events, script_id, template, headers, max_retries) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            new.name,
+            new.kind,
+            new.target,
+            new.events.join(","),
+            new.script_id,
+            new.template,
+            new.headers,
+            new.max_retries.unwrap_or(3)
+        ],
+    )?;
+    get_notifier_by_id(conn, conn.last_insert_rowid())
+}
+
+pub fn update_notifier(conn: &Connection, id: i64, update: &UpdateNotifier) -> Result<Notifier, rusqlite::Error> {
+    if let Some(ref name) = update.name {
+        conn.execute("UPDATE notifiers SET name = ?1 WHERE id = ?2", params![name, id])?;
+    }
+    if let Some(ref target) = update.target {
+        conn.execute("UPDATE notifiers SET target = ?1 WHERE id = ?2", params![target, id])?;
+    }
+    if let Some(ref events) = update.events {
+        conn.execute("UPDATE notifiers SET events = ?1 WHERE id = ?2", params![events.join(","), id])?;
+    }
+    if let Some(ref template) = update.template {
+        conn.execute("UPDATE notifiers SET template = NULLIF(?1, '') WHERE id = ?2", params![template, id])?;
+    }
+    if let Some(ref headers) = update.headers {
+        conn.execute("UPDATE notifiers SET headers = NULLIF(?1, '') WHERE id = ?2", params![headers, id])?;
+    }
+    if let Some(max_retries) = update.max_retries {
+        conn.execute("UPDATE notifiers SET max_retries = ?1 WHERE id = ?2", params![max_retries, id])?;
+    }
+    if let Some(enabled) = update.enabled {
+        conn.execute("UPDATE notifiers SET enabled = ?1 WHERE id = ?2", params![enabled, id])?;
+    }
+    get_notifier_by_id(conn, id)
+}
+
+pub fn delete_notifier(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM notifiers WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+pub fn insert_notification_log(
+    conn: &Connection,
+    notifier_id: i64,
+    run_id: Option<i64>,
+    event: &str,
+    attempts: i64,
+    status: &str,
+    response: Option<&str>,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO notification_log (notifier_id, run_id, event, attempts, status, response) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![notifier_id, run_id, event, attempts, status, response],
+    )?;
+    Ok(())
+}
+
+pub fn get_notification_log(
+    conn: &Connection,
+    notifier_id: Option<i64>,
+    limit: i64,
+) -> Result<Vec<NotificationLogEntry>, rusqlite::Error> {
     let mut stmt = conn.prepare(
-        "SELECT id, name, path, description, category_id, color, is_executable, run_as_admin, created_at, updated_at FROM scripts WHERE id = ?1",
+        "SELECT id, notifier_id, run_id, event, attempts, status, response, created_at FROM notification_log WHERE ?1 IS NULL OR notifier_id = ?1 ORDER BY id DESC LIMIT ?2",
     )?;
-    stmt.query_row(params![id], |row| {
-        Ok(Script {
+    let rows = stmt.query_map(params![notifier_id, limit], |row| {
+        Ok(NotificationLogEntry {
             id: row.get(0)?,
-            name: row.get(1)?,
-            path: row.get(2)?,
-            description: row.get(3)?,
-            category_id: row.get(4)?,
-            color: row.get(5)?,
-            is_executable: row.get(6)?,
-            run_as_admin: row.get(7)?,
-            created_at: row.get(8)?,
-            updated_at: row.get(9)?,
+            notifier_id: row.get(1)?,
+            run_id: row.get(2)?,
+            event: row.get(3)?,
+            attempts: row.get(4)?,
+            status: row.get(5)?,
+            response: row.get(6)?,
+            created_at: row.get(7)?,
         })
+    })?;
+    rows.collect()
+}
+
+pub fn get_last_digest_date(conn: &Connection) -> Result<Option<String>, rusqlite::Error> {
+    conn.query_row("SELECT last_digest_date FROM settings WHERE id = 1", [], |row| row.get(0))
+}
+
+pub fn set_last_digest_date(conn: &Connection, date: &str) -> Result<(), rusqlite::Error> {
+    conn.execute("UPDATE settings SET last_digest_date = ?1 WHERE id = 1", params![date])?;
+    Ok(())
+}
+
+/// Runs started at or after `since`, newest first, paired with their script name.
+pub fn get_runs_since(conn: &Connection, since: &str) -> Result<Vec<(RunRecord, String)>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, s.name FROM run_history r JOIN scripts s ON s.id = r.script_id WHERE r.started_at >= ?1 ORDER BY r.started_at DESC",
+        RUN_COLUMNS
+    ))?;
+    let name_index = RUN_COLUMNS.split(',').count();
+    let rows = stmt.query_map(params![since], |row| Ok((row_to_run_record(row)?, row.get(name_index)?)))?;
+    rows.collect()
+}
+
+// --- Output rule queries ---
+
+fn row_to_output_rule(row: &rusqlite::Row) -> Result<OutputRule, rusqlite::Error> {
+    Ok(OutputRule {
+        id: row.get(0)?,
+        script_id: row.get(1)?,
+        name: row.get(2)?,
+        pattern: row.get(3)?,
+        severity: row.get(4)?,
+        notify: row.get(5)?,
+        enabled: row.get(6)?,
+        created_at: row.get(7)?,
     })
 }
+
+pub fn get_output_rules(conn: &Connection, script_id: i64) -> Result<Vec<OutputRule>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, script_id, name, pattern, severity, notify, enabled, created_at FROM output_rules WHERE script_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![script_id], row_to_output_rule)?;
+    rows.collect()
+}
+
+pub fn get_output_rule_by_id(conn: &Connection, id: i64) -> Result<OutputRule, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, script_id, name, pattern, severity, notify, enabled, created_at FROM output_rules WHERE id = ?1",
+    )?;
+    stmt.query_row(params![id], row_to_output_rule)
+}
+
+pub fn insert_output_rule(conn: &Connection, new: &NewOutputRule) -> Result<OutputRule, rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO output_rules (script_id, name, pattern, severity, notify) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![new.script_id, new.name, new.pattern, new.severity, new.notify],
+    )?;
+    get_output_rule_by_id(conn, conn.last_insert_rowid())
+}
+
+pub fn update_output_rule(conn: &Connection, id: i64, update: &UpdateOutputRule) -> Result<OutputRule, rusqlite::Error> {
+    if let Some(ref name) = update.name {
+        conn.execute("UPDATE output_rules SET name = ?1 WHERE id = ?2", params![name, id])?;
+    }
+    if let Some(ref pattern) = update.pattern {
+        conn.execute("UPDATE output_rules SET pattern = ?1 WHERE id = ?2", params![pattern, id])?;
+    }
+    if let Some(ref severity) = update.severity {
+        conn.execute("UPDATE output_rules SET severity = ?1 WHERE id = ?2", params![severity, id])?;
+    }
+    if let Some(notify) = update.notify {
+        conn.execute("UPDATE output_rules SET notify = ?1 WHERE id = ?2", params![notify, id])?;
+    }
+    if let Some(enabled) = update.enabled {
+        conn.execute("UPDATE output_rules SET enabled = ?1 WHERE id = ?2", params![enabled, id])?;
+    }
+    get_output_rule_by_id(conn, id)
+}
+
+pub fn delete_output_rule(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM output_rules WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+pub fn insert_run_matches(conn: &Connection, run_id: i64, matches: &[RunMatch]) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO run_matches (run_id, rule_id, rule_name, severity, line_number, line, captures) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for m in matches {
+        stmt.execute(params![
+            run_id,
+            m.rule_id,
+            m.rule_name,
+            m.severity,
+            m.line_number,
+            m.line,
+            m.captures.as_ref().map(|c| c.to_string())
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn get_run_matches(conn: &Connection, run_id: i64) -> Result<Vec<RunMatch>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, run_id, rule_id, rule_name, severity, line_number, line, captures FROM run_matches WHERE run_id = ?1 ORDER BY line_number",
+    )?;
+    let rows = stmt.query_map(params![run_id], |row| {
+        Ok(RunMatch {
+            id: row.get(0)?,
+            run_id: row.get(1)?,
+            rule_id: row.get(2)?,
+            rule_name: row.get(3)?,
+            severity: row.get(4)?,
+            line_number: row.get(5)?,
+            line: row.get(6)?,
+            captures: parse_json_column(row.get(7)?),
+        })
+    })?;
+    rows.collect()
+}
+
+// --- Run metric queries ---
+
+pub fn insert_run_metric(
+    conn: &Connection,
+    run_id: i64,
+    name: &str,
+    value: f64,
+    recorded_at: &str,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO run_metrics (run_id, name, value, recorded_at) VALUES (?1, ?2, ?3, ?4)",
+        params![run_id, name, value, recorded_at],
+    )?;
+    Ok(())
+}
+
+pub fn get_run_metrics(conn: &Connection, run_id: i64) -> Result<Vec<RunMetric>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, run_id, name, value, recorded_at FROM run_metrics WHERE run_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![run_id], |row| {
+        Ok(RunMetric {
+            id: row.get(0)?,
+            run_id: row.get(1)?,
+            name: row.get(2)?,
+            value: row.get(3)?,
+            recorded_at: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn get_metric_names(conn: &Connection, script_id: i64) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT m.name FROM run_metrics m JOIN run_history r ON r.id = m.run_id WHERE r.script_id = ?1 ORDER BY m.name",
+    )?;
+    let rows = stmt.query_map(params![script_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Latest value of a metric per run, oldest run first.
+pub fn get_metric_series(
+    conn: &Connection,
+    script_id: i64,
+    name: &str,
+    since: Option<&str>,
+    limit: i64,
+) -> Result<Vec<MetricPoint>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT r.id, r.started_at, r.status, m.value FROM run_metrics m JOIN run_history r ON r.id = m.run_id
+         WHERE r.script_id = ?1 AND m.name = ?2 AND (?3 IS NULL OR r.started_at >= ?3)
+           AND m.id = (SELECT MAX(id) FROM run_metrics WHERE run_id = m.run_id AND name = m.name)
+         ORDER BY r.started_at DESC LIMIT ?4",
+    )?;
+    let rows = stmt.query_map(params![script_id, name, since, limit], |row| {
+        Ok(MetricPoint {
+            run_id: row.get(0)?,
+            started_at: row.get(1)?,
+            status: row.get(2)?,
+            value: row.get(3)?,
+        })
+    })?;
+    let mut points: Vec<MetricPoint> = rows.collect::<Result<_, _>>()?;
+    points.reverse();
+    Ok(points)
+}
+
+/// Wall-clock duration in seconds of each finished run, oldest first.
+pub fn get_duration_series(
+    conn: &Connection,
+    script_id: i64,
+    since: Option<&str>,
+    limit: i64,
+) -> Result<Vec<MetricPoint>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, started_at, status, {} FROM run_history
+         WHERE script_id = ?1 AND finished_at IS NOT NULL AND (?2 IS NULL OR started_at >= ?2)
+         ORDER BY started_at DESC LIMIT ?3",
+        RUN_DURATION_SQL
+    ))?;
+    let rows = stmt.query_map(params![script_id, since, limit], |row| {
+        Ok(MetricPoint {
+            run_id: row.get(0)?,
+            started_at: row.get(1)?,
+            status: row.get(2)?,
+            value: row.get(3)?,
+        })
+    })?;
+    let mut points: Vec<MetricPoint> = rows.collect::<Result<_, _>>()?;
+    points.reverse();
+    Ok(points)
+}
+
+// --- Search queries ---
+
+/// Run output containing `fts_query`, newest first. Matched terms in the
+/// snippet are wrapped in `highlight`'s start/end markers.
+pub fn search_run_output(
+    conn: &Connection,
+    fts_query: &str,
+    filter: &RunSearchFilter,
+    highlight: (&str, &str),
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<RunSearchHit>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT r.id, r.script_id, s.name, r.started_at, r.status, r.exit_code,
+                snippet(run_output_fts, 0, ?2, ?3, '…', 16)
+         FROM run_output_fts
+         JOIN run_history r ON r.id = run_output_fts.rowid
+         JOIN scripts s ON s.id = r.script_id
+         WHERE run_output_fts MATCH ?1
+           AND (?4 IS NULL OR r.script_id = ?4)
+           AND (?5 IS NULL OR s.category_id = ?5)
+           AND (?6 IS NULL OR r.status = ?6)
+           AND (?7 IS NULL OR r.started_at >= ?7)
+           AND (?8 IS NULL OR r.started_at < ?8)
+         ORDER BY r.started_at DESC
+         LIMIT ?9 OFFSET ?10",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            fts_query,
+            highlight.0,
+            highlight.1,
+            filter.script_id,
+            filter.category_id,
+            filter.status,
+            filter.since,
+            filter.before,
+            limit,
+            offset
+        ],
+        |row| {
+            Ok(RunSearchHit {
+                run_id: row.get(0)?,
+                script_id: row.get(1)?,
+                script_name: row.get(2)?,
+                started_at: row.get(3)?,
+                status: row.get(4)?,
+                exit_code: row.get(5)?,
+                snippet: row.get(6)?,
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+pub fn search_scripts(conn: &Connection, fts_query: &str, limit: i64) -> Result<Vec<ScriptSearchHit>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.name, s.description FROM scripts_fts
+         JOIN scripts s ON s.id = scripts_fts.rowid
+         WHERE scripts_fts MATCH ?1
+         ORDER BY rank LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![fts_query, limit], |row| {
+        Ok(ScriptSearchHit {
+            script_id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+// --- Retention queries ---
+
+pub fn get_script_retention(conn: &Connection, script_id: i64) -> Result<Option<ScriptRetention>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT script_id, keep_runs, keep_days, keep_failures_days FROM script_retention WHERE script_id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![script_id], |row| {
+        Ok(ScriptRetention {
+            script_id: row.get(0)?,
+            keep_runs: row.get(1)?,
+            keep_days: row.get(2)?,
+            keep_failures_days: row.get(3)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+pub fn upsert_script_retention(conn: &Connection, policy: &ScriptRetention) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO script_retention (script_id, keep_runs, keep_days, keep_failures_days) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(script_id) DO UPDATE SET keep_runs = ?2, keep_days = ?3, keep_failures_days = ?4",
+        params![policy.script_id, policy.keep_runs, policy.keep_days, policy.keep_failures_days],
+    )?;
+    Ok(())
+}
+
+pub fn delete_script_retention(conn: &Connection, script_id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM script_retention WHERE script_id = ?1", params![script_id])?;
+    Ok(())
+}
+
+/// (id, started_at, status) of a script's finished runs, newest first.
+pub fn get_run_summaries(conn: &Connection, script_id: i64) -> Result<Vec<(i64, String, String)>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, started_at, status FROM run_history WHERE script_id = ?1 AND status != 'running' ORDER BY started_at DESC, id DESC",
+    )?;
+    let rows = stmt.query_map(params![script_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    rows.collect()
+}
+
+pub fn delete_run_records(conn: &Connection, ids: &[i64]) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare("DELETE FROM run_history WHERE id = ?1")?;
+        for id in ids {
+            stmt.execute(params![id])?;
+        }
+    }
+    tx.commit()
+}
+
+// --- Dashboard queries ---
+
+/// Runs across all scripts, newest first, without their output.
+pub fn get_recent_runs(
+    conn: &Connection,
+    filter: &RunSearchFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<RunListItem>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT r.id, r.script_id, s.name, s.category_id, r.started_at, r.finished_at, r.exit_code, r.status, {},
+                r.peak_rss_kb, r.user_cpu_ms + r.system_cpu_ms
+         FROM run_history r JOIN scripts s ON s.id = r.script_id
+         WHERE (?1 IS NULL OR r.script_id = ?1)
+           AND (?2 IS NULL OR s.category_id = ?2)
+           AND (?3 IS NULL OR r.status = ?3)
+           AND (?4 IS NULL OR r.started_at >= ?4)
+           AND (?5 IS NULL OR r.started_at < ?5)
+         ORDER BY r.started_at DESC, r.id DESC
+         LIMIT ?6 OFFSET ?7",
+        RUN_DURATION_SQL
+    ))?;
+    let rows = stmt.query_map(
+        params![
+            filter.script_id,
+            filter.category_id,
+            filter.status,
+            filter.since,
+            filter.before,
+            limit,
+            offset
+        ],
+        |row| {
+            Ok(RunListItem {
+                id: row.get(0)?,
+                script_id: row.get(1)?,
+                script_name: row.get(2)?,
+                category_id: row.get(3)?,
+                started_at: row.get(4)?,
+                finished_at: row.get(5)?,
+                exit_code: row.get(6)?,
+                status: row.get(7)?,
+                duration_seconds: row.get(8)?,
+                peak_rss_kb: row.get(9)?,
+                cpu_ms: row.get(10)?,
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+/// Nearest-rank percentile of an ascending slice.
+fn percentile(sorted: &[f64], pct: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+fn success_rate(successes: i64, failures: i64) -> Option<f64> {
+    let decided = successes + failures;
+    (decided > 0).then(|| successes as f64 / decided as f64)
+}
+
+/// Per-script success rate and duration statistics for runs started at or
+/// after `since` (all time when `None`).
+pub fn get_script_stats(conn: &Connection, since: Option<&str>) -> Result<Vec<ScriptStats>, rusqlite::Error> {
+    let mut stats: Vec<ScriptStats> = get_all_scripts(conn)?
+        .into_iter()
+        .map(|script| ScriptStats {
+            script_id: script.id,
+            script_name: script.name,
+            category_id: script.category_id,
+            total_runs: 0,
+            successes: 0,
+            failures: 0,
+            success_rate: None,
+            avg_duration_seconds: None,
+            p95_duration_seconds: None,
+            last_run_at: None,
+            last_status: None,
+            avg_cpu_ms: None,
+            max_peak_rss_kb: None,
+        })
+        .collect();
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT script_id, started_at, status, {}, user_cpu_ms + system_cpu_ms, peak_rss_kb FROM run_history
+         WHERE (?1 IS NULL OR started_at >= ?1)
+         ORDER BY script_id, started_at DESC",
+        RUN_DURATION_SQL
+    ))?;
+    let rows = stmt.query_map(params![since], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<f64>>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+            row.get::<_, Option<i64>>(5)?,
+        ))
+    })?;
+
+    // Entry index by script id, so the output keeps get_all_scripts' order
+    let index: std::collections::HashMap<i64, usize> =
+        stats.iter().enumerate().map(|(i, s)| (s.script_id, i)).collect();
+    let mut durations: std::collections::HashMap<i64, Vec<f64>> = std::collections::HashMap::new();
+    let mut cpu_times: std::collections::HashMap<i64, Vec<i64>> = std::collections::HashMap::new();
+    for row in rows {
+        let (script_id, started_at, status, duration, cpu_ms, peak_rss_kb) = row?;
+        let Some(&i) = index.get(&script_id) else {
+            continue;
+        };
+        let entry = &mut stats[i];
+        entry.total_runs += 1;
+        // Rows arrive newest first per script
+        if entry.last_run_at.is_none() {
+            entry.last_run_at = Some(started_at);
+            entry.last_status = Some(status.clone());
+        }
+        match status.as_str() {
+            "success" | "warning" => entry.successes += 1,
+            "error" => entry.failures += 1,
+            _ => {}
+        }
+        if let Some(d) = duration {
+            durations.entry(script_id).or_default().push(d);
+        }
+        if let Some(ms) = cpu_ms {
+            cpu_times.entry(script_id).or_default().push(ms);
+        }
+        if peak_rss_kb > entry.max_peak_rss_kb {
+            entry.max_peak_rss_kb = peak_rss_kb;
+        }
+    }
+
+    for entry in &mut stats {
+        entry.success_rate = success_rate(entry.successes, entry.failures);
+        if let Some(values) = durations.get_mut(&entry.script_id) {
+            values.sort_by(|a, b| a.total_cmp(b));
+            entry.avg_duration_seconds = Some(values.iter().sum::<f64>() / values.len() as f64);
+            entry.p95_duration_seconds = percentile(values, 95.0);
+        }
+        if let Some(values) = cpu_times.get(&entry.script_id) {
+            entry.avg_cpu_ms = Some(values.iter().sum::<i64>() as f64 / values.len() as f64);
+        }
+    }
+    Ok(stats)
+}
+
+/// Scripts whose most recent finished run failed, with the length of the
+/// current failure streak.
+pub fn get_failing_scripts(conn: &Connection) -> Result<Vec<FailingScript>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.name, s.category_id, r.id, r.started_at, r.exit_code,
+                (SELECT COUNT(*) FROM run_history e
+                 WHERE e.script_id = s.id AND e.status = 'error'
+                   AND e.started_at > COALESCE(
+                       (SELECT MAX(ok.started_at) FROM run_history ok
+                        WHERE ok.script_id = s.id AND ok.status IN ('success', 'warning')), ''))
+         FROM scripts s
+         JOIN run_history r ON r.id = (
+             SELECT id FROM run_history
+             WHERE script_id = s.id AND status NOT IN ('running', 'cancelled')
+             ORDER BY started_at DESC, id DESC LIMIT 1)
+         WHERE r.status = 'error'
+         ORDER BY r.started_at DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(FailingScript {
+            script_id: row.get(0)?,
+            script_name: row.get(1)?,
+            category_id: row.get(2)?,
+            last_run_id: row.get(3)?,
+            failed_at: row.get(4)?,
+            exit_code: row.get(5)?,
+            consecutive_failures: row.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Run totals and success rate per category for runs started at or after `since`.
+pub fn get_category_stats(conn: &Connection, since: Option<&str>) -> Result<Vec<CategoryStats>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT c.id, c.name,
+                (SELECT COUNT(*) FROM scripts WHERE category_id = c.id),
+                COUNT(r.id),
+                COALESCE(SUM(r.status IN ('success', 'warning')), 0),
+                COALESCE(SUM(r.status = 'error'), 0),
+                AVG({})
+         FROM categories c
+         LEFT JOIN scripts s ON s.category_id = c.id
+         LEFT JOIN run_history r ON r.script_id = s.id AND (?1 IS NULL OR r.started_at >= ?1)
+         GROUP BY c.id
+         ORDER BY c.sort_order, c.name",
+        RUN_DURATION_SQL
+    ))?;
+    let rows = stmt.query_map(params![since], |row| {
+        let successes: i64 = row.get(4)?;
+        let failures: i64 = row.get(5)?;
+        Ok(CategoryStats {
+            category_id: row.get(0)?,
+            category_name: row.get(1)?,
+            script_count: row.get(2)?,
+            total_runs: row.get(3)?,
+            successes,
+            failures,
+            success_rate: success_rate(successes, failures),
+            avg_duration_seconds: row.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+// --- Export queries ---
+
+/// Filter shared by the export queries; runs still in progress are never exported.
+const RUN_EXPORT_FILTER_SQL: &str = "r.status != 'running'
+           AND (?1 IS NULL OR r.script_id = ?1)
+           AND (?2 IS NULL OR s.category_id = ?2)
+           AND (?3 IS NULL OR r.status = ?3)
+           AND (?4 IS NULL OR r.started_at >= ?4)
+           AND (?5 IS NULL OR r.started_at < ?5)";
+
+pub fn get_run_export_totals(conn: &Connection, filter: &RunSearchFilter) -> Result<RunExportTotals, rusqlite::Error> {
+    conn.query_row(
+        &format!(
+            "SELECT COUNT(*), COALESCE(SUM(r.status = 'error'), 0), COALESCE(SUM(r.status = 'cancelled'), 0),
+                    COALESCE(SUM({}), 0)
+             FROM run_history r JOIN scripts s ON s.id = r.script_id
+             WHERE {}",
+            RUN_DURATION_SQL, RUN_EXPORT_FILTER_SQL
+        ),
+        params![filter.script_id, filter.category_id, filter.status, filter.since, filter.before],
+        |row| {
+            Ok(RunExportTotals {
+                runs: row.get(0)?,
+                failures: row.get(1)?,
+                skipped: row.get(2)?,
+                duration_seconds: row.get(3)?,
+            })
+        },
+    )
+}
+
+/// Feed matching runs to `f` oldest first, one row at a time, so exports of
+/// large histories never hold more than a single run's output in memory.
+/// A page of finished runs for export, in `(started_at, id)` order and
+/// after `after`, the key of the last row of the previous page.
+pub fn get_run_export_page(
+    conn: &Connection,
+    filter: &RunSearchFilter,
+    after: Option<(&str, i64)>,
+    limit: i64,
+) -> Result<Vec<RunExportRow>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT r.id, r.script_id, s.name, COALESCE(c.name, ''), r.started_at, r.finished_at, {},
+                r.exit_code, r.status, r.output, r.metadata
+         FROM run_history r
+         JOIN scripts s ON s.id = r.script_id
+         LEFT JOIN categories c ON c.id = s.category_id
+         WHERE {}
+           AND (?6 IS NULL OR r.started_at > ?6 OR (r.started_at = ?6 AND r.id > ?7))
+         ORDER BY r.started_at, r.id
+         LIMIT ?8",
+        RUN_DURATION_SQL, RUN_EXPORT_FILTER_SQL
+    ))?;
+    let rows = stmt.query_map(
+        params![
+            filter.script_id,
+            filter.category_id,
+            filter.status,
+            filter.since,
+            filter.before,
+            after.map(|(started_at, _)| started_at),
+            after.map(|(_, id)| id),
+            limit
+        ],
+        |row| {
+            Ok(RunExportRow {
+                id: row.get(0)?,
+                script_id: row.get(1)?,
+                script_name: row.get(2)?,
+                category_name: row.get(3)?,
+                started_at: row.get(4)?,
+                finished_at: row.get(5)?,
+                duration_seconds: row.get(6)?,
+                exit_code: row.get(7)?,
+                status: row.get(8)?,
+                output: row.get(9)?,
+                metadata: parse_json_column(row.get(10)?),
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+// --- Script param queries ---
+
+pub fn get_script_params(conn: &Connection, script_id: i64) -> Result<Vec<ScriptParam>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, script_id, name, description, default_value, required FROM script_params
+         WHERE script_id = ?1 ORDER BY sort_order, id",
+    )?;
+    let rows = stmt.query_map(params![script_id], |row| {
+        Ok(ScriptParam {
+            id: row.get(0)?,
+            script_id: row.get(1)?,
+            name: row.get(2)?,
+            description: row.get(3)?,
+            default_value: row.get(4)?,
+            required: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Replace a script's parameter definitions, keeping the given order. Uses a
+/// savepoint so it also nests inside a config reconcile's transaction.
+pub fn replace_script_params(conn: &Connection, script_id: i64, defs: &[ScriptParam]) -> Result<(), rusqlite::Error> {
+    conn.execute_batch("SAVEPOINT replace_script_params")?;
+    let replaced = (|| {
+        conn.execute("DELETE FROM script_params WHERE script_id = ?1", params![script_id])?;
+        let mut stmt = conn.prepare(
+            "INSERT INTO script_params (script_id, name, description, default_value, required, sort_order)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (order, p) in defs.iter().enumerate() {
+            stmt.execute(params![script_id, p.name, p.description, p.default_value, p.required, order as i64])?;
+        }
+        Ok(())
+    })();
+    if replaced.is_err() {
+        let _ = conn.execute_batch("ROLLBACK TO replace_script_params");
+    }
+    conn.execute_batch("RELEASE replace_script_params")?;
+    replaced
+}
+
+// --- Config queries ---
+
+pub fn set_script_config_managed(conn: &Connection, id: i64, managed: bool) -> Result<(), rusqlite::Error> {
+    conn.execute("UPDATE scripts SET config_managed = ?1 WHERE id = ?2", params![managed, id])?;
+    Ok(())
+}
+
+/// Hand every config-managed script back to the user, e.g. when the config
+/// file is removed.
+pub fn release_config_managed_scripts(conn: &Connection) -> Result<usize, rusqlite::Error> {
+    conn.execute("UPDATE scripts SET config_managed = 0 WHERE config_managed = 1", [])
+}
+
+// --- Script source queries ---
+
+fn split_globs(text: String) -> Vec<String> {
+    text.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect()
+}
+
+fn row_to_script_source(row: &rusqlite::Row) -> Result<ScriptSource, rusqlite::Error> {
+    Ok(ScriptSource {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        include_globs: split_globs(row.get(2)?),
+        exclude_globs: split_globs(row.get(3)?),
+        category_id: row.get(4)?,
+        enabled: row.get(5)?,
+        last_scanned_at: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+// Globs may contain commas (`{a,b}`), so they are stored one per line.
+const SCRIPT_SOURCE_COLUMNS: &str =
+    "id, path, include_globs, exclude_globs, category_id, enabled, last_scanned_at, created_at";
+
+pub fn get_script_sources(conn: &Connection) -> Result<Vec<ScriptSource>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM script_sources ORDER BY path", SCRIPT_SOURCE_COLUMNS))?;
+    let rows = stmt.query_map([], row_to_script_source)?;
+    rows.collect()
+}
+
+pub fn get_script_source_by_id(conn: &Connection, id: i64) -> Result<ScriptSource, rusqlite::Error> {
+    conn.query_row(
+        &format!("SELECT {} FROM script_sources WHERE id = ?1", SCRIPT_SOURCE_COLUMNS),
+        params![id],
+        row_to_script_source,
+    )
+}
+
+pub fn insert_script_source(conn: &Connection, new: &NewScriptSource) -> Result<ScriptSource, rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO script_sources (path, include_globs, exclude_globs, category_id) VALUES (?1, ?2, ?3, ?4)",
+        params![new.path, new.include_globs.join("\n"), new.exclude_globs.join("\n"), new.category_id],
+    )?;
+    get_script_source_by_id(conn, conn.last_insert_rowid())
+}
+
+pub fn update_script_source(conn: &Connection, id: i64, update: &UpdateScriptSource) -> Result<ScriptSource, rusqlite::Error> {
+    if let Some(ref globs) = update.include_globs {
+        conn.execute("UPDATE script_sources SET include_globs = ?1 WHERE id = ?2", params![globs.join("\n"), id])?;
+    }
+    if let Some(ref globs) = update.exclude_globs {
+        conn.execute("UPDATE script_sources SET exclude_globs = ?1 WHERE id = ?2", params![globs.join("\n"), id])?;
+    }
+    if let Some(category_id) = update.category_id {
+        conn.execute(
+            "UPDATE script_sources SET category_id = NULLIF(?1, 0) WHERE id = ?2",
+            params![category_id, id],
+        )?;
+    }
+    if let Some(enabled) = update.enabled {
+        conn.execute("UPDATE script_sources SET enabled = ?1 WHERE id = ?2", params![enabled, id])?;
+    }
+    get_script_source_by_id(conn, id)
+}
+
+pub fn delete_script_source(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM script_sources WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+pub fn set_source_scanned(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE script_sources SET last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        params![id],
+    )?;
+    Ok(())
+}
+
+pub fn set_script_source(conn: &Connection, script_id: i64, source_id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE scripts SET source_id = ?1, missing = 0 WHERE id = ?2",
+        params![source_id, script_id],
+    )?;
+    Ok(())
+}
+
+pub fn set_script_missing(conn: &Connection, script_id: i64, missing: bool) -> Result<(), rusqlite::Error> {
+    conn.execute("UPDATE scripts SET missing = ?1 WHERE id = ?2", params![missing, script_id])?;
+    Ok(())
+}
+
+// --- Script header queries ---
+
+pub fn get_header_applied(conn: &Connection, script_id: i64) -> Result<Option<String>, rusqlite::Error> {
+    conn.query_row(
+        "SELECT header_applied FROM scripts WHERE id = ?1",
+        params![script_id],
+        |row| row.get(0),
+    )
+}
+
+pub fn set_header_applied(conn: &Connection, script_id: i64, header: &str) -> Result<(), rusqlite::Error> {
+    conn.execute("UPDATE scripts SET header_applied = ?1 WHERE id = ?2", params![header, script_id])?;
+    Ok(())
+}
+
+// --- Script health queries ---
+
+fn row_to_script_health(row: &rusqlite::Row) -> Result<ScriptHealth, rusqlite::Error> {
+    Ok(ScriptHealth {
+        script_id: row.get(0)?,
+        status: row.get(1)?,
+        size: row.get(2)?,
+        modified_at: row.get(3)?,
+        mode: row.get(4)?,
+        hash: row.get(5)?,
+        approved_hash: row.get(6)?,
+        checked_at: row.get(7)?,
+    })
+}
+
+const SCRIPT_HEALTH_COLUMNS: &str = "script_id, status, size, modified_at, mode, hash, approved_hash, checked_at";
+
+pub fn get_script_health(conn: &Connection, script_id: i64) -> Result<Option<ScriptHealth>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM script_health WHERE script_id = ?1",
+        SCRIPT_HEALTH_COLUMNS
+    ))?;
+    let mut rows = stmt.query_map(params![script_id], row_to_script_health)?;
+    rows.next().transpose()
+}
+
+pub fn get_all_script_health(conn: &Connection) -> Result<Vec<ScriptHealth>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM script_health ORDER BY script_id",
+        SCRIPT_HEALTH_COLUMNS
+    ))?;
+    let rows = stmt.query_map([], row_to_script_health)?;
+    rows.collect()
+}
+
+pub fn upsert_script_health(conn: &Connection, health: &ScriptHealth) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO script_health (script_id, status, size, modified_at, mode, hash, approved_hash, checked_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(script_id) DO UPDATE SET
+            status = excluded.status, size = excluded.size, modified_at = excluded.modified_at,
+            mode = excluded.mode, hash = excluded.hash, approved_hash = excluded.approved_hash,
+            checked_at = excluded.checked_at",
+        params![
+            health.script_id,
+            health.status,
+            health.size,
+            health.modified_at,
+            health.mode,
+            health.hash,
+            health.approved_hash,
+            health.checked_at,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn set_script_pinned(conn: &Connection, script_id: i64, pinned: bool) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE scripts SET pinned = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![pinned, script_id],
+    )?;
+    Ok(())
+}
+
+pub fn set_script_executable(conn: &Connection, script_id: i64, is_executable: bool) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE scripts SET is_executable = ?1 WHERE id = ?2",
+        params![is_executable, script_id],
+    )?;
+    Ok(())
+}
+
+// --- Script version queries ---
+
+/// Id of the snapshot with this content, storing it on first sight.
+pub fn get_or_insert_script_version(conn: &Connection, script_id: i64, hash: &str, content: &[u8]) -> Result<i64, rusqlite::Error> {
+    conn.execute(
+        "INSERT OR IGNORE INTO script_versions (script_id, hash, content) VALUES (?1, ?2, ?3)",
+        params![script_id, hash, content],
+    )?;
+    conn.query_row(
+        "SELECT id FROM script_versions WHERE script_id = ?1 AND hash = ?2",
+        params![script_id, hash],
+        |row| row.get(0),
+    )
+}
+
+pub fn set_run_version(conn: &Connection, run_id: i64, version_id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("UPDATE run_history SET version_id = ?1 WHERE id = ?2", params![version_id, run_id])?;
+    Ok(())
+}
+
+/// Versions of a script, newest first, with how often each one ran.
+pub fn get_script_versions(conn: &Connection, script_id: i64) -> Result<Vec<ScriptVersion>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT v.id, v.script_id, v.hash, length(v.content), v.created_at,
+                COUNT(r.id), MAX(r.started_at),
+                (SELECT status FROM run_history WHERE version_id = v.id ORDER BY started_at DESC LIMIT 1)
+         FROM script_versions v
+         LEFT JOIN run_history r ON r.version_id = v.id
+         WHERE v.script_id = ?1
+         GROUP BY v.id
+         ORDER BY v.created_at DESC, v.id DESC",
+    )?;
+    let rows = stmt.query_map(params![script_id], |row| {
+        Ok(ScriptVersion {
+            id: row.get(0)?,
+            script_id: row.get(1)?,
+            hash: row.get(2)?,
+            size: row.get(3)?,
+            created_at: row.get(4)?,
+            run_count: row.get(5)?,
+            last_run_at: row.get(6)?,
+            last_status: row.get(7)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The owning script id and stored content of a version.
+pub fn get_script_version_content(conn: &Connection, version_id: i64) -> Result<(i64, Vec<u8>), rusqlite::Error> {
+    conn.query_row(
+        "SELECT script_id, content FROM script_versions WHERE id = ?1",
+        params![version_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+
+// --- Lint queries ---
+
+pub fn set_script_lint_policy(conn: &Connection, script_id: i64, policy: Option<&str>) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE scripts SET lint_policy = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        params![policy, script_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_lint_report(conn: &Connection, script_id: i64) -> Result<Option<LintReport>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT script_id, tool, status, hash, message, linted_at FROM script_lint WHERE script_id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![script_id], |row| {
+        Ok(LintReport {
+            script_id: row.get(0)?,
+            tool: row.get(1)?,
+            status: row.get(2)?,
+            hash: row.get(3)?,
+            message: row.get(4)?,
+            linted_at: row.get(5)?,
+            errors: 0,
+            warnings: 0,
+            diagnostics: Vec::new(),
+        })
+    })?;
+    let Some(mut report) = rows.next().transpose()? else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT tool, line, column, end_line, end_column, level, code, message
+         FROM lint_diagnostics WHERE script_id = ?1 ORDER BY line, column, id",
+    )?;
+    let rows = stmt.query_map(params![script_id], |row| {
+        Ok(LintDiagnostic {
+            tool: row.get(0)?,
+            line: row.get(1)?,
+            column: row.get(2)?,
+            end_line: row.get(3)?,
+            end_column: row.get(4)?,
+            level: row.get(5)?,
+            code: row.get(6)?,
+            message: row.get(7)?,
+        })
+    })?;
+    report.diagnostics = rows.collect::<Result<Vec<_>, _>>()?;
+    report.errors = report.diagnostics.iter().filter(|d| d.level == "error").count() as i64;
+    report.warnings = report.diagnostics.iter().filter(|d| d.level == "warning").count() as i64;
+    Ok(Some(report))
+}
+
+/// Replace a script's stored lint result and diagnostics.
+pub fn save_lint_report(conn: &Connection, report: &LintReport) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO script_lint (script_id, tool, status, hash, message, linted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(script_id) DO UPDATE SET
+            tool = excluded.tool, status = excluded.status, hash = excluded.hash,
+            message = excluded.message, linted_at = excluded.linted_at",
+        params![report.script_id, report.tool, report.status, report.hash, report.message, report.linted_at],
+    )?;
+    tx.execute("DELETE FROM lint_diagnostics WHERE script_id = ?1", params![report.script_id])?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO lint_diagnostics (script_id, tool, line, column, end_line, end_column, level, code, message)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for d in &report.diagnostics {
+            stmt.execute(params![
+                report.script_id,
+                d.tool,
+                d.line,
+                d.column,
+                d.end_line,
+                d.end_column,
+                d.level,
+                d.code,
+                d.message,
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+// --- Secrets queries ---
+
+/// `None` until the vault has been set up.
+pub fn get_vault(conn: &Connection) -> Result<Option<VaultKeyInfo>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT salt, verifier FROM vault WHERE id = 1")?;
+    let mut rows = stmt.query_map([], |row| {
+        Ok(VaultKeyInfo {
+            salt: row.get(0)?,
+            verifier: row.get(1)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+pub fn insert_vault(conn: &Connection, salt: &[u8], verifier: &[u8]) -> Result<(), rusqlite::Error> {
+    conn.execute("INSERT INTO vault (id, salt, verifier) VALUES (1, ?1, ?2)", params![salt, verifier])?;
+    Ok(())
+}
+
+/// Swap in a new key: the salt and verifier together with every secret
+/// re-encrypted under it.
+pub fn rekey_vault(
+    conn: &Connection,
+    salt: &[u8],
+    verifier: &[u8],
+    secrets: &[(String, Vec<u8>)],
+) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE vault SET salt = ?1, verifier = ?2 WHERE id = 1", params![salt, verifier])?;
+    {
+        let mut stmt = tx.prepare("UPDATE secrets SET ciphertext = ?1 WHERE name = ?2")?;
+        for (name, ciphertext) in secrets {
+            stmt.execute(params![ciphertext, name])?;
+        }
+    }
+    tx.commit()
+}
+
+/// Drop the vault and every secret in it. Script environments keep their
+/// references, which fail to resolve until the secrets are added again.
+pub fn reset_vault(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM secrets", [])?;
+    tx.execute("DELETE FROM vault", [])?;
+    tx.commit()
+}
+
+pub fn get_secrets(conn: &Connection) -> Result<Vec<SecretInfo>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT s.name, s.created_at, s.updated_at,
+                (SELECT COUNT(DISTINCT e.script_id) FROM script_env e WHERE e.secret = s.name)
+         FROM secrets s ORDER BY s.name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SecretInfo {
+            name: row.get(0)?,
+            created_at: row.get(1)?,
+            updated_at: row.get(2)?,
+            used_by: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn get_secret_ciphertext(conn: &Connection, name: &str) -> Result<Option<Vec<u8>>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT ciphertext FROM secrets WHERE name = ?1")?;
+    let mut rows = stmt.query_map(params![name], |row| row.get(0))?;
+    rows.next().transpose()
+}
+
+pub fn get_secret_ciphertexts(conn: &Connection) -> Result<Vec<(String, Vec<u8>)>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT name, ciphertext FROM secrets")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+pub fn upsert_secret(conn: &Connection, name: &str, ciphertext: &[u8]) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO secrets (name, ciphertext) VALUES (?1, ?2)
+         ON CONFLICT(name) DO UPDATE SET ciphertext = excluded.ciphertext, updated_at = CURRENT_TIMESTAMP",
+        params![name, ciphertext],
+    )?;
+    Ok(())
+}
+
+pub fn delete_secret(conn: &Connection, name: &str) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM secrets WHERE name = ?1", params![name])?;
+    Ok(())
+}
+
+pub fn count_secrets(conn: &Connection) -> Result<i64, rusqlite::Error> {
+    conn.query_row("SELECT COUNT(*) FROM secrets", [], |row| row.get(0))
+}
+
+pub fn get_script_env(conn: &Connection, script_id: i64) -> Result<Vec<ScriptEnvVar>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, script_id, name, value, secret FROM script_env WHERE script_id = ?1 ORDER BY name",
+    )?;
+    let rows = stmt.query_map(params![script_id], |row| {
+        Ok(ScriptEnvVar {
+            id: row.get(0)?,
+            script_id: row.get(1)?,
+            name: row.get(2)?,
+            value: row.get(3)?,
+            secret: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn replace_script_env(conn: &Connection, script_id: i64, vars: &[NewScriptEnvVar]) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM script_env WHERE script_id = ?1", params![script_id])?;
+    {
+        let mut stmt = tx.prepare("INSERT INTO script_env (script_id, name, value, secret) VALUES (?1, ?2, ?3, ?4)")?;
+        for v in vars {
+            stmt.execute(params![script_id, v.name, v.value, v.secret])?;
+        }
+    }
+    tx.commit()
+}
+
+pub fn script_has_env(conn: &Connection, script_id: i64) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM script_env WHERE script_id = ?1)",
+        params![script_id],
+        |row| row.get(0),
+    )
+}
+
+// --- Redaction queries ---
+
+fn row_to_redaction_rule(row: &rusqlite::Row) -> Result<RedactionRule, rusqlite::Error> {
+    Ok(RedactionRule {
+        id: row.get(0)?,
+        script_id: row.get(1)?,
+        name: row.get(2)?,
+        pattern: row.get(3)?,
+        enabled: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// Every rule when `script_id` is `None`; otherwise the global rules plus
+/// the script's own.
+pub fn get_redaction_rules(conn: &Connection, script_id: Option<i64>) -> Result<Vec<RedactionRule>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, script_id, name, pattern, enabled, created_at FROM redaction_rules
+         WHERE ?1 IS NULL OR script_id IS NULL OR script_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![script_id], row_to_redaction_rule)?;
+    rows.collect()
+}
+
+pub fn get_redaction_rule_by_id(conn: &Connection, id: i64) -> Result<RedactionRule, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT id, script_id, name, pattern, enabled, created_at FROM redaction_rules WHERE id = ?1",
+    )?;
+    stmt.query_row(params![id], row_to_redaction_rule)
+}
+
+pub fn insert_redaction_rule(conn: &Connection, new: &NewRedactionRule) -> Result<RedactionRule, rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO redaction_rules (script_id, name, pattern) VALUES (?1, ?2, ?3)",
+        params![new.script_id, new.name, new.pattern],
+    )?;
+    get_redaction_rule_by_id(conn, conn.last_insert_rowid())
+}
+
+pub fn update_redaction_rule(conn: &Connection, id: i64, update: &UpdateRedactionRule) -> Result<RedactionRule, rusqlite::Error> {
+    if let Some(ref name) = update.name {
+        conn.execute("UPDATE redaction_rules SET name = ?1 WHERE id = ?2", params![name, id])?;
+    }
+    if let Some(ref pattern) = update.pattern {
+        conn.execute("UPDATE redaction_rules SET pattern = ?1 WHERE id = ?2", params![pattern, id])?;
+    }
+    if let Some(enabled) = update.enabled {
+        conn.execute("UPDATE redaction_rules SET enabled = ?1 WHERE id = ?2", params![enabled, id])?;
+    }
+    get_redaction_rule_by_id(conn, id)
+}
+
+pub fn delete_redaction_rule(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM redaction_rules WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// Ids of built-in detectors that have been switched off.
+pub fn get_disabled_detectors(conn: &Connection) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT id FROM redaction_detectors WHERE enabled = 0")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+pub fn set_detector_enabled(conn: &Connection, id: &str, enabled: bool) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO redaction_detectors (id, enabled) VALUES (?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET enabled = excluded.enabled",
+        params![id, enabled],
+    )?;
+    Ok(())
+}
+
+// --- Audit log queries ---
+
+pub fn insert_audit_entry(conn: &Connection, actor: &str, entry: &NewAuditEntry) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO audit_log (actor, action, target_type, target_id, target_name, before, after) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            actor,
+            entry.action,
+            entry.target_type,
+            entry.target_id,
+            entry.target_name,
+            entry.before.as_ref().map(|v| v.to_string()),
+            entry.after.as_ref().map(|v| v.to_string()),
+        ],
+    )?;
+    Ok(())
+}
+
+const AUDIT_FILTER_SQL: &str = "(?1 IS NULL OR action = ?1)
+           AND (?2 IS NULL OR target_type = ?2)
+           AND (?3 IS NULL OR target_id = ?3)
+           AND (?4 IS NULL OR actor = ?4)
+           AND (?5 IS NULL OR created_at >= ?5)
+           AND (?6 IS NULL OR created_at < ?6)";
+
+const AUDIT_COLUMNS: &str = "id, created_at, actor, action, target_type, target_id, target_name, before, after";
+
+fn row_to_audit_entry(row: &rusqlite::Row) -> Result<AuditEntry, rusqlite::Error> {
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        created_at: row.get(1)?,
+        actor: row.get(2)?,
+        action: row.get(3)?,
+        target_type: row.get(4)?,
+        target_id: row.get(5)?,
+        target_name: row.get(6)?,
+        before: parse_json_column(row.get(7)?),
+        after: parse_json_column(row.get(8)?),
+    })
+}
+
+/// Newest first.
+pub fn get_audit_log(conn: &Connection, filter: &AuditFilter, limit: i64, offset: i64) -> Result<Vec<AuditEntry>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM audit_log WHERE {} ORDER BY id DESC LIMIT ?7 OFFSET ?8",
+        AUDIT_COLUMNS, AUDIT_FILTER_SQL
+    ))?;
+    let rows = stmt.query_map(
+        params![
+            filter.action,
+            filter.target_type,
+            filter.target_id,
+            filter.actor,
+            filter.since,
+            filter.before,
+            limit,
+            offset
+        ],
+        row_to_audit_entry,
+    )?;
+    rows.collect()
+}
+
+/// Oldest first, streamed so large logs can be exported.
+/// A page of audit entries for export, oldest first and after the entry
+/// with id `after_id`.
+pub fn get_audit_export_page(
+    conn: &Connection,
+    filter: &AuditFilter,
+    after_id: Option<i64>,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM audit_log WHERE {} AND (?7 IS NULL OR id > ?7) ORDER BY id LIMIT ?8",
+        AUDIT_COLUMNS, AUDIT_FILTER_SQL
+    ))?;
+    let rows = stmt.query_map(
+        params![
+            filter.action,
+            filter.target_type,
+            filter.target_id,
+            filter.actor,
+            filter.since,
+            filter.before,
+            after_id,
+            limit
+        ],
+        row_to_audit_entry,
+    )?;
+    rows.collect()
+}
+
+// --- Script protection queries ---
+
+pub fn get_script_protection(conn: &Connection, script_id: i64) -> Result<Option<ScriptProtection>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT script_id, confirm_phrase, cooldown_seconds, allowed_from, allowed_until, last_run_at
+         FROM script_protection WHERE script_id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![script_id], |row| {
+        Ok(ScriptProtection {
+            script_id: row.get(0)?,
+            confirm_phrase: row.get(1)?,
+            cooldown_seconds: row.get(2)?,
+            allowed_from: row.get(3)?,
+            allowed_until: row.get(4)?,
+            last_run_at: row.get(5)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+/// Keeps `last_run_at`, so changing the guards doesn't reset a cooldown.
+pub fn upsert_script_protection(
+    conn: &Connection,
+    script_id: i64,
+    confirm_phrase: &str,
+    cooldown_seconds: Option<i64>,
+    allowed_from: Option<&str>,
+    allowed_until: Option<&str>,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO script_protection (script_id, confirm_phrase, cooldown_seconds, allowed_from, allowed_until)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(script_id) DO UPDATE SET
+            confirm_phrase = excluded.confirm_phrase,
+            cooldown_seconds = excluded.cooldown_seconds,
+            allowed_from = excluded.allowed_from,
+            allowed_until = excluded.allowed_until",
+        params![script_id, confirm_phrase, cooldown_seconds, allowed_from, allowed_until],
+    )?;
+    Ok(())
+}
+
+pub fn delete_script_protection(conn: &Connection, script_id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM script_protection WHERE script_id = ?1", params![script_id])?;
+    Ok(())
+}
+
+pub fn set_protection_last_run(conn: &Connection, script_id: i64, ran_at: Option<&str>) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE script_protection SET last_run_at = ?2 WHERE script_id = ?1",
+        params![script_id, ran_at],
+    )?;
+    Ok(())
+}
+
+// --- Resource limit queries ---
+
+pub fn get_script_limits(conn: &Connection, script_id: i64) -> Result<Option<ResourceLimits>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT script_id, cpu_seconds, address_space_mb, open_files, nice, io_class, io_priority, memory_max_mb, cpu_quota_percent
+         FROM script_limits WHERE script_id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![script_id], |row| {
+        Ok(ResourceLimits {
+            script_id: row.get(0)?,
+            cpu_seconds: row.get(1)?,
+            address_space_mb: row.get(2)?,
+            open_files: row.get(3)?,
+            nice: row.get(4)?,
+            io_class: row.get(5)?,
+            io_priority: row.get(6)?,
+            memory_max_mb: row.get(7)?,
+            cpu_quota_percent: row.get(8)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+pub fn upsert_script_limits(conn: &Connection, script_id: i64, limits: &NewResourceLimits) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT OR REPLACE INTO script_limits
+            (script_id, cpu_seconds, address_space_mb, open_files, nice, io_class, io_priority, memory_max_mb, cpu_quota_percent)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            script_id,
+            limits.cpu_seconds,
+            limits.address_space_mb,
+            limits.open_files,
+            limits.nice,
+            limits.io_class,
+            limits.io_priority,
+            limits.memory_max_mb,
+            limits.cpu_quota_percent
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_script_limits(conn: &Connection, script_id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM script_limits WHERE script_id = ?1", params![script_id])?;
+    Ok(())
+}
+
+// --- Sandbox queries ---
+
+pub fn get_script_sandbox(conn: &Connection, script_id: i64) -> Result<Option<SandboxConfig>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT script_id, tool, write_paths, allow_network FROM script_sandbox WHERE script_id = ?1")?;
+    let mut rows = stmt.query_map(params![script_id], |row| {
+        Ok(SandboxConfig {
+            script_id: row.get(0)?,
+            tool: row.get(1)?,
+            write_paths: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+            allow_network: row.get(3)?,
+        })
+    })?;
+    rows.next().transpose()
+}
+
+pub fn upsert_script_sandbox(
+    conn: &Connection,
+    script_id: i64,
+    tool: &str,
+    write_paths: &[String],
+    allow_network: bool,
+) -> Result<(), rusqlite::Error> {
+    let write_paths = serde_json::to_string(write_paths).unwrap_or_else(|_| "[]".to_string());
+    conn.execute(
+        "INSERT OR REPLACE INTO script_sandbox (script_id, tool, write_paths, allow_network) VALUES (?1, ?2, ?3, ?4)",
+        params![script_id, tool, write_paths, allow_network],
+    )?;
+    Ok(())
+}
+
+pub fn delete_script_sandbox(conn: &Connection, script_id: i64) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM script_sandbox WHERE script_id = ?1", params![script_id])?;
+    Ok(())
+}
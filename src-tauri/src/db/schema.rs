@@ -30,7 +30,7 @@ pub fn initialize(conn: &Connection) -> Result<(), rusqlite::Error> {
             finished_at DATETIME,
             exit_code INTEGER,
             output TEXT,
-            status TEXT NOT NULL DEFAULT 'running' CHECK(status IN ('running','success','error','cancelled'))
+            status TEXT NOT NULL DEFAULT 'running' CHECK(status IN ('running','success','warning','error','cancelled'))
         );
 
         CREATE TABLE IF NOT EXISTS schedules (
@@ -72,5 +72,447 @@ pub fn initialize(conn: &Connection) -> Result<(), rusqlite::Error> {
         "ALTER TABLE settings ADD COLUMN powershell_version TEXT NOT NULL DEFAULT 'ps7'",
     );
 
+    // Migration: SMTP settings for email notifiers. The password itself lives
+    // in the OS keyring; smtp_password_set only records that one was stored.
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN smtp_host TEXT NOT NULL DEFAULT ''");
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN smtp_port INTEGER NOT NULL DEFAULT 587");
+    let _ = conn.execute_batch(
+        "ALTER TABLE settings ADD COLUMN smtp_security TEXT NOT NULL DEFAULT 'starttls'",
+    );
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN smtp_username TEXT NOT NULL DEFAULT ''");
+    let _ = conn.execute_batch(
+        "ALTER TABLE settings ADD COLUMN smtp_password_set BOOLEAN NOT NULL DEFAULT 0",
+    );
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN smtp_from TEXT NOT NULL DEFAULT ''");
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN digest_time TEXT NOT NULL DEFAULT '08:00'");
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN last_digest_date TEXT");
+
+    // Migration: global history retention (0 = unlimited)
+    let _ = conn.execute_batch(
+        "ALTER TABLE settings ADD COLUMN retention_keep_runs INTEGER NOT NULL DEFAULT 0",
+    );
+    let _ = conn.execute_batch(
+        "ALTER TABLE settings ADD COLUMN retention_keep_days INTEGER NOT NULL DEFAULT 0",
+    );
+    let _ = conn.execute_batch(
+        "ALTER TABLE settings ADD COLUMN retention_failure_days INTEGER NOT NULL DEFAULT 0",
+    );
+    let _ = conn.execute_batch(
+        "ALTER TABLE settings ADD COLUMN log_retention_days INTEGER NOT NULL DEFAULT 0",
+    );
+
+    // Migration: allow the 'warning' run status. SQLite can't alter a CHECK
+    // constraint, so older run_history tables are rebuilt in place.
+    let run_history_sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'run_history'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !run_history_sql.contains("'warning'") {
+        conn.execute_batch(
+            "
+            PRAGMA foreign_keys=OFF;
+            BEGIN;
+            CREATE TABLE run_history_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                script_id INTEGER NOT NULL REFERENCES scripts(id) ON DELETE CASCADE,
+                started_at DATETIME NOT NULL,
+                finished_at DATETIME,
+                exit_code INTEGER,
+                output TEXT,
+                status TEXT NOT NULL DEFAULT 'running' CHECK(status IN ('running','success','warning','error','cancelled'))
+            );
+            INSERT INTO run_history_new (id, script_id, started_at, finished_at, exit_code, output, status)
+                SELECT id, script_id, started_at, finished_at, exit_code, output, status FROM run_history;
+            DROP TABLE run_history;
+            ALTER TABLE run_history_new RENAME TO run_history;
+            CREATE INDEX IF NOT EXISTS idx_run_history_script ON run_history(script_id);
+            CREATE INDEX IF NOT EXISTS idx_run_history_started ON run_history(started_at);
+            COMMIT;
+            PRAGMA foreign_keys=ON;
+            ",
+        )?;
+    }
+
+    // Migration: values extracted from output by pattern rules (JSON object)
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN metadata TEXT");
+
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS output_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            script_id INTEGER NOT NULL REFERENCES scripts(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            severity TEXT NOT NULL DEFAULT 'info' CHECK(severity IN ('info','warning','error')),
+            notify BOOLEAN NOT NULL DEFAULT 0,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS run_matches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id INTEGER NOT NULL REFERENCES run_history(id) ON DELETE CASCADE,
+            rule_id INTEGER REFERENCES output_rules(id) ON DELETE SET NULL,
+            rule_name TEXT NOT NULL,
+            severity TEXT NOT NULL,
+            line_number INTEGER NOT NULL,
+            line TEXT NOT NULL,
+            captures TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS run_metrics (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id INTEGER NOT NULL REFERENCES run_history(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            value REAL NOT NULL,
+            recorded_at DATETIME NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS script_retention (
+            script_id INTEGER PRIMARY KEY REFERENCES scripts(id) ON DELETE CASCADE,
+            keep_runs INTEGER,
+            keep_days INTEGER,
+            keep_failures_days INTEGER
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_output_rules_script ON output_rules(script_id);
+        CREATE INDEX IF NOT EXISTS idx_run_metrics_run ON run_metrics(run_id);
+        CREATE INDEX IF NOT EXISTS idx_run_metrics_name ON run_metrics(name);
+        CREATE INDEX IF NOT EXISTS idx_run_matches_run ON run_matches(run_id);
+        ",
+    )?;
+
+    // Full-text indexes over run output and script names/descriptions. Both
+    // are external-content tables kept in sync by triggers; existing rows are
+    // indexed once when the tables are first created.
+    let fts_exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'run_output_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS run_output_fts USING fts5(
+            output, content='run_history', content_rowid='id'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS run_history_fts_insert AFTER INSERT ON run_history BEGIN
+            INSERT INTO run_output_fts(rowid, output) VALUES (new.id, new.output);
+        END;
+        CREATE TRIGGER IF NOT EXISTS run_history_fts_delete AFTER DELETE ON run_history BEGIN
+            INSERT INTO run_output_fts(run_output_fts, rowid, output) VALUES ('delete', old.id, old.output);
+        END;
+        CREATE TRIGGER IF NOT EXISTS run_history_fts_update AFTER UPDATE OF output ON run_history BEGIN
+            INSERT INTO run_output_fts(run_output_fts, rowid, output) VALUES ('delete', old.id, old.output);
+            INSERT INTO run_output_fts(rowid, output) VALUES (new.id, new.output);
+        END;
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS scripts_fts USING fts5(
+            name, description, content='scripts', content_rowid='id'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS scripts_fts_insert AFTER INSERT ON scripts BEGIN
+            INSERT INTO scripts_fts(rowid, name, description) VALUES (new.id, new.name, new.description);
+        END;
+        CREATE TRIGGER IF NOT EXISTS scripts_fts_delete AFTER DELETE ON scripts BEGIN
+            INSERT INTO scripts_fts(scripts_fts, rowid, name, description) VALUES ('delete', old.id, old.name, old.description);
+        END;
+        CREATE TRIGGER IF NOT EXISTS scripts_fts_update AFTER UPDATE OF name, description ON scripts BEGIN
+            INSERT INTO scripts_fts(scripts_fts, rowid, name, description) VALUES ('delete', old.id, old.name, old.description);
+            INSERT INTO scripts_fts(rowid, name, description) VALUES (new.id, new.name, new.description);
+        END;
+        ",
+    )?;
+    if !fts_exists {
+        conn.execute_batch(
+            "INSERT INTO run_output_fts(run_output_fts) VALUES ('rebuild');
+             INSERT INTO scripts_fts(scripts_fts) VALUES ('rebuild');",
+        )?;
+    }
+
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS notifiers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL CHECK(kind IN ('webhook','command','email')),
+            target TEXT NOT NULL,
+            events TEXT NOT NULL DEFAULT 'failure',
+            script_id INTEGER REFERENCES scripts(id) ON DELETE CASCADE,
+            template TEXT,
+            headers TEXT,
+            max_retries INTEGER NOT NULL DEFAULT 3,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS notification_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            notifier_id INTEGER NOT NULL REFERENCES notifiers(id) ON DELETE CASCADE,
+            run_id INTEGER,
+            event TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL CHECK(status IN ('delivered','failed')),
+            response TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_notification_log_notifier ON notification_log(notifier_id);
+        ",
+    )?;
+
+    // Migration: scripts declared in a conduit.toml/yaml config file. They are
+    // reconciled from the file and read-only everywhere else.
+    let _ = conn.execute_batch("ALTER TABLE scripts ADD COLUMN config_managed BOOLEAN NOT NULL DEFAULT 0");
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN config_dir TEXT NOT NULL DEFAULT ''");
+
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_params (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            script_id INTEGER NOT NULL REFERENCES scripts(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            description TEXT,
+            default_value TEXT,
+            required BOOLEAN NOT NULL DEFAULT 0,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            UNIQUE(script_id, name)
+        );
+        ",
+    )?;
+
+    // Folders scanned for scripts. Discovered scripts point back at their
+    // source and are flagged rather than deleted when their file disappears.
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_sources (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL UNIQUE,
+            include_globs TEXT NOT NULL DEFAULT '',
+            exclude_globs TEXT NOT NULL DEFAULT '',
+            category_id INTEGER REFERENCES categories(id) ON DELETE SET NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            last_scanned_at DATETIME,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        ",
+    )?;
+    let _ = conn.execute_batch(
+        "ALTER TABLE scripts ADD COLUMN source_id INTEGER REFERENCES script_sources(id) ON DELETE SET NULL",
+    );
+    let _ = conn.execute_batch("ALTER TABLE scripts ADD COLUMN missing BOOLEAN NOT NULL DEFAULT 0");
+
+    // Header metadata: header_applied keeps the last header written to the
+    // row so it is only re-applied once the header itself changes. Scripts
+    // that predate it get '' so their current header becomes the baseline
+    // instead of being applied over what the user entered.
+    let _ = conn.execute_batch("ALTER TABLE scripts ADD COLUMN timeout_seconds INTEGER");
+    if conn.execute_batch("ALTER TABLE scripts ADD COLUMN header_applied TEXT").is_ok() {
+        conn.execute_batch("UPDATE scripts SET header_applied = ''")?;
+    }
+
+    // File health: the last seen state of each script's file and the content
+    // hash the user approved
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_health (
+            script_id INTEGER PRIMARY KEY REFERENCES scripts(id) ON DELETE CASCADE,
+            status TEXT NOT NULL DEFAULT 'ok',
+            size INTEGER,
+            modified_at DATETIME,
+            mode INTEGER,
+            hash TEXT,
+            approved_hash TEXT,
+            checked_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+        ",
+    )?;
+    let _ = conn.execute_batch("ALTER TABLE scripts ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT 0");
+
+    // Script content as it was when run, stored once per distinct hash
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            script_id INTEGER NOT NULL REFERENCES scripts(id) ON DELETE CASCADE,
+            hash TEXT NOT NULL,
+            content BLOB NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(script_id, hash)
+        );
+        ",
+    )?;
+    let _ = conn.execute_batch(
+        "ALTER TABLE run_history ADD COLUMN version_id INTEGER REFERENCES script_versions(id) ON DELETE SET NULL",
+    );
+
+    // Static lint results, kept for the content hash they were produced for
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_lint (
+            script_id INTEGER PRIMARY KEY REFERENCES scripts(id) ON DELETE CASCADE,
+            tool TEXT,
+            status TEXT NOT NULL,
+            hash TEXT,
+            message TEXT,
+            linted_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS lint_diagnostics (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            script_id INTEGER NOT NULL REFERENCES scripts(id) ON DELETE CASCADE,
+            tool TEXT NOT NULL,
+            line INTEGER NOT NULL,
+            column INTEGER NOT NULL,
+            end_line INTEGER,
+            end_column INTEGER,
+            level TEXT NOT NULL,
+            code TEXT NOT NULL,
+            message TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_lint_diagnostics_script ON lint_diagnostics(script_id);
+        ",
+    )?;
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN lint_policy TEXT NOT NULL DEFAULT 'off'");
+    let _ = conn.execute_batch("ALTER TABLE scripts ADD COLUMN lint_policy TEXT");
+
+    // Secrets vault: values are encrypted with a key derived from the master
+    // passphrase; only names are stored in the clear. `verifier` is a known
+    // value encrypted with the key, used to check a passphrase.
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS vault (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            salt BLOB NOT NULL,
+            verifier BLOB NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS secrets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            ciphertext BLOB NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS script_env (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            script_id INTEGER NOT NULL REFERENCES scripts(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            value TEXT,
+            secret TEXT,
+            UNIQUE(script_id, name)
+        );
+        ",
+    )?;
+
+    // Output redaction: user patterns (script_id NULL applies to every
+    // script) and the on/off state of built-in detectors, which default to on
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS redaction_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            script_id INTEGER REFERENCES scripts(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS redaction_detectors (
+            id TEXT PRIMARY KEY,
+            enabled BOOLEAN NOT NULL
+        );
+        ",
+    )?;
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN redactions INTEGER NOT NULL DEFAULT 0");
+
+    // Elevation: how an admin run was elevated, and why it failed if the
+    // script never got to run
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN elevation TEXT");
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN elevation_error TEXT");
+    let _ = conn.execute_batch("ALTER TABLE settings ADD COLUMN elevation_method TEXT NOT NULL DEFAULT 'auto'");
+
+    // Append-only audit trail; `before` and `after` are JSON snapshots of the
+    // target around the change
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            actor TEXT NOT NULL,
+            action TEXT NOT NULL,
+            target_type TEXT NOT NULL,
+            target_id INTEGER,
+            target_name TEXT,
+            before TEXT,
+            after TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at);
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;
+        ",
+    )?;
+
+    // Protected scripts: runs need the confirmation phrase typed, and may be
+    // limited to a daily window (local "HH:MM", wrapping past midnight when
+    // allowed_from > allowed_until) and a cooldown since last_run_at
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_protection (
+            script_id INTEGER PRIMARY KEY REFERENCES scripts(id) ON DELETE CASCADE,
+            confirm_phrase TEXT NOT NULL,
+            cooldown_seconds INTEGER,
+            allowed_from TEXT,
+            allowed_until TEXT,
+            last_run_at DATETIME
+        );
+        ",
+    )?;
+
+    // Per-script resource limits. Address space, CPU time, open files,
+    // nice and IO priority are rlimits/priorities on the child; memory_max_mb
+    // and cpu_quota_percent are cgroup limits
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_limits (
+            script_id INTEGER PRIMARY KEY REFERENCES scripts(id) ON DELETE CASCADE,
+            cpu_seconds INTEGER,
+            address_space_mb INTEGER,
+            open_files INTEGER,
+            nice INTEGER,
+            io_class TEXT,
+            io_priority INTEGER,
+            memory_max_mb INTEGER,
+            cpu_quota_percent INTEGER
+        );
+        ",
+    )?;
+
+    // Resource usage of a run's process tree, from wait4
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN peak_rss_kb INTEGER");
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN user_cpu_ms INTEGER");
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN system_cpu_ms INTEGER");
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN wall_ms INTEGER");
+
+    // Opt-in sandbox: the root filesystem is read-only apart from
+    // write_paths (a JSON array), and the network can be cut off. The
+    // profile a run actually used is kept with it
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS script_sandbox (
+            script_id INTEGER PRIMARY KEY REFERENCES scripts(id) ON DELETE CASCADE,
+            tool TEXT NOT NULL DEFAULT 'auto',
+            write_paths TEXT NOT NULL DEFAULT '[]',
+            allow_network BOOLEAN NOT NULL DEFAULT 1
+        );
+        ",
+    )?;
+    let _ = conn.execute_batch("ALTER TABLE run_history ADD COLUMN sandbox TEXT");
+
     Ok(())
 }
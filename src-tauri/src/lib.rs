@@ -2,13 +2,45 @@ mod commands;
 mod db;
 mod models;
 
+use commands::config::ConfigState;
+use commands::elevation::ElevationState;
 use commands::runner::RunnerState;
+use commands::secrets::VaultState;
+use commands::sources::SourceState;
 use db::Database;
 
+/// Run a script headless for a scheduled task; see `conduit --run-script`.
+pub fn run_scheduled(script_id: &str) -> i32 {
+    commands::runner::run_scheduled(script_id)
+}
+
+/// Environment variable marking Conduit's launch as sudo's askpass helper.
+pub use commands::elevation::ASKPASS_SOCKET_ENV;
+
+/// Forward a sudo password prompt to the running app; see `ASKPASS_SOCKET_ENV`.
+#[cfg(unix)]
+pub fn askpass(socket: &str, prompt: &str) -> i32 {
+    commands::elevation::askpass(socket, prompt)
+}
+
+/// First argument of a Conduit launch that applies resource limits and then
+/// execs the rest of its arguments.
+pub use commands::limits::WITH_LIMITS_FLAG;
+
+/// Apply the limits given after `WITH_LIMITS_FLAG` and exec the command.
+#[cfg(unix)]
+pub fn exec_with_limits(args: &[String]) -> i32 {
+    commands::limits::exec_with_limits(args)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let database = Database::new().expect("Failed to initialize database");
     let runner_state = RunnerState::new();
+    let config_state = ConfigState::new();
+    let source_state = SourceState::new();
+    let vault_state = VaultState::new();
+    let elevation_state = ElevationState::new();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
@@ -16,11 +48,16 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .manage(database)
         .manage(runner_state)
+        .manage(config_state)
+        .manage(source_state)
+        .manage(vault_state)
+        .manage(elevation_state)
         .invoke_handler(tauri::generate_handler![
             commands::scripts::add_script,
             commands::scripts::get_scripts,
             commands::scripts::update_script,
             commands::scripts::delete_script,
+            commands::scripts::get_script_params,
             commands::categories::get_categories,
             commands::categories::add_category,
             commands::categories::update_category,
@@ -31,20 +68,113 @@ pub fn run() {
             commands::runner::is_script_running,
             commands::runner::write_script_input,
             commands::runner::resize_script_pty,
+            commands::runner::preview_run,
             commands::history::get_run_history,
             commands::history::get_latest_run,
             commands::history::clear_history,
+            commands::history::get_recent_runs,
+            commands::history::get_script_stats,
+            commands::history::get_failing_scripts,
+            commands::history::get_category_stats,
+            commands::export::export_run_history,
+            commands::library::export_library,
+            commands::library::preview_library_import,
+            commands::library::import_library,
+            commands::config::get_config_status,
+            commands::config::set_config_dir,
+            commands::config::reload_config,
+            commands::sources::get_script_sources,
+            commands::sources::add_script_source,
+            commands::sources::update_script_source,
+            commands::sources::delete_script_source,
+            commands::sources::scan_script_sources,
+            commands::header::get_script_header,
+            commands::header::apply_script_header,
+            commands::health::get_script_health,
+            commands::health::check_script_files,
+            commands::health::approve_script_file,
+            commands::health::set_script_pinned,
+            commands::health::find_moved_script,
+            commands::health::relocate_script,
+            commands::versions::get_script_versions,
+            commands::versions::get_script_version_content,
+            commands::versions::diff_script_versions,
+            commands::versions::restore_script_version,
+            commands::editor::get_script_templates,
+            commands::editor::create_script_from_template,
+            commands::editor::read_script_file,
+            commands::editor::save_script_file,
+            commands::lint::lint_script,
+            commands::lint::get_lint_report,
+            commands::lint::set_script_lint_policy,
+            commands::secrets::get_vault_status,
+            commands::secrets::setup_vault,
+            commands::secrets::unlock_vault,
+            commands::secrets::lock_vault,
+            commands::secrets::change_vault_passphrase,
+            commands::secrets::reset_vault,
+            commands::secrets::get_secrets,
+            commands::secrets::set_secret,
+            commands::secrets::delete_secret,
+            commands::secrets::get_script_env,
+            commands::secrets::set_script_env,
+            commands::redaction::get_redaction_rules,
+            commands::redaction::add_redaction_rule,
+            commands::redaction::update_redaction_rule,
+            commands::redaction::delete_redaction_rule,
+            commands::redaction::get_redaction_detectors,
+            commands::redaction::set_redaction_detector,
+            commands::elevation::get_elevation_status,
+            commands::elevation::answer_elevation_prompt,
+            commands::audit::get_audit_log,
+            commands::audit::export_audit_log,
+            commands::protection::get_script_protection,
+            commands::protection::set_script_protection,
+            commands::limits::get_script_limits,
+            commands::limits::set_script_limits,
+            commands::limits::get_resource_limit_support,
+            commands::sandbox::get_script_sandbox,
+            commands::sandbox::set_script_sandbox,
+            commands::sandbox::get_sandbox_tools,
             commands::scheduler::create_schedule,
             commands::scheduler::update_schedule,
             commands::scheduler::delete_schedule,
             commands::scheduler::toggle_schedule,
+            commands::scheduler::get_schedule_usage,
             commands::scheduler::get_schedules,
             commands::scheduler::sync_schedules,
+            commands::scheduler::preview_schedule,
+            commands::scheduler::preview_new_schedule,
             commands::settings::get_settings,
             commands::settings::update_settings,
             commands::settings::open_in_editor,
+            commands::notifications::get_notifiers,
+            commands::notifications::add_notifier,
+            commands::notifications::update_notifier,
+            commands::notifications::delete_notifier,
+            commands::notifications::test_notifier,
+            commands::notifications::get_notification_log,
+            commands::output_rules::get_output_rules,
+            commands::output_rules::add_output_rule,
+            commands::output_rules::update_output_rule,
+            commands::output_rules::delete_output_rule,
+            commands::output_rules::get_run_matches,
+            commands::metrics::get_run_metrics,
+            commands::metrics::get_metric_names,
+            commands::metrics::get_metric_series,
+            commands::search::search_runs,
+            commands::search::search_scripts,
+            commands::retention::get_script_retention,
+            commands::retention::set_script_retention,
+            commands::retention::clear_script_retention,
+            commands::retention::prune_history,
         ])
-        .setup(|_app| {
+        .setup(|app| {
+            commands::notifications::start_digest_worker(app.handle().clone());
+            commands::retention::start_retention_worker(app.handle().clone());
+            commands::config::start_config_watcher(app.handle().clone());
+            commands::sources::start_source_watcher(app.handle().clone());
+            commands::health::start_health_monitor(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -8,5 +8,29 @@ fn main() {
         std::env::set_var("GDK_BACKEND", "wayland");
     }
 
+    let args: Vec<String> = std::env::args().collect();
+
+    // Runs with resource limits are started through us; checked first since
+    // the askpass variable is inherited by the script
+    #[cfg(unix)]
+    if args.get(1).map(String::as_str) == Some(conduit_lib::WITH_LIMITS_FLAG) {
+        std::process::exit(conduit_lib::exec_with_limits(&args[2..]));
+    }
+
+    // sudo -A runs us as its askpass helper with the prompt as the argument
+    #[cfg(unix)]
+    if let Ok(socket) = std::env::var(conduit_lib::ASKPASS_SOCKET_ENV) {
+        let prompt = args.get(1).map(String::as_str).unwrap_or("Password:");
+        std::process::exit(conduit_lib::askpass(&socket, prompt));
+    }
+
+    // Scheduled tasks for scripts with environment variables or that run as
+    // admin go through us
+    if let [_, flag, script_id] = args.as_slice() {
+        if flag == "--run-script" {
+            std::process::exit(conduit_lib::run_scheduled(script_id));
+        }
+    }
+
     conduit_lib::run()
 }
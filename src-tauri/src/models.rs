@@ -13,6 +13,22 @@ pub struct Script {
     pub run_as_admin: bool,
     pub created_at: String,
     pub updated_at: String,
+    /// Declared in a conduit.toml/yaml config file and only editable there.
+    pub config_managed: bool,
+    /// The watched folder this script was discovered in, if any.
+    pub source_id: Option<i64>,
+    /// Set when a discovered script's file is no longer found by a scan.
+    pub missing: bool,
+    /// Runs still going after this many seconds are terminated.
+    pub timeout_seconds: Option<i64>,
+    /// Refuse to run once the file differs from the approved content.
+    pub pinned: bool,
+    /// "off", "warn" or "block"; None follows the global setting.
+    pub lint_policy: Option<String>,
+    /// Has a `ScriptProtection`, so runs must be confirmed.
+    pub protected: bool,
+    /// Has a `SandboxConfig`, so runs are sandboxed.
+    pub sandboxed: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -34,6 +50,23 @@ pub struct RunRecord {
     pub exit_code: Option<i32>,
     pub output: Option<String>,
     pub status: String,
+    pub metadata: Option<serde_json::Value>,
+    /// Snapshot of the script content this run executed.
+    pub version_id: Option<i64>,
+    /// Secrets and sensitive matches replaced in this run's output.
+    pub redactions: i64,
+    /// How an admin run was elevated, e.g. "sudo-askpass" or "pkexec".
+    pub elevation: Option<String>,
+    /// Set when elevation failed and the script never ran.
+    pub elevation_error: Option<String>,
+    /// Largest resident set of any process in the run.
+    pub peak_rss_kb: Option<i64>,
+    pub user_cpu_ms: Option<i64>,
+    pub system_cpu_ms: Option<i64>,
+    /// From spawn until the script exited.
+    pub wall_ms: Option<i64>,
+    /// The sandbox the run was started in, if any.
+    pub sandbox: Option<SandboxProfile>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -70,6 +103,9 @@ pub struct UpdateScript {
     pub category_id: Option<i64>,
     pub color: Option<String>,
     pub run_as_admin: Option<bool>,
+    /// 0 clears the timeout.
+    #[serde(default)]
+    pub timeout_seconds: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -89,6 +125,25 @@ pub struct Settings {
     pub editor_path: String,
     pub theme: String,
     pub powershell_version: String,
+    pub smtp_host: String,
+    pub smtp_port: i64,
+    pub smtp_security: String,
+    pub smtp_username: String,
+    pub smtp_password_set: bool,
+    pub smtp_from: String,
+    pub digest_time: String,
+    pub retention_keep_runs: i64,
+    pub retention_keep_days: i64,
+    pub retention_failure_days: i64,
+    pub log_retention_days: i64,
+    /// Directory watched for conduit.toml/yaml; empty when unset.
+    pub config_dir: String,
+    /// Pre-run lint: "off", "warn" (lint and record) or "block" (refuse to
+    /// run with lint errors).
+    pub lint_policy: String,
+    /// How admin scripts elevate on Unix: "auto", "sudo", "pkexec" or
+    /// "terminal" (sudo prompting inside the run's terminal).
+    pub elevation_method: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,4 +152,923 @@ pub struct UpdateSettings {
     pub editor_path: Option<String>,
     pub theme: Option<String>,
     pub powershell_version: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<i64>,
+    pub smtp_security: Option<String>,
+    pub smtp_username: Option<String>,
+    /// Stored in the OS keyring, never in the database. An empty string
+    /// removes the saved password.
+    pub smtp_password: Option<String>,
+    pub smtp_from: Option<String>,
+    pub digest_time: Option<String>,
+    pub retention_keep_runs: Option<i64>,
+    pub retention_keep_days: Option<i64>,
+    pub retention_failure_days: Option<i64>,
+    pub log_retention_days: Option<i64>,
+    pub lint_policy: Option<String>,
+    pub elevation_method: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Notifier {
+    pub id: i64,
+    pub name: String,
+    pub kind: String,
+    pub target: String,
+    pub events: Vec<String>,
+    pub script_id: Option<i64>,
+    pub template: Option<String>,
+    pub headers: Option<String>,
+    pub max_retries: i64,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewNotifier {
+    pub name: String,
+    pub kind: String,
+    pub target: String,
+    pub events: Vec<String>,
+    pub script_id: Option<i64>,
+    pub template: Option<String>,
+    pub headers: Option<String>,
+    pub max_retries: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNotifier {
+    pub name: Option<String>,
+    pub target: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub template: Option<String>,
+    pub headers: Option<String>,
+    pub max_retries: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationLogEntry {
+    pub id: i64,
+    pub notifier_id: i64,
+    pub run_id: Option<i64>,
+    pub event: String,
+    pub attempts: i64,
+    pub status: String,
+    pub response: Option<String>,
+    pub created_at: String,
+}
+
+/// JSON body sent to notifiers for run events.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPayload {
+    pub event: String,
+    pub script_id: i64,
+    pub script_name: String,
+    pub run_id: i64,
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub duration_seconds: Option<i64>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub output_tail: String,
+    /// Name of the output rule that fired, for "match" events.
+    pub matched_rule: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputRule {
+    pub id: i64,
+    pub script_id: i64,
+    pub name: String,
+    pub pattern: String,
+    pub severity: String,
+    pub notify: bool,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewOutputRule {
+    pub script_id: i64,
+    pub name: String,
+    pub pattern: String,
+    pub severity: String,
+    pub notify: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOutputRule {
+    pub name: Option<String>,
+    pub pattern: Option<String>,
+    pub severity: Option<String>,
+    pub notify: Option<bool>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMatch {
+    pub id: i64,
+    pub run_id: i64,
+    pub rule_id: Option<i64>,
+    pub rule_name: String,
+    pub severity: String,
+    pub line_number: i64,
+    pub line: String,
+    pub captures: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMetric {
+    pub id: i64,
+    pub run_id: i64,
+    pub name: String,
+    pub value: f64,
+    pub recorded_at: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricPoint {
+    pub run_id: i64,
+    pub started_at: String,
+    pub status: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSearchFilter {
+    pub script_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub status: Option<String>,
+    /// Inclusive lower bound on `started_at` (UTC, `YYYY-MM-DD[ HH:MM:SS]`).
+    pub since: Option<String>,
+    /// Exclusive upper bound on `started_at`.
+    pub before: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSearchHit {
+    pub run_id: i64,
+    pub script_id: i64,
+    pub script_name: String,
+    pub started_at: String,
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptSearchHit {
+    pub script_id: i64,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Per-script retention override. `None` inherits the global setting and
+/// 0 means unlimited.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptRetention {
+    pub script_id: i64,
+    pub keep_runs: Option<i64>,
+    pub keep_days: Option<i64>,
+    pub keep_failures_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunedScript {
+    pub script_id: i64,
+    pub script_name: String,
+    pub runs: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneReport {
+    pub dry_run: bool,
+    pub total_runs: i64,
+    pub scripts: Vec<PrunedScript>,
+    pub log_files: Vec<String>,
+    pub log_bytes: u64,
+}
+
+/// A run without its captured output, for cross-script listings.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunListItem {
+    pub id: i64,
+    pub script_id: i64,
+    pub script_name: String,
+    pub category_id: i64,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub exit_code: Option<i32>,
+    pub status: String,
+    pub duration_seconds: Option<f64>,
+    pub peak_rss_kb: Option<i64>,
+    /// User plus system CPU time.
+    pub cpu_ms: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptStats {
+    pub script_id: i64,
+    pub script_name: String,
+    pub category_id: i64,
+    pub total_runs: i64,
+    pub successes: i64,
+    pub failures: i64,
+    /// Share of finished, non-cancelled runs that succeeded (warnings count
+    /// as successes). `None` when there are no such runs.
+    pub success_rate: Option<f64>,
+    pub avg_duration_seconds: Option<f64>,
+    pub p95_duration_seconds: Option<f64>,
+    pub last_run_at: Option<String>,
+    pub last_status: Option<String>,
+    /// Over runs with recorded usage.
+    pub avg_cpu_ms: Option<f64>,
+    pub max_peak_rss_kb: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FailingScript {
+    pub script_id: i64,
+    pub script_name: String,
+    pub category_id: i64,
+    pub last_run_id: i64,
+    pub failed_at: String,
+    pub exit_code: Option<i32>,
+    pub consecutive_failures: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryStats {
+    pub category_id: i64,
+    pub category_name: String,
+    pub script_count: i64,
+    pub total_runs: i64,
+    pub successes: i64,
+    pub failures: i64,
+    pub success_rate: Option<f64>,
+    pub avg_duration_seconds: Option<f64>,
+}
+
+/// One finished run as written by the history exporters.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunExportRow {
+    pub id: i64,
+    pub script_id: i64,
+    pub script_name: String,
+    pub category_name: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub duration_seconds: Option<f64>,
+    pub exit_code: Option<i32>,
+    pub status: String,
+    pub output: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct RunExportTotals {
+    pub runs: i64,
+    pub failures: i64,
+    pub skipped: i64,
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LibraryImportOptions {
+    /// Where bundled script files are extracted; defaults to ~/.conduit/scripts.
+    pub scripts_dir: Option<String>,
+    /// What to do when a script's file or path already exists: "rename"
+    /// (default), "overwrite" or "skip".
+    pub on_conflict: Option<String>,
+    pub apply_settings: bool,
+    pub skip_schedules: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedCategory {
+    pub name: String,
+    /// "create" or "existing".
+    pub action: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedScript {
+    pub name: String,
+    pub category: String,
+    pub source_path: String,
+    pub target_path: String,
+    /// "create", "update" or "skip".
+    pub action: String,
+    /// For bundled files: "write", "overwrite", "identical" or "keep".
+    pub file_action: Option<String>,
+    pub run_as_admin: bool,
+    pub schedules: i64,
+    /// The schedules that would be installed, e.g. "daily 08:00".
+    pub schedule_specs: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryImportPlan {
+    pub version: u32,
+    pub categories: Vec<PlannedCategory>,
+    pub scripts: Vec<PlannedScript>,
+    /// Settings that differ from the current ones and are applied when
+    /// `applySettings` is set.
+    pub settings_changes: Vec<String>,
+    pub applied: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptParam {
+    pub id: i64,
+    pub script_id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub default_value: Option<String>,
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigError {
+    pub file: String,
+    /// 1-based line in the config file, when it can be located.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigStatus {
+    /// The config file in use, if one was found in the config directory.
+    pub path: Option<String>,
+    pub loaded_at: Option<String>,
+    /// Validation errors; when non-empty the database was left unchanged.
+    pub errors: Vec<ConfigError>,
+    /// Problems applying an otherwise valid config, such as schedules the
+    /// platform backend rejected.
+    pub warnings: Vec<String>,
+    pub managed_scripts: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptSource {
+    pub id: i64,
+    pub path: String,
+    /// Globs matched against paths relative to the folder.
+    pub include_globs: Vec<String>,
+    pub exclude_globs: Vec<String>,
+    /// Category for files directly in the folder; files in subfolders go to
+    /// a category named after their top-level subfolder.
+    pub category_id: Option<i64>,
+    pub enabled: bool,
+    pub last_scanned_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewScriptSource {
+    pub path: String,
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    pub category_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateScriptSource {
+    pub include_globs: Option<Vec<String>>,
+    pub exclude_globs: Option<Vec<String>>,
+    /// 0 clears the category.
+    pub category_id: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReport {
+    pub source_id: i64,
+    pub added: i64,
+    pub updated: i64,
+    pub missing: i64,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderParam {
+    pub name: String,
+    pub description: Option<String>,
+    pub default_value: Option<String>,
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderSchedule {
+    pub schedule_type: String,
+    pub time: Option<String>,
+    pub weekday: Option<i32>,
+    pub interval_seconds: Option<i64>,
+}
+
+/// Metadata declared with `@tag` lines in a script's top comment block.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptHeader {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub params: Vec<HeaderParam>,
+    pub schedules: Vec<HeaderSchedule>,
+    pub timeout_seconds: Option<i64>,
+    /// Tag lines that couldn't be understood, with their line number.
+    pub warnings: Vec<String>,
+    /// The description came from the first comment paragraph rather than an
+    /// `@description` tag, so it is only applied on request.
+    #[serde(skip)]
+    pub description_untagged: bool,
+}
+
+/// A field whose value in the library differs from the script's header.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderDrift {
+    pub field: String,
+    pub header_value: String,
+    pub db_value: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptHeaderReport {
+    pub header: ScriptHeader,
+    pub drift: Vec<HeaderDrift>,
+}
+
+/// Last known state of a script's file on disk.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptHealth {
+    pub script_id: i64,
+    /// "ok", "modified" (content differs from the approved hash), "missing"
+    /// or "unreadable".
+    pub status: String,
+    pub size: Option<i64>,
+    pub modified_at: Option<String>,
+    /// Unix permission bits; always None on Windows.
+    pub mode: Option<i64>,
+    pub hash: Option<String>,
+    pub approved_hash: Option<String>,
+    pub checked_at: String,
+}
+
+/// A distinct revision of a script's content, captured when it ran.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptVersion {
+    pub id: i64,
+    pub script_id: i64,
+    pub hash: String,
+    pub size: i64,
+    pub created_at: String,
+    pub run_count: i64,
+    pub last_run_at: Option<String>,
+    /// Status of the most recent run of this version.
+    pub last_status: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptDiff {
+    pub unified: String,
+    pub additions: i64,
+    pub deletions: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptTemplate {
+    pub id: String,
+    pub label: String,
+    pub extension: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTemplateScript {
+    pub name: String,
+    /// One of the ids from `get_script_templates`.
+    pub template: String,
+    pub description: Option<String>,
+    pub category_id: i64,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LintDiagnostic {
+    pub tool: String,
+    pub line: i64,
+    pub column: i64,
+    pub end_line: Option<i64>,
+    pub end_column: Option<i64>,
+    /// "error", "warning", "info" or "style".
+    pub level: String,
+    /// Rule id, e.g. "SC2086" or "PSAvoidUsingWriteHost".
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LintReport {
+    pub script_id: i64,
+    pub tool: Option<String>,
+    /// "ok", "issues", "unsupported" (no linter for this file type),
+    /// "unavailable" (linter not installed) or "failed".
+    pub status: String,
+    /// Content hash the diagnostics belong to.
+    pub hash: Option<String>,
+    pub message: Option<String>,
+    pub linted_at: String,
+    pub errors: i64,
+    pub warnings: i64,
+    pub diagnostics: Vec<LintDiagnostic>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewEnvVar {
+    pub name: String,
+    pub value: String,
+    /// Set by Conduit rather than inherited from the app's environment.
+    pub overridden: bool,
+    /// Comes from the secrets vault; `value` is masked.
+    pub secret: bool,
+}
+
+/// What `run_script` would spawn for a script, without spawning it.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunPreview {
+    pub script_id: i64,
+    pub argv: Vec<String>,
+    pub env: Vec<PreviewEnvVar>,
+    pub cwd: String,
+    pub cols: u16,
+    pub rows: u16,
+    pub run_as_admin: bool,
+    /// The elevation method an admin run would use.
+    pub elevation: Option<String>,
+    /// The sandbox a run would start in.
+    pub sandbox: Option<SandboxProfile>,
+    pub timeout_seconds: Option<i64>,
+    pub lint_policy: String,
+    /// Why a real run would be refused right now.
+    pub blocked: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// The OS scheduler files and commands a schedule installs, without
+/// installing them.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulePreview {
+    pub label: String,
+    pub files: Vec<PreviewFile>,
+    pub commands: Vec<Vec<String>>,
+}
+
+/// The vault's key salt and passphrase verifier.
+#[derive(Debug)]
+pub struct VaultKeyInfo {
+    pub salt: Vec<u8>,
+    pub verifier: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub initialized: bool,
+    pub unlocked: bool,
+    /// The vault key is kept in the OS keyring, so it unlocks on its own.
+    pub remembered: bool,
+    pub secret_count: i64,
+}
+
+/// A stored secret; the value itself is never returned.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInfo {
+    pub name: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Scripts whose environment references this secret.
+    pub used_by: i64,
+}
+
+/// An environment variable set for a script's runs, either a literal value or
+/// a reference to a secret by name.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptEnvVar {
+    pub id: i64,
+    pub script_id: i64,
+    pub name: String,
+    pub value: Option<String>,
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewScriptEnvVar {
+    pub name: String,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRule {
+    pub id: i64,
+    /// `None` applies the rule to every script.
+    pub script_id: Option<i64>,
+    pub name: String,
+    pub pattern: String,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewRedactionRule {
+    pub script_id: Option<i64>,
+    pub name: String,
+    pub pattern: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRedactionRule {
+    pub name: Option<String>,
+    pub pattern: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// A built-in pattern for a common credential format.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionDetector {
+    pub id: String,
+    pub label: String,
+    pub pattern: String,
+    pub enabled: bool,
+}
+
+/// What this machine offers for running admin scripts.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ElevationStatus {
+    pub sudo: bool,
+    /// `sudo -n` succeeds without a password.
+    pub passwordless_sudo: bool,
+    pub pkexec: bool,
+    /// The configured setting.
+    pub method: String,
+    /// What an admin run would use right now.
+    pub effective: Option<String>,
+    /// Why an admin run can't elevate with the configured method.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: String,
+    /// OS account that performed the action.
+    pub actor: String,
+    /// e.g. "script.update" or "schedule.toggle".
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<i64>,
+    pub target_name: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<i64>,
+    pub actor: Option<String>,
+    /// Inclusive lower bound on `created_at` (UTC, `YYYY-MM-DD[ HH:MM:SS]`).
+    pub since: Option<String>,
+    /// Exclusive upper bound on `created_at`.
+    pub before: Option<String>,
+}
+
+pub struct NewAuditEntry {
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<i64>,
+    pub target_name: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Guards on running a script: the phrase to type, and optionally a minimum
+/// gap between runs and a daily window (local time) they're allowed in.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptProtection {
+    pub script_id: i64,
+    pub confirm_phrase: String,
+    pub cooldown_seconds: Option<i64>,
+    /// "HH:MM"; set together with `allowed_until`.
+    pub allowed_from: Option<String>,
+    /// "HH:MM", exclusive. Earlier than `allowed_from` for overnight windows.
+    pub allowed_until: Option<String>,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    pub last_run_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewScriptProtection {
+    /// Defaults to the script's name.
+    #[serde(default)]
+    pub confirm_phrase: Option<String>,
+    #[serde(default)]
+    pub cooldown_seconds: Option<i64>,
+    #[serde(default)]
+    pub allowed_from: Option<String>,
+    #[serde(default)]
+    pub allowed_until: Option<String>,
+}
+
+/// Resource limits applied to a script's runs on Unix. None leaves the
+/// inherited value.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimits {
+    pub script_id: i64,
+    /// RLIMIT_CPU; the run is killed after this much CPU time.
+    pub cpu_seconds: Option<i64>,
+    /// RLIMIT_AS.
+    pub address_space_mb: Option<i64>,
+    /// RLIMIT_NOFILE.
+    pub open_files: Option<i64>,
+    /// 0 to 19; runs can only be made lower priority.
+    pub nice: Option<i32>,
+    /// "best-effort" or "idle" (Linux only).
+    pub io_class: Option<String>,
+    /// 0 (highest) to 7, for the best-effort class.
+    pub io_priority: Option<i32>,
+    /// cgroup `MemoryMax`; needs systemd and cgroup v2.
+    pub memory_max_mb: Option<i64>,
+    /// cgroup `CPUQuota`, where 100 is one full CPU; needs systemd and cgroup v2.
+    pub cpu_quota_percent: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewResourceLimits {
+    #[serde(default)]
+    pub cpu_seconds: Option<i64>,
+    #[serde(default)]
+    pub address_space_mb: Option<i64>,
+    #[serde(default)]
+    pub open_files: Option<i64>,
+    #[serde(default)]
+    pub nice: Option<i32>,
+    #[serde(default)]
+    pub io_class: Option<String>,
+    #[serde(default)]
+    pub io_priority: Option<i32>,
+    #[serde(default)]
+    pub memory_max_mb: Option<i64>,
+    #[serde(default)]
+    pub cpu_quota_percent: Option<i64>,
+}
+
+/// What this machine can enforce.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimitSupport {
+    /// Runs can be placed in a systemd scope for `memoryMaxMb` and `cpuQuotaPercent`.
+    pub cgroups: bool,
+    pub io_priority: bool,
+}
+
+/// Resource usage of a finished run.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RunUsage {
+    pub peak_rss_kb: Option<i64>,
+    pub user_cpu_ms: Option<i64>,
+    pub system_cpu_ms: Option<i64>,
+    pub wall_ms: Option<i64>,
+}
+
+/// Usage of a schedule's most recent run, as accounted by systemd.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleUsage {
+    pub schedule_id: i64,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    pub started_at: Option<String>,
+    pub exit_code: Option<i32>,
+    /// User plus system; systemd doesn't split them.
+    pub cpu_ms: Option<i64>,
+    pub peak_memory_kb: Option<i64>,
+    pub wall_ms: Option<i64>,
+}
+
+/// Opt-in sandbox for a script's runs (Linux).
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxConfig {
+    pub script_id: i64,
+    /// "auto", "bwrap" or "firejail"; auto prefers bubblewrap.
+    pub tool: String,
+    /// Absolute paths left writable under the read-only root.
+    pub write_paths: Vec<String>,
+    pub allow_network: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSandboxConfig {
+    /// Defaults to "auto".
+    #[serde(default)]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub write_paths: Vec<String>,
+    /// Defaults to allowed.
+    #[serde(default)]
+    pub allow_network: Option<bool>,
+}
+
+/// The sandbox a run actually started in.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxProfile {
+    /// "bwrap" or "firejail".
+    pub tool: String,
+    pub read_only_root: bool,
+    pub write_paths: Vec<String>,
+    pub network: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxTools {
+    pub bwrap: bool,
+    pub firejail: bool,
 }
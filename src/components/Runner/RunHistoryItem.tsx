@@ -37,6 +37,7 @@ export function RunHistoryItem({ record }: RunHistoryItemProps) {
           className={clsx(
             "w-2 h-2 rounded-none",
             record.status === "success" && "bg-status-success",
+            record.status === "warning" && "bg-status-warning",
             record.status === "error" && "bg-status-error",
             record.status === "running" && "bg-status-running animate-pulse",
             record.status === "cancelled" && "bg-hub-text-dim",
@@ -8,6 +8,65 @@ import type {
   UpdateScript,
   Settings,
   UpdateSettings,
+  Notifier,
+  NewNotifier,
+  UpdateNotifier,
+  NotificationLogEntry,
+  OutputRule,
+  NewOutputRule,
+  UpdateOutputRule,
+  RunMatch,
+  RunMetric,
+  MetricPoint,
+  RunSearchFilter,
+  RunSearchHit,
+  ScriptSearchHit,
+  ScriptRetention,
+  PruneReport,
+  RunListItem,
+  ScriptStats,
+  FailingScript,
+  CategoryStats,
+  ExportFormat,
+  LibraryImportOptions,
+  LibraryImportPlan,
+  ScriptParam,
+  ConfigStatus,
+  ScriptSource,
+  NewScriptSource,
+  UpdateScriptSource,
+  ScanReport,
+  ScriptHeaderReport,
+  ScriptHealth,
+  ScriptVersion,
+  ScriptDiff,
+  ScriptTemplate,
+  NewTemplateScript,
+  LintReport,
+  LintPolicy,
+  RunPreview,
+  SchedulePreview,
+  VaultStatus,
+  SecretInfo,
+  ScriptEnvVar,
+  NewScriptEnvVar,
+  RedactionRule,
+  NewRedactionRule,
+  UpdateRedactionRule,
+  RedactionDetector,
+  ElevationStatus,
+  AuditEntry,
+  AuditFilter,
+  AuditExportFormat,
+  ScriptProtection,
+  NewScriptProtection,
+  ResourceLimits,
+  NewResourceLimits,
+  ResourceLimitSupport,
+  ScheduleUsage,
+  SandboxConfig,
+  NewSandboxConfig,
+  SandboxTools,
 } from "../types";
 
 // Script commands
@@ -32,6 +91,307 @@ export async function deleteScript(id: number): Promise<void> {
   return invoke("delete_script", { id });
 }
 
+export async function getScriptParams(scriptId: number): Promise<ScriptParam[]> {
+  return invoke("get_script_params", { scriptId });
+}
+
+// Script header commands
+export async function getScriptHeader(
+  scriptId: number,
+): Promise<ScriptHeaderReport> {
+  return invoke("get_script_header", { scriptId });
+}
+
+/** Returns the updated script and any schedules that failed to install. */
+export async function applyScriptHeader(
+  scriptId: number,
+): Promise<[Script, string[]]> {
+  return invoke("apply_script_header", { scriptId });
+}
+
+// File health commands
+export async function getScriptHealth(): Promise<ScriptHealth[]> {
+  return invoke("get_script_health");
+}
+
+export async function checkScriptFiles(): Promise<ScriptHealth[]> {
+  return invoke("check_script_files");
+}
+
+export async function approveScriptFile(
+  scriptId: number,
+): Promise<ScriptHealth> {
+  return invoke("approve_script_file", { scriptId });
+}
+
+export async function setScriptPinned(
+  scriptId: number,
+  pinned: boolean,
+): Promise<Script> {
+  return invoke("set_script_pinned", { scriptId, pinned });
+}
+
+export async function findMovedScript(scriptId: number): Promise<string[]> {
+  return invoke("find_moved_script", { scriptId });
+}
+
+export async function relocateScript(
+  scriptId: number,
+  path: string,
+): Promise<Script> {
+  return invoke("relocate_script", { scriptId, path });
+}
+
+// Script version commands
+export async function getScriptVersions(
+  scriptId: number,
+): Promise<ScriptVersion[]> {
+  return invoke("get_script_versions", { scriptId });
+}
+
+export async function getScriptVersionContent(
+  versionId: number,
+): Promise<string> {
+  return invoke("get_script_version_content", { versionId });
+}
+
+/** Without `toVersionId` the diff is against the file on disk. */
+export async function diffScriptVersions(
+  fromVersionId: number,
+  toVersionId?: number,
+): Promise<ScriptDiff> {
+  return invoke("diff_script_versions", {
+    fromVersionId,
+    toVersionId: toVersionId ?? null,
+  });
+}
+
+export async function restoreScriptVersion(
+  versionId: number,
+): Promise<ScriptHealth> {
+  return invoke("restore_script_version", { versionId });
+}
+
+// In-app editor commands
+export async function getScriptTemplates(): Promise<ScriptTemplate[]> {
+  return invoke("get_script_templates");
+}
+
+export async function createScriptFromTemplate(
+  script: NewTemplateScript,
+): Promise<Script> {
+  return invoke("create_script_from_template", { script });
+}
+
+export async function readScriptFile(scriptId: number): Promise<string> {
+  return invoke("read_script_file", { scriptId });
+}
+
+export async function saveScriptFile(
+  scriptId: number,
+  content: string,
+): Promise<Script> {
+  return invoke("save_script_file", { scriptId, content });
+}
+
+// Lint commands
+export async function lintScript(
+  scriptId: number,
+  force?: boolean,
+): Promise<LintReport> {
+  return invoke("lint_script", { scriptId, force: force ?? null });
+}
+
+export async function getLintReport(
+  scriptId: number,
+): Promise<LintReport | null> {
+  return invoke("get_lint_report", { scriptId });
+}
+
+/** Pass null to follow the global lint policy. */
+export async function setScriptLintPolicy(
+  scriptId: number,
+  policy: LintPolicy | null,
+): Promise<Script> {
+  return invoke("set_script_lint_policy", { scriptId, policy });
+}
+
+// Secrets commands
+export async function getVaultStatus(): Promise<VaultStatus> {
+  return invoke("get_vault_status");
+}
+
+export async function setupVault(
+  passphrase: string,
+  remember?: boolean,
+): Promise<VaultStatus> {
+  return invoke("setup_vault", { passphrase, remember: remember ?? null });
+}
+
+export async function unlockVault(
+  passphrase: string,
+  remember?: boolean,
+): Promise<VaultStatus> {
+  return invoke("unlock_vault", { passphrase, remember: remember ?? null });
+}
+
+export async function lockVault(): Promise<VaultStatus> {
+  return invoke("lock_vault");
+}
+
+export async function changeVaultPassphrase(
+  current: string,
+  newPassphrase: string,
+): Promise<VaultStatus> {
+  return invoke("change_vault_passphrase", { current, newPassphrase });
+}
+
+export async function resetVault(): Promise<VaultStatus> {
+  return invoke("reset_vault");
+}
+
+export async function getSecrets(): Promise<SecretInfo[]> {
+  return invoke("get_secrets");
+}
+
+export async function setSecret(name: string, value: string): Promise<void> {
+  return invoke("set_secret", { name, value });
+}
+
+export async function deleteSecret(name: string): Promise<void> {
+  return invoke("delete_secret", { name });
+}
+
+export async function getScriptEnv(scriptId: number): Promise<ScriptEnvVar[]> {
+  return invoke("get_script_env", { scriptId });
+}
+
+export async function setScriptEnv(
+  scriptId: number,
+  vars: NewScriptEnvVar[],
+): Promise<ScriptEnvVar[]> {
+  return invoke("set_script_env", { scriptId, vars });
+}
+
+// Redaction commands
+export async function getRedactionRules(
+  scriptId?: number | null,
+): Promise<RedactionRule[]> {
+  return invoke("get_redaction_rules", { scriptId: scriptId ?? null });
+}
+
+export async function addRedactionRule(
+  rule: NewRedactionRule,
+): Promise<RedactionRule> {
+  return invoke("add_redaction_rule", { rule });
+}
+
+export async function updateRedactionRule(
+  id: number,
+  update: UpdateRedactionRule,
+): Promise<RedactionRule> {
+  return invoke("update_redaction_rule", { id, update });
+}
+
+export async function deleteRedactionRule(id: number): Promise<void> {
+  return invoke("delete_redaction_rule", { id });
+}
+
+export async function getRedactionDetectors(): Promise<RedactionDetector[]> {
+  return invoke("get_redaction_detectors");
+}
+
+export async function setRedactionDetector(
+  id: string,
+  enabled: boolean,
+): Promise<void> {
+  return invoke("set_redaction_detector", { id, enabled });
+}
+
+// Elevation commands
+export async function getElevationStatus(): Promise<ElevationStatus> {
+  return invoke("get_elevation_status");
+}
+
+/** Answer an `elevation-prompt`; `null` cancels it. */
+export async function answerElevationPrompt(
+  requestId: number,
+  password: string | null,
+): Promise<void> {
+  return invoke("answer_elevation_prompt", { requestId, password });
+}
+
+// Audit log commands
+export async function getAuditLog(
+  filter?: AuditFilter | null,
+  limit?: number | null,
+  offset?: number | null,
+): Promise<AuditEntry[]> {
+  return invoke("get_audit_log", {
+    filter: filter ?? null,
+    limit: limit ?? null,
+    offset: offset ?? null,
+  });
+}
+
+export async function exportAuditLog(
+  path: string,
+  format: AuditExportFormat,
+  filter?: AuditFilter | null,
+): Promise<number> {
+  return invoke("export_audit_log", { path, format, filter: filter ?? null });
+}
+
+// Protection commands
+export async function getScriptProtection(
+  scriptId: number,
+): Promise<ScriptProtection | null> {
+  return invoke("get_script_protection", { scriptId });
+}
+
+export async function setScriptProtection(
+  scriptId: number,
+  protection: NewScriptProtection | null,
+): Promise<ScriptProtection | null> {
+  return invoke("set_script_protection", { scriptId, protection });
+}
+
+// Resource limit commands
+export async function getScriptLimits(
+  scriptId: number,
+): Promise<ResourceLimits | null> {
+  return invoke("get_script_limits", { scriptId });
+}
+
+export async function setScriptLimits(
+  scriptId: number,
+  limits: NewResourceLimits | null,
+): Promise<ResourceLimits | null> {
+  return invoke("set_script_limits", { scriptId, limits });
+}
+
+export async function getResourceLimitSupport(): Promise<ResourceLimitSupport> {
+  return invoke("get_resource_limit_support");
+}
+
+// Sandbox commands
+export async function getScriptSandbox(
+  scriptId: number,
+): Promise<SandboxConfig | null> {
+  return invoke("get_script_sandbox", { scriptId });
+}
+
+export async function setScriptSandbox(
+  scriptId: number,
+  sandbox: NewSandboxConfig | null,
+): Promise<SandboxConfig | null> {
+  return invoke("set_script_sandbox", { scriptId, sandbox });
+}
+
+export async function getSandboxTools(): Promise<SandboxTools> {
+  return invoke("get_sandbox_tools");
+}
+
 // Category commands
 export async function getCategories(): Promise<Category[]> {
   return invoke("get_categories");
@@ -65,11 +425,13 @@ export async function runScript(
   scriptId: number,
   cols?: number,
   rows?: number,
+  confirmation?: string | null,
 ): Promise<number> {
   return invoke("run_script", {
     scriptId,
     cols: cols ?? null,
     rows: rows ?? null,
+    confirmation: confirmation ?? null,
   });
 }
 
@@ -96,6 +458,18 @@ export async function resizeScriptPty(
   return invoke("resize_script_pty", { scriptId, cols, rows });
 }
 
+export async function previewRun(
+  scriptId: number,
+  cols?: number,
+  rows?: number,
+): Promise<RunPreview> {
+  return invoke("preview_run", {
+    scriptId,
+    cols: cols ?? null,
+    rows: rows ?? null,
+  });
+}
+
 // History commands
 export async function getRunHistory(
   scriptId: number,
@@ -114,6 +488,103 @@ export async function clearHistory(scriptId: number): Promise<void> {
   return invoke("clear_history", { scriptId });
 }
 
+export async function getRecentRuns(
+  filter?: RunSearchFilter | null,
+  limit?: number,
+  offset?: number,
+): Promise<RunListItem[]> {
+  return invoke("get_recent_runs", {
+    filter: filter ?? null,
+    limit: limit ?? null,
+    offset: offset ?? null,
+  });
+}
+
+export async function getScriptStats(
+  since?: string | null,
+): Promise<ScriptStats[]> {
+  return invoke("get_script_stats", { since: since ?? null });
+}
+
+export async function getFailingScripts(): Promise<FailingScript[]> {
+  return invoke("get_failing_scripts");
+}
+
+export async function getCategoryStats(
+  since?: string | null,
+): Promise<CategoryStats[]> {
+  return invoke("get_category_stats", { since: since ?? null });
+}
+
+// Export commands
+export async function exportRunHistory(
+  path: string,
+  format: ExportFormat,
+  filter?: RunSearchFilter | null,
+): Promise<number> {
+  return invoke("export_run_history", { path, format, filter: filter ?? null });
+}
+
+// Library commands
+/** The format follows the extension: .json, .toml, or .zip with script files. */
+export async function exportLibrary(path: string): Promise<number> {
+  return invoke("export_library", { path });
+}
+
+export async function previewLibraryImport(
+  path: string,
+  options?: LibraryImportOptions | null,
+): Promise<LibraryImportPlan> {
+  return invoke("preview_library_import", { path, options: options ?? null });
+}
+
+export async function importLibrary(
+  path: string,
+  options?: LibraryImportOptions | null,
+): Promise<LibraryImportPlan> {
+  return invoke("import_library", { path, options: options ?? null });
+}
+
+// Config file commands
+export async function getConfigStatus(): Promise<ConfigStatus> {
+  return invoke("get_config_status");
+}
+
+/** Pass null to stop using a config directory. */
+export async function setConfigDir(dir: string | null): Promise<ConfigStatus> {
+  return invoke("set_config_dir", { dir });
+}
+
+export async function reloadConfig(): Promise<ConfigStatus> {
+  return invoke("reload_config");
+}
+
+// Script source commands
+export async function getScriptSources(): Promise<ScriptSource[]> {
+  return invoke("get_script_sources");
+}
+
+export async function addScriptSource(
+  source: NewScriptSource,
+): Promise<[ScriptSource, ScanReport]> {
+  return invoke("add_script_source", { source });
+}
+
+export async function updateScriptSource(
+  id: number,
+  update: UpdateScriptSource,
+): Promise<ScriptSource> {
+  return invoke("update_script_source", { id, update });
+}
+
+export async function deleteScriptSource(id: number): Promise<void> {
+  return invoke("delete_script_source", { id });
+}
+
+export async function scanScriptSources(): Promise<ScanReport[]> {
+  return invoke("scan_script_sources");
+}
+
 // Schedule commands
 export async function createSchedule(
   scriptId: number,
@@ -121,6 +592,7 @@ export async function createSchedule(
   time: string | null,
   weekday: number | null,
   intervalSeconds: number | null,
+  confirmation?: string | null,
 ): Promise<Schedule> {
   return invoke("create_schedule", {
     scriptId,
@@ -128,6 +600,7 @@ export async function createSchedule(
     time,
     weekday,
     intervalSeconds,
+    confirmation: confirmation ?? null,
   });
 }
 
@@ -146,6 +619,12 @@ export async function toggleSchedule(scheduleId: number): Promise<boolean> {
   return invoke("toggle_schedule", { scheduleId });
 }
 
+export async function getScheduleUsage(
+  scheduleId: number,
+): Promise<ScheduleUsage | null> {
+  return invoke("get_schedule_usage", { scheduleId });
+}
+
 export async function getSchedules(scriptId: number): Promise<Schedule[]> {
   return invoke("get_schedules", { scriptId });
 }
@@ -154,6 +633,28 @@ export async function syncSchedules(): Promise<void> {
   return invoke("sync_schedules");
 }
 
+export async function previewSchedule(
+  scheduleId: number,
+): Promise<SchedulePreview> {
+  return invoke("preview_schedule", { scheduleId });
+}
+
+export async function previewNewSchedule(
+  scriptId: number,
+  scheduleType: string,
+  time: string | null,
+  weekday: number | null,
+  intervalSeconds: number | null,
+): Promise<SchedulePreview> {
+  return invoke("preview_new_schedule", {
+    scriptId,
+    scheduleType,
+    time,
+    weekday,
+    intervalSeconds,
+  });
+}
+
 // Settings commands
 export async function getSettings(): Promise<Settings> {
   return invoke("get_settings");
@@ -168,3 +669,127 @@ export async function updateSettings(
 export async function openInEditor(scriptPath: string): Promise<void> {
   return invoke("open_in_editor", { scriptPath });
 }
+
+// Notifier commands
+export async function getNotifiers(): Promise<Notifier[]> {
+  return invoke("get_notifiers");
+}
+
+export async function addNotifier(notifier: NewNotifier): Promise<Notifier> {
+  return invoke("add_notifier", { notifier });
+}
+
+export async function updateNotifier(
+  id: number,
+  update: UpdateNotifier,
+): Promise<Notifier> {
+  return invoke("update_notifier", { id, update });
+}
+
+export async function deleteNotifier(id: number): Promise<void> {
+  return invoke("delete_notifier", { id });
+}
+
+export async function testNotifier(id: number): Promise<string> {
+  return invoke("test_notifier", { id });
+}
+
+export async function getNotificationLog(
+  notifierId?: number | null,
+  limit?: number,
+): Promise<NotificationLogEntry[]> {
+  return invoke("get_notification_log", {
+    notifierId: notifierId ?? null,
+    limit: limit ?? null,
+  });
+}
+
+// Output rule commands
+export async function getOutputRules(scriptId: number): Promise<OutputRule[]> {
+  return invoke("get_output_rules", { scriptId });
+}
+
+export async function addOutputRule(rule: NewOutputRule): Promise<OutputRule> {
+  return invoke("add_output_rule", { rule });
+}
+
+export async function updateOutputRule(
+  id: number,
+  update: UpdateOutputRule,
+): Promise<OutputRule> {
+  return invoke("update_output_rule", { id, update });
+}
+
+export async function deleteOutputRule(id: number): Promise<void> {
+  return invoke("delete_output_rule", { id });
+}
+
+export async function getRunMatches(runId: number): Promise<RunMatch[]> {
+  return invoke("get_run_matches", { runId });
+}
+
+// Metric commands
+export async function getRunMetrics(runId: number): Promise<RunMetric[]> {
+  return invoke("get_run_metrics", { runId });
+}
+
+export async function getMetricNames(scriptId: number): Promise<string[]> {
+  return invoke("get_metric_names", { scriptId });
+}
+
+export async function getMetricSeries(
+  scriptId: number,
+  name: string,
+  since?: string | null,
+  limit?: number,
+): Promise<MetricPoint[]> {
+  return invoke("get_metric_series", {
+    scriptId,
+    name,
+    since: since ?? null,
+    limit: limit ?? null,
+  });
+}
+
+// Search commands
+export async function searchRuns(
+  query: string,
+  filter?: RunSearchFilter | null,
+  limit?: number,
+  offset?: number,
+): Promise<RunSearchHit[]> {
+  return invoke("search_runs", {
+    query,
+    filter: filter ?? null,
+    limit: limit ?? null,
+    offset: offset ?? null,
+  });
+}
+
+export async function searchScripts(
+  query: string,
+  limit?: number,
+): Promise<ScriptSearchHit[]> {
+  return invoke("search_scripts", { query, limit: limit ?? null });
+}
+
+// Retention commands
+export async function getScriptRetention(
+  scriptId: number,
+): Promise<ScriptRetention | null> {
+  return invoke("get_script_retention", { scriptId });
+}
+
+export async function setScriptRetention(
+  policy: ScriptRetention,
+): Promise<void> {
+  return invoke("set_script_retention", { policy });
+}
+
+export async function clearScriptRetention(scriptId: number): Promise<void> {
+  return invoke("clear_script_retention", { scriptId });
+}
+
+export async function pruneHistory(dryRun: boolean): Promise<PruneReport> {
+  return invoke("prune_history", { dryRun });
+}
@@ -96,6 +96,7 @@ export const useRunnerStore = create<RunnerState>()((set, get) => ({
       scriptId: number;
       exitCode: number;
       recordId: number;
+      status: RunRecord["status"];
     }>("script-finished", (event) => {
       set((state) => {
         const running = new Map(state.runningScripts);
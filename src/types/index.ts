@@ -9,6 +9,22 @@ export interface Script {
   runAsAdmin: boolean;
   createdAt: string;
   updatedAt: string;
+  /** Declared in conduit.toml/yaml; edit the config file instead. */
+  configManaged: boolean;
+  /** Watched folder this script was discovered in. */
+  sourceId: number | null;
+  /** The discovered file is no longer there. */
+  missing: boolean;
+  /** Runs are terminated after this many seconds. */
+  timeoutSeconds: number | null;
+  /** Refuses to run once the file differs from the approved content. */
+  pinned: boolean;
+  /** null follows the global lint policy. */
+  lintPolicy: LintPolicy | null;
+  /** Runs need the script's confirmation phrase typed. */
+  protected: boolean;
+  /** Runs start inside bubblewrap or firejail. */
+  sandboxed: boolean;
 }
 
 export interface Category {
@@ -25,7 +41,23 @@ export interface RunRecord {
   finishedAt: string | null;
   exitCode: number | null;
   output: string | null;
-  status: "running" | "success" | "error" | "cancelled";
+  status: "running" | "success" | "warning" | "error" | "cancelled";
+  metadata: Record<string, string | number> | null;
+  /** Snapshot of the script content this run executed. */
+  versionId: number | null;
+  /** Secrets and sensitive matches replaced in this run's output. */
+  redactions: number;
+  /** How an admin run was elevated, e.g. "sudo-askpass" or "pkexec". */
+  elevation: string | null;
+  /** Set when elevation failed and the script never ran. */
+  elevationError: string | null;
+  /** Largest resident set of any process in the run. */
+  peakRssKb: number | null;
+  userCpuMs: number | null;
+  systemCpuMs: number | null;
+  wallMs: number | null;
+  /** The sandbox the run started in. */
+  sandbox: SandboxProfile | null;
 }
 
 export interface Schedule {
@@ -56,6 +88,8 @@ export interface UpdateScript {
   categoryId: number | null;
   color: string | null;
   runAsAdmin: boolean | null;
+  /** 0 clears the timeout. */
+  timeoutSeconds?: number | null;
 }
 
 export interface NewSchedule {
@@ -70,15 +104,710 @@ export type Theme = "win98" | "macos8" | "xp" | "vista";
 
 export type PowerShellVersion = "ps5" | "ps7";
 
+export type SmtpSecurity = "none" | "starttls" | "tls";
+
+/** "warn" lints before each run; "block" also refuses to run on errors. */
+export type LintPolicy = "off" | "warn" | "block";
+
+/** "terminal" keeps sudo prompting inside the run's terminal. */
+export type ElevationMethod = "auto" | "sudo" | "pkexec" | "terminal";
+
 export interface Settings {
   id: number;
   editorPath: string;
   theme: Theme;
   powershellVersion: PowerShellVersion;
+  smtpHost: string;
+  smtpPort: number;
+  smtpSecurity: SmtpSecurity;
+  smtpUsername: string;
+  smtpPasswordSet: boolean;
+  smtpFrom: string;
+  digestTime: string;
+  retentionKeepRuns: number;
+  retentionKeepDays: number;
+  retentionFailureDays: number;
+  logRetentionDays: number;
+  configDir: string;
+  lintPolicy: LintPolicy;
+  elevationMethod: ElevationMethod;
 }
 
 export interface UpdateSettings {
   editorPath: string | null;
   theme: Theme | null;
   powershellVersion: PowerShellVersion | null;
+  smtpHost?: string | null;
+  smtpPort?: number | null;
+  smtpSecurity?: SmtpSecurity | null;
+  smtpUsername?: string | null;
+  smtpPassword?: string | null;
+  smtpFrom?: string | null;
+  digestTime?: string | null;
+  retentionKeepRuns?: number | null;
+  retentionKeepDays?: number | null;
+  retentionFailureDays?: number | null;
+  logRetentionDays?: number | null;
+  lintPolicy?: LintPolicy | null;
+  elevationMethod?: ElevationMethod | null;
+}
+
+export type NotifierKind = "webhook" | "command" | "email";
+
+export type NotifierEvent =
+  | "start"
+  | "finish"
+  | "failure"
+  | "match"
+  | "digest";
+
+export interface Notifier {
+  id: number;
+  name: string;
+  kind: NotifierKind;
+  target: string;
+  events: NotifierEvent[];
+  scriptId: number | null;
+  template: string | null;
+  headers: string | null;
+  maxRetries: number;
+  enabled: boolean;
+  createdAt: string;
+}
+
+export interface NewNotifier {
+  name: string;
+  kind: NotifierKind;
+  target: string;
+  events: NotifierEvent[];
+  scriptId: number | null;
+  template: string | null;
+  headers: string | null;
+  maxRetries: number | null;
+}
+
+export interface UpdateNotifier {
+  name: string | null;
+  target: string | null;
+  events: NotifierEvent[] | null;
+  template: string | null;
+  headers: string | null;
+  maxRetries: number | null;
+  enabled: boolean | null;
+}
+
+export interface NotificationLogEntry {
+  id: number;
+  notifierId: number;
+  runId: number | null;
+  event: NotifierEvent | "test";
+  attempts: number;
+  status: "delivered" | "failed";
+  response: string | null;
+  createdAt: string;
+}
+
+export type RuleSeverity = "info" | "warning" | "error";
+
+export interface OutputRule {
+  id: number;
+  scriptId: number;
+  name: string;
+  pattern: string;
+  severity: RuleSeverity;
+  notify: boolean;
+  enabled: boolean;
+  createdAt: string;
+}
+
+export interface NewOutputRule {
+  scriptId: number;
+  name: string;
+  pattern: string;
+  severity: RuleSeverity;
+  notify: boolean;
+}
+
+export interface UpdateOutputRule {
+  name: string | null;
+  pattern: string | null;
+  severity: RuleSeverity | null;
+  notify: boolean | null;
+  enabled: boolean | null;
+}
+
+export interface RunMatch {
+  id: number;
+  runId: number;
+  ruleId: number | null;
+  ruleName: string;
+  severity: RuleSeverity;
+  lineNumber: number;
+  line: string;
+  captures: Record<string, string | number> | null;
+}
+
+export interface RunMetric {
+  id: number;
+  runId: number;
+  name: string;
+  value: number;
+  recordedAt: string;
+}
+
+export interface MetricPoint {
+  runId: number;
+  startedAt: string;
+  status: RunRecord["status"];
+  value: number;
+}
+
+export interface RunSearchFilter {
+  scriptId?: number | null;
+  categoryId?: number | null;
+  status?: RunRecord["status"] | null;
+  since?: string | null;
+  before?: string | null;
+}
+
+/** Matched terms in `snippet` are wrapped in \u0002 ... \u0003. */
+export interface RunSearchHit {
+  runId: number;
+  scriptId: number;
+  scriptName: string;
+  startedAt: string;
+  status: RunRecord["status"];
+  exitCode: number | null;
+  snippet: string;
+}
+
+export interface ScriptSearchHit {
+  scriptId: number;
+  name: string;
+  description: string | null;
+}
+
+/** Per-script override; null inherits the global setting, 0 is unlimited. */
+export interface ScriptRetention {
+  scriptId: number;
+  keepRuns: number | null;
+  keepDays: number | null;
+  keepFailuresDays: number | null;
+}
+
+export interface PrunedScript {
+  scriptId: number;
+  scriptName: string;
+  runs: number;
+}
+
+export interface PruneReport {
+  dryRun: boolean;
+  totalRuns: number;
+  scripts: PrunedScript[];
+  logFiles: string[];
+  logBytes: number;
+}
+
+export interface RunListItem {
+  id: number;
+  scriptId: number;
+  scriptName: string;
+  categoryId: number;
+  startedAt: string;
+  finishedAt: string | null;
+  exitCode: number | null;
+  status: RunRecord["status"];
+  durationSeconds: number | null;
+  peakRssKb: number | null;
+  /** User plus system CPU time. */
+  cpuMs: number | null;
+}
+
+/** Warnings count as successes; cancelled runs are excluded from the rate. */
+export interface ScriptStats {
+  scriptId: number;
+  scriptName: string;
+  categoryId: number;
+  totalRuns: number;
+  successes: number;
+  failures: number;
+  successRate: number | null;
+  avgDurationSeconds: number | null;
+  p95DurationSeconds: number | null;
+  lastRunAt: string | null;
+  lastStatus: RunRecord["status"] | null;
+  /** Over runs with recorded usage. */
+  avgCpuMs: number | null;
+  maxPeakRssKb: number | null;
+}
+
+export interface FailingScript {
+  scriptId: number;
+  scriptName: string;
+  categoryId: number;
+  lastRunId: number;
+  failedAt: string;
+  exitCode: number | null;
+  consecutiveFailures: number;
+}
+
+export interface CategoryStats {
+  categoryId: number;
+  categoryName: string;
+  scriptCount: number;
+  totalRuns: number;
+  successes: number;
+  failures: number;
+  successRate: number | null;
+  avgDurationSeconds: number | null;
+}
+
+export type ExportFormat = "csv" | "jsonl" | "junit";
+
+export interface LibraryImportOptions {
+  scriptsDir?: string | null;
+  onConflict?: "rename" | "overwrite" | "skip" | null;
+  applySettings?: boolean;
+  skipSchedules?: boolean;
+}
+
+export interface PlannedCategory {
+  name: string;
+  action: "create" | "existing";
+}
+
+export interface PlannedScript {
+  name: string;
+  category: string;
+  sourcePath: string;
+  targetPath: string;
+  action: "create" | "update" | "skip";
+  fileAction: "write" | "overwrite" | "identical" | "keep" | null;
+  runAsAdmin: boolean;
+  schedules: number;
+  /** The schedules that would be installed, e.g. "daily 08:00". */
+  scheduleSpecs: string[];
+  warnings: string[];
+}
+
+export interface LibraryImportPlan {
+  version: number;
+  categories: PlannedCategory[];
+  scripts: PlannedScript[];
+  settingsChanges: string[];
+  applied: boolean;
+}
+
+export interface ScriptParam {
+  id: number;
+  scriptId: number;
+  name: string;
+  description: string | null;
+  defaultValue: string | null;
+  required: boolean;
+}
+
+export interface ConfigError {
+  file: string;
+  line: number | null;
+  message: string;
+}
+
+export interface ConfigStatus {
+  path: string | null;
+  loadedAt: string | null;
+  errors: ConfigError[];
+  warnings: string[];
+  managedScripts: number;
+}
+
+export interface ScriptSource {
+  id: number;
+  path: string;
+  includeGlobs: string[];
+  excludeGlobs: string[];
+  categoryId: number | null;
+  enabled: boolean;
+  lastScannedAt: string | null;
+  createdAt: string;
+}
+
+export interface NewScriptSource {
+  path: string;
+  includeGlobs?: string[];
+  excludeGlobs?: string[];
+  categoryId: number | null;
+}
+
+export interface UpdateScriptSource {
+  includeGlobs: string[] | null;
+  excludeGlobs: string[] | null;
+  /** 0 clears the category. */
+  categoryId: number | null;
+  enabled: boolean | null;
+}
+
+export interface ScanReport {
+  sourceId: number;
+  added: number;
+  updated: number;
+  missing: number;
+  errors: string[];
+}
+
+export interface HeaderParam {
+  name: string;
+  description: string | null;
+  defaultValue: string | null;
+  required: boolean;
+}
+
+export interface HeaderSchedule {
+  scheduleType: "daily" | "weekly" | "interval";
+  time: string | null;
+  weekday: number | null;
+  intervalSeconds: number | null;
+}
+
+/** Metadata declared with `@tag` lines in a script's top comment block. */
+export interface ScriptHeader {
+  name: string | null;
+  description: string | null;
+  category: string | null;
+  params: HeaderParam[];
+  schedules: HeaderSchedule[];
+  timeoutSeconds: number | null;
+  warnings: string[];
+}
+
+export interface HeaderDrift {
+  field: "name" | "description" | "category" | "timeout" | "params" | "schedule";
+  headerValue: string;
+  dbValue: string;
+}
+
+export interface ScriptHeaderReport {
+  header: ScriptHeader;
+  drift: HeaderDrift[];
+}
+
+export type ScriptFileStatus = "ok" | "modified" | "missing" | "unreadable";
+
+/** Last known state of a script's file on disk. */
+export interface ScriptHealth {
+  scriptId: number;
+  status: ScriptFileStatus;
+  size: number | null;
+  modifiedAt: string | null;
+  /** Unix permission bits; null on Windows. */
+  mode: number | null;
+  hash: string | null;
+  approvedHash: string | null;
+  checkedAt: string;
+}
+
+/** A distinct revision of a script's content, captured when it ran. */
+export interface ScriptVersion {
+  id: number;
+  scriptId: number;
+  hash: string;
+  size: number;
+  createdAt: string;
+  runCount: number;
+  lastRunAt: string | null;
+  lastStatus: RunRecord["status"] | null;
+}
+
+export interface ScriptDiff {
+  unified: string;
+  additions: number;
+  deletions: number;
+}
+
+export type ScriptTemplateId = "bash" | "python" | "powershell" | "node";
+
+export interface ScriptTemplate {
+  id: ScriptTemplateId;
+  label: string;
+  extension: string;
+}
+
+export interface NewTemplateScript {
+  name: string;
+  template: ScriptTemplateId;
+  description: string | null;
+  categoryId: number;
+  color?: string | null;
+}
+
+export interface LintDiagnostic {
+  tool: string;
+  line: number;
+  column: number;
+  endLine: number | null;
+  endColumn: number | null;
+  level: "error" | "warning" | "info" | "style";
+  /** Rule id, e.g. "SC2086" or "PSAvoidUsingWriteHost". */
+  code: string;
+  message: string;
+}
+
+export interface LintReport {
+  scriptId: number;
+  tool: "shellcheck" | "PSScriptAnalyzer" | null;
+  status: "ok" | "issues" | "unsupported" | "unavailable" | "failed";
+  hash: string | null;
+  message: string | null;
+  lintedAt: string;
+  errors: number;
+  warnings: number;
+  diagnostics: LintDiagnostic[];
+}
+
+export interface PreviewEnvVar {
+  name: string;
+  value: string;
+  /** Set by Conduit rather than inherited from the app's environment. */
+  overridden: boolean;
+  /** Comes from the secrets vault; `value` is masked. */
+  secret: boolean;
+}
+
+/** What a run would spawn, resolved without running anything. */
+export interface RunPreview {
+  scriptId: number;
+  argv: string[];
+  env: PreviewEnvVar[];
+  cwd: string;
+  cols: number;
+  rows: number;
+  runAsAdmin: boolean;
+  /** The elevation method an admin run would use. */
+  elevation: string | null;
+  sandbox: SandboxProfile | null;
+  timeoutSeconds: number | null;
+  lintPolicy: LintPolicy;
+  /** Why a real run would be refused right now. */
+  blocked: string | null;
+}
+
+export interface PreviewFile {
+  path: string;
+  content: string;
+}
+
+/** Unit/plist files a schedule writes and the commands that install it. */
+export interface SchedulePreview {
+  label: string;
+  files: PreviewFile[];
+  commands: string[][];
+}
+
+export interface VaultStatus {
+  initialized: boolean;
+  unlocked: boolean;
+  /** The vault key is kept in the OS keyring, so it unlocks on its own. */
+  remembered: boolean;
+  secretCount: number;
+}
+
+/** A stored secret; its value is never sent to the frontend. */
+export interface SecretInfo {
+  name: string;
+  createdAt: string;
+  updatedAt: string;
+  /** Scripts whose environment references this secret. */
+  usedBy: number;
+}
+
+/** Either a literal `value` or the name of a `secret`. */
+export interface ScriptEnvVar {
+  id: number;
+  scriptId: number;
+  name: string;
+  value: string | null;
+  secret: string | null;
+}
+
+export interface NewScriptEnvVar {
+  name: string;
+  value?: string | null;
+  secret?: string | null;
+}
+
+/** A regex redacted from output; with a capture group only the group is replaced. */
+export interface RedactionRule {
+  id: number;
+  /** `null` applies the rule to every script. */
+  scriptId: number | null;
+  name: string;
+  pattern: string;
+  enabled: boolean;
+  createdAt: string;
+}
+
+export interface NewRedactionRule {
+  scriptId?: number | null;
+  name: string;
+  pattern: string;
+}
+
+export interface UpdateRedactionRule {
+  name?: string;
+  pattern?: string;
+  enabled?: boolean;
+}
+
+/** A built-in pattern for a common credential format. */
+export interface RedactionDetector {
+  id: string;
+  label: string;
+  pattern: string;
+  enabled: boolean;
+}
+
+/** What this machine offers for running admin scripts. */
+export interface ElevationStatus {
+  sudo: boolean;
+  /** `sudo -n` succeeds without a password. */
+  passwordlessSudo: boolean;
+  pkexec: boolean;
+  method: ElevationMethod;
+  /** What an admin run would use right now. */
+  effective: string | null;
+  /** Why an admin run can't elevate with the configured method. */
+  error: string | null;
+}
+
+/** Payload of the `elevation-prompt` event; answer with `answerElevationPrompt`. */
+export interface ElevationPrompt {
+  requestId: number;
+  scriptId: number;
+  prompt: string;
+}
+
+/** One append-only audit record, e.g. action "script.update" on a script. */
+export interface AuditEntry {
+  id: number;
+  createdAt: string;
+  /** OS account that performed the action. */
+  actor: string;
+  action: string;
+  targetType: string;
+  targetId: number | null;
+  targetName: string | null;
+  before: unknown | null;
+  after: unknown | null;
+}
+
+export interface AuditFilter {
+  action?: string | null;
+  targetType?: string | null;
+  targetId?: number | null;
+  actor?: string | null;
+  since?: string | null;
+  before?: string | null;
+}
+
+export type AuditExportFormat = "csv" | "jsonl";
+
+/** Guards on running a script. Times are local "HH:MM". */
+export interface ScriptProtection {
+  scriptId: number;
+  confirmPhrase: string;
+  cooldownSeconds: number | null;
+  allowedFrom: string | null;
+  /** Exclusive; earlier than allowedFrom for overnight windows. */
+  allowedUntil: string | null;
+  lastRunAt: string | null;
+}
+
+export interface NewScriptProtection {
+  /** Defaults to the script's name. */
+  confirmPhrase?: string | null;
+  cooldownSeconds?: number | null;
+  allowedFrom?: string | null;
+  allowedUntil?: string | null;
+}
+
+/** Per-script resource limits for runs on Unix; null leaves the inherited value. */
+export interface ResourceLimits {
+  scriptId: number;
+  /** The run is killed after this much CPU time. */
+  cpuSeconds: number | null;
+  addressSpaceMb: number | null;
+  openFiles: number | null;
+  /** 0 to 19. */
+  nice: number | null;
+  /** Linux only. */
+  ioClass: IoClass | null;
+  /** 0 (highest) to 7, best-effort class only. */
+  ioPriority: number | null;
+  /** cgroup MemoryMax; needs systemd and cgroup v2. */
+  memoryMaxMb: number | null;
+  /** cgroup CPUQuota, where 100 is one full CPU. */
+  cpuQuotaPercent: number | null;
+}
+
+export type IoClass = "best-effort" | "idle";
+
+export interface NewResourceLimits {
+  cpuSeconds?: number | null;
+  addressSpaceMb?: number | null;
+  openFiles?: number | null;
+  nice?: number | null;
+  ioClass?: IoClass | null;
+  ioPriority?: number | null;
+  memoryMaxMb?: number | null;
+  cpuQuotaPercent?: number | null;
+}
+
+export interface ResourceLimitSupport {
+  /** memoryMaxMb and cpuQuotaPercent can be enforced. */
+  cgroups: boolean;
+  ioPriority: boolean;
+}
+
+/** Usage of a schedule's most recent run, as accounted by systemd. */
+export interface ScheduleUsage {
+  scheduleId: number;
+  startedAt: string | null;
+  exitCode: number | null;
+  /** User plus system CPU time. */
+  cpuMs: number | null;
+  peakMemoryKb: number | null;
+  wallMs: number | null;
+}
+
+export type SandboxTool = "auto" | "bwrap" | "firejail";
+
+/** Linux only; "auto" prefers bubblewrap over firejail. */
+export interface SandboxConfig {
+  scriptId: number;
+  tool: SandboxTool;
+  /** Absolute paths left writable over the read-only root. */
+  writePaths: string[];
+  allowNetwork: boolean;
+}
+
+export interface NewSandboxConfig {
+  tool?: SandboxTool | null;
+  writePaths?: string[];
+  /** Defaults to true. */
+  allowNetwork?: boolean | null;
+}
+
+/** The sandbox a run actually started in. */
+export interface SandboxProfile {
+  tool: "bwrap" | "firejail";
+  readOnlyRoot: boolean;
+  writePaths: string[];
+  network: boolean;
+}
+
+/** Which sandbox tools are installed. */
+export interface SandboxTools {
+  bwrap: boolean;
+  firejail: boolean;
 }
@@ -21,6 +21,7 @@ export default {
           error: "var(--status-error)",
           running: "var(--status-running)",
           scheduled: "var(--status-scheduled)",
+          warning: "var(--status-warning)",
         },
         win: {
           white: "var(--win-white)",